@@ -0,0 +1,144 @@
+//! Vault-wide liability totals, surplus computation and solvency checks.
+//!
+//! **PRs that only change how vault liabilities are tracked should edit this file only.**
+//!
+//! The vault owes two kinds of funds: subscriber `prepaid_balance`s and unpaid
+//! merchant earnings. Both are tracked as running totals so that the surplus
+//! (token balance minus liabilities) can be computed in O(1), without
+//! iterating over subscriptions or merchants.
+
+use crate::admin::get_token;
+use crate::roles::require_role;
+use crate::types::{DataKey, Error, Role, SolvencyReport};
+use soroban_sdk::{Address, Env, Symbol};
+
+fn adjust(env: &Env, key: &DataKey, delta: i128) -> Result<(), Error> {
+    let total: i128 = env.storage().instance().get(key).unwrap_or(0);
+    let total = total.checked_add(delta).ok_or(Error::Overflow)?;
+    env.storage().instance().set(key, &total);
+    Ok(())
+}
+
+/// Sum of all subscriber `prepaid_balance`s.
+pub fn get_total_prepaid(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::TotalPrepaid)
+        .unwrap_or(0)
+}
+
+/// Sum of all merchant earnings not yet withdrawn.
+pub fn get_total_merchant_balance(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::TotalMerchantBalance)
+        .unwrap_or(0)
+}
+
+/// Apply a signed change to the total prepaid liability.
+pub fn adjust_total_prepaid(env: &Env, delta: i128) -> Result<(), Error> {
+    adjust(env, &DataKey::TotalPrepaid, delta)
+}
+
+/// Apply a signed change to the total merchant earnings liability.
+pub fn adjust_total_merchant_balance(env: &Env, delta: i128) -> Result<(), Error> {
+    adjust(env, &DataKey::TotalMerchantBalance, delta)
+}
+
+/// Everything the vault owes: prepaid balances plus unpaid merchant earnings.
+pub fn total_liabilities(env: &Env) -> Result<i128, Error> {
+    get_total_prepaid(env)
+        .checked_add(get_total_merchant_balance(env))
+        .ok_or(Error::Overflow)
+}
+
+fn vault_token_balance(env: &Env) -> Result<i128, Error> {
+    let token_client = soroban_sdk::token::Client::new(env, &get_token(env)?);
+    Ok(token_client.balance(&env.current_contract_address()))
+}
+
+/// Token balance held by the vault that is not owed to anyone.
+///
+/// Returns 0 when the vault holds less than its liabilities.
+pub fn recoverable_surplus(env: &Env) -> Result<i128, Error> {
+    Ok(vault_token_balance(env)?
+        .checked_sub(total_liabilities(env)?)
+        .unwrap_or(0)
+        .max(0))
+}
+
+pub fn is_protective_mode(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get(&DataKey::ProtectiveMode)
+        .unwrap_or(false)
+}
+
+/// Fails with `ProtectiveMode` while the vault is under protection.
+///
+/// Guarded operations are those that move tokens out of custody: subscriber
+/// refunds, merchant withdrawals and stranded fund recovery. Deposits stay open:
+/// they add reserves and liabilities in equal measure, so they never deepen a
+/// shortfall, and subscribers can keep their subscriptions funded.
+pub fn require_not_protective(env: &Env) -> Result<(), Error> {
+    if is_protective_mode(env) {
+        return Err(Error::ProtectiveMode);
+    }
+    Ok(())
+}
+
+/// Compare token reserves with recorded liabilities.
+pub fn get_solvency_report(env: &Env) -> Result<SolvencyReport, Error> {
+    let token_balance = vault_token_balance(env)?;
+    let total_prepaid = get_total_prepaid(env);
+    let total_merchant_balance = get_total_merchant_balance(env);
+    let total_liabilities = total_liabilities(env)?;
+    let surplus = token_balance
+        .checked_sub(total_liabilities)
+        .ok_or(Error::Overflow)?;
+
+    Ok(SolvencyReport {
+        token_balance,
+        total_prepaid,
+        total_merchant_balance,
+        total_liabilities,
+        surplus,
+        is_solvent: surplus >= 0,
+        protective_mode: is_protective_mode(env),
+    })
+}
+
+/// Permissionless reserve check.
+///
+/// If the token balance has fallen below liabilities (e.g. after an issuer
+/// clawback), the vault enters protective mode and emits `protective`.
+/// Anyone may call this; it never clears protective mode.
+pub fn do_reconcile(env: &Env) -> Result<SolvencyReport, Error> {
+    let mut report = get_solvency_report(env)?;
+    if !report.is_solvent && !report.protective_mode {
+        env.storage()
+            .instance()
+            .set(&DataKey::ProtectiveMode, &true);
+        report.protective_mode = true;
+        env.events()
+            .publish((Symbol::new(env, "protective"),), report.clone());
+    }
+    Ok(report)
+}
+
+/// A pauser lifts protective mode once reserves cover liabilities again.
+pub fn do_exit_protective_mode(env: &Env, admin: Address) -> Result<(), Error> {
+    require_role(env, Role::Pauser, &admin)?;
+
+    let report = get_solvency_report(env)?;
+    if !report.is_solvent {
+        return Err(Error::VaultInsolvent);
+    }
+
+    env.storage()
+        .instance()
+        .set(&DataKey::ProtectiveMode, &false);
+    env.events()
+        .publish((Symbol::new(env, "protective_exit"),), report);
+    Ok(())
+}
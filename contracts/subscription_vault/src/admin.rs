@@ -1,21 +1,46 @@
-//! Admin and config: init, min_topup, batch_charge.
+//! Admin and config: init, min_topup, batch_charge, admin rotation and handover, recovery.
 //!
 //! **PRs that only change admin or batch behavior should edit this file only.**
 
+use crate::accounting::{recoverable_surplus, require_not_protective};
 use crate::charge_core::charge_one;
-use crate::types::{BatchChargeResult, Error, RecoveryEvent, RecoveryReason};
+use crate::governance::require_no_council;
+use crate::roles::require_role;
+use crate::storage::bump_instance;
+use crate::types::{
+    BatchChargeResult, DataKey, Error, PendingAdmin, RecoveryEvent, RecoveryReason, Role,
+    SubscriptionLimits, ADMIN_PROPOSAL_TTL_SECONDS,
+};
 use soroban_sdk::{Address, Env, Symbol, Vec};
 
+pub fn is_initialized(env: &Env) -> bool {
+    env.storage().instance().has(&DataKey::Admin)
+}
+
+/// Fails with `NotInitialized` until `init` has run.
+///
+/// Every entrypoint passes through here, so it also keeps the contract
+/// instance alive.
+pub fn require_initialized(env: &Env) -> Result<(), Error> {
+    if !is_initialized(env) {
+        return Err(Error::NotInitialized);
+    }
+    bump_instance(env);
+    Ok(())
+}
+
 pub fn do_init(env: &Env, token: Address, admin: Address, min_topup: i128) -> Result<(), Error> {
+    if is_initialized(env) {
+        return Err(Error::AlreadyInitialized);
+    }
     env.storage()
         .instance()
         .set(&Symbol::new(env, "token"), &token);
-    env.storage()
-        .instance()
-        .set(&Symbol::new(env, "admin"), &admin);
+    set_admin(env, &admin);
     env.storage()
         .instance()
         .set(&Symbol::new(env, "min_topup"), &min_topup);
+    bump_instance(env);
     env.events().publish(
         (Symbol::new(env, "initialized"),),
         (token, admin, min_topup),
@@ -26,16 +51,22 @@ pub fn do_init(env: &Env, token: Address, admin: Address, min_topup: i128) -> Re
 pub fn require_admin(env: &Env) -> Result<Address, Error> {
     env.storage()
         .instance()
-        .get(&Symbol::new(env, "admin"))
+        .get(&DataKey::Admin)
         .ok_or(Error::Unauthorized)
 }
 
+fn set_admin(env: &Env, admin: &Address) {
+    env.storage().instance().set(&DataKey::Admin, admin);
+}
+
 pub fn do_set_min_topup(env: &Env, admin: Address, min_topup: i128) -> Result<(), Error> {
-    admin.require_auth();
-    let stored = require_admin(env)?;
-    if admin != stored {
-        return Err(Error::Unauthorized);
-    }
+    require_role(env, Role::ConfigManager, &admin)?;
+    require_no_council(env)?;
+    apply_min_topup(env, min_topup)
+}
+
+/// Store a new `min_topup`. Callers must have authorized the change.
+pub fn apply_min_topup(env: &Env, min_topup: i128) -> Result<(), Error> {
     env.storage()
         .instance()
         .set(&Symbol::new(env, "min_topup"), &min_topup);
@@ -44,6 +75,44 @@ pub fn do_set_min_topup(env: &Env, admin: Address, min_topup: i128) -> Result<()
     Ok(())
 }
 
+pub fn get_subscription_limits(env: &Env) -> SubscriptionLimits {
+    env.storage()
+        .instance()
+        .get(&DataKey::SubscriptionLimits)
+        .unwrap_or(SubscriptionLimits::default_limits())
+}
+
+/// Config manager updates the global bounds checked by `create_subscription`.
+pub fn do_set_subscription_limits(
+    env: &Env,
+    admin: Address,
+    limits: SubscriptionLimits,
+) -> Result<(), Error> {
+    require_role(env, Role::ConfigManager, &admin)?;
+    if limits.min_interval_seconds == 0
+        || (limits.max_interval_seconds != 0
+            && limits.max_interval_seconds < limits.min_interval_seconds)
+    {
+        return Err(Error::InvalidInterval);
+    }
+    if limits.max_amount < 0 {
+        return Err(Error::InvalidAmount);
+    }
+    env.storage()
+        .instance()
+        .set(&DataKey::SubscriptionLimits, &limits);
+    env.events()
+        .publish((Symbol::new(env, "limits_updated"),), limits);
+    Ok(())
+}
+
+pub fn get_token(env: &Env) -> Result<Address, Error> {
+    env.storage()
+        .instance()
+        .get(&Symbol::new(env, "token"))
+        .ok_or(Error::NotFound)
+}
+
 pub fn get_min_topup(env: &Env) -> Result<i128, Error> {
     env.storage()
         .instance()
@@ -53,10 +122,10 @@ pub fn get_min_topup(env: &Env) -> Result<i128, Error> {
 
 pub fn do_batch_charge(
     env: &Env,
+    operator: Address,
     subscription_ids: &Vec<u32>,
 ) -> Result<Vec<BatchChargeResult>, Error> {
-    let auth_admin = require_admin(env)?;
-    auth_admin.require_auth();
+    require_role(env, Role::BillingOperator, &operator)?;
 
     let mut results = Vec::new(env);
     for id in subscription_ids.iter() {
@@ -79,32 +148,113 @@ pub fn do_batch_charge(
 pub fn do_get_admin(env: &Env) -> Result<Address, Error> {
     env.storage()
         .instance()
-        .get(&Symbol::new(env, "admin"))
+        .get(&DataKey::Admin)
         .ok_or(Error::NotFound)
 }
 
 pub fn do_rotate_admin(env: &Env, current_admin: Address, new_admin: Address) -> Result<(), Error> {
-    current_admin.require_auth();
+    require_role(env, Role::Admin, &current_admin)?;
+    require_no_council(env)?;
+
+    apply_rotate_admin(env, current_admin, new_admin);
+    Ok(())
+}
+
+/// Replace the stored admin. Callers must have authorized the change.
+pub fn apply_rotate_admin(env: &Env, current_admin: Address, new_admin: Address) {
+    set_admin(env, &new_admin);
+    clear_pending_admin(env);
+
+    env.events().publish(
+        (Symbol::new(env, "admin_rotation"), current_admin.clone()),
+        (current_admin, new_admin, env.ledger().timestamp()),
+    );
+}
+
+/// Clears any pending proposal; a rotation or a new council supersedes it.
+pub fn clear_pending_admin(env: &Env) {
+    env.storage().instance().remove(&DataKey::PendingAdmin);
+}
+
+pub fn get_pending_admin(env: &Env) -> Option<PendingAdmin> {
+    env.storage().instance().get(&DataKey::PendingAdmin)
+}
 
-    let stored_admin: Address = env
-        .storage()
+/// Step one of a two-step rotation: the admin nominates `new_admin`.
+///
+/// Replaces any earlier proposal. The nominee has
+/// [`ADMIN_PROPOSAL_TTL_SECONDS`] to call `accept_admin`.
+pub fn do_propose_admin(
+    env: &Env,
+    current_admin: Address,
+    new_admin: Address,
+) -> Result<PendingAdmin, Error> {
+    require_role(env, Role::Admin, &current_admin)?;
+    require_no_council(env)?;
+
+    let expires_at = env
+        .ledger()
+        .timestamp()
+        .checked_add(ADMIN_PROPOSAL_TTL_SECONDS)
+        .ok_or(Error::Overflow)?;
+    let pending = PendingAdmin {
+        proposer: current_admin,
+        proposed_admin: new_admin.clone(),
+        expires_at,
+    };
+    env.storage()
         .instance()
-        .get(&Symbol::new(env, "admin"))
-        .ok_or(Error::NotFound)?;
+        .set(&DataKey::PendingAdmin, &pending);
+
+    env.events().publish(
+        (Symbol::new(env, "admin_proposed"), new_admin),
+        pending.clone(),
+    );
+    Ok(pending)
+}
 
-    if current_admin != stored_admin {
+/// Step two: the nominee authorizes and takes over as admin.
+///
+/// Fails with `GovernanceRequired` once a council exists; handovers then go
+/// through a council proposal.
+pub fn do_accept_admin(env: &Env, new_admin: Address) -> Result<(), Error> {
+    new_admin.require_auth();
+    require_no_council(env)?;
+
+    let pending = get_pending_admin(env).ok_or(Error::NoPendingAdmin)?;
+    if pending.proposed_admin != new_admin {
         return Err(Error::Unauthorized);
     }
+    let now = env.ledger().timestamp();
+    if now > pending.expires_at {
+        return Err(Error::AdminProposalExpired);
+    }
 
-    env.storage()
-        .instance()
-        .set(&Symbol::new(env, "admin"), &new_admin);
+    let previous_admin = do_get_admin(env)?;
+    set_admin(env, &new_admin);
+    clear_pending_admin(env);
 
     env.events().publish(
-        (Symbol::new(env, "admin_rotation"), current_admin.clone()),
-        (current_admin, new_admin, env.ledger().timestamp()),
+        (Symbol::new(env, "admin_accepted"), new_admin.clone()),
+        (previous_admin, new_admin, now),
     );
+    Ok(())
+}
 
+/// The admin withdraws a pending proposal before it is accepted.
+pub fn do_cancel_admin_proposal(env: &Env, current_admin: Address) -> Result<(), Error> {
+    require_role(env, Role::Admin, &current_admin)?;
+
+    let pending = get_pending_admin(env).ok_or(Error::NoPendingAdmin)?;
+    clear_pending_admin(env);
+
+    env.events().publish(
+        (
+            Symbol::new(env, "admin_proposal_cancelled"),
+            pending.proposed_admin.clone(),
+        ),
+        pending,
+    );
     Ok(())
 }
 
@@ -115,22 +265,34 @@ pub fn do_recover_stranded_funds(
     amount: i128,
     reason: RecoveryReason,
 ) -> Result<(), Error> {
-    admin.require_auth();
-
-    let stored_admin: Address = env
-        .storage()
-        .instance()
-        .get(&Symbol::new(env, "admin"))
-        .ok_or(Error::NotFound)?;
-
-    if admin != stored_admin {
-        return Err(Error::Unauthorized);
-    }
+    require_role(env, Role::Recoverer, &admin)?;
+    require_no_council(env)?;
+    apply_recovery(env, admin, recipient, amount, reason)
+}
 
+/// Transfer `amount` of surplus to `recipient`. `admin` is the authorizer
+/// recorded in the [`RecoveryEvent`]; callers must have authorized it.
+pub fn apply_recovery(
+    env: &Env,
+    admin: Address,
+    recipient: Address,
+    amount: i128,
+    reason: RecoveryReason,
+) -> Result<(), Error> {
     if amount <= 0 {
         return Err(Error::InvalidRecoveryAmount);
     }
 
+    require_not_protective(env)?;
+
+    // Only funds not owed to subscribers or merchants may leave the vault.
+    if amount > recoverable_surplus(env)? {
+        return Err(Error::RecoveryExceedsSurplus);
+    }
+
+    let token_client = soroban_sdk::token::Client::new(env, &get_token(env)?);
+    token_client.transfer(&env.current_contract_address(), &recipient, &amount);
+
     let recovery_event = RecoveryEvent {
         admin: admin.clone(),
         recipient: recipient.clone(),
@@ -144,8 +306,5 @@ pub fn do_recover_stranded_funds(
         recovery_event,
     );
 
-    // TODO: Actual token transfer logic
-    // token_client.transfer(&env.current_contract_address(), &recipient, &amount);
-
     Ok(())
 }
@@ -0,0 +1,178 @@
+//! Billing schedule arithmetic for fixed and calendar-anchored intervals.
+//!
+//! **PRs that only change how due dates and period numbers are computed should edit this file only.**
+//!
+//! Calendar periods start at 00:00 UTC on the anchor day. An anchor day past the end
+//! of a short month is clamped to its last day (the 31st bills on February 28th or 29th).
+//! Date conversion uses the proleptic Gregorian calendar (days-from-civil algorithm).
+
+use crate::types::{IntervalKind, Subscription};
+
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Nominal `interval_seconds` stored for monthly subscriptions (used by estimates).
+pub const NOMINAL_MONTH_SECONDS: u64 = 30 * SECONDS_PER_DAY;
+/// Nominal `interval_seconds` stored for yearly subscriptions (used by estimates).
+pub const NOMINAL_YEAR_SECONDS: u64 = 365 * SECONDS_PER_DAY;
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a civil date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = i64::from(month);
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Civil date `(year, month, day)` of a Unix timestamp.
+pub fn civil_from_timestamp(timestamp: u64) -> (i64, u32, u32) {
+    let z = (timestamp / SECONDS_PER_DAY) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// 00:00 UTC on `day` of the given month, clamping `day` to the month's length.
+fn anchored_date(year: i64, month: u32, day: u32) -> u64 {
+    let day = day.min(days_in_month(year, month));
+    (days_from_civil(year, month, day).max(0) as u64) * SECONDS_PER_DAY
+}
+
+/// Whether a calendar anchor is well formed (`Seconds` needs no anchor).
+pub fn is_valid_anchor(kind: &IntervalKind) -> bool {
+    match *kind {
+        IntervalKind::Seconds => true,
+        IntervalKind::Months(day) => (1..=31).contains(&day),
+        // Validated against a leap year so that February 29th is accepted.
+        IntervalKind::Years(month, day) => {
+            (1..=12).contains(&month) && day >= 1 && day <= days_in_month(2000, month)
+        }
+    }
+}
+
+/// Start of calendar period `index` (months since year 0, or years).
+fn calendar_boundary(kind: &IntervalKind, index: i64) -> u64 {
+    match *kind {
+        IntervalKind::Months(day) => {
+            anchored_date(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, day)
+        }
+        IntervalKind::Years(month, day) => anchored_date(index, month, day),
+        IntervalKind::Seconds => 0,
+    }
+}
+
+/// Index of the calendar period containing `timestamp`.
+fn calendar_index(kind: &IntervalKind, timestamp: u64) -> i64 {
+    let (year, month, _) = civil_from_timestamp(timestamp);
+    let index = match kind {
+        IntervalKind::Months(_) => year * 12 + i64::from(month) - 1,
+        _ => year,
+    };
+    if timestamp < calendar_boundary(kind, index) {
+        index - 1
+    } else {
+        index
+    }
+}
+
+/// Number of the billing period containing `timestamp`, counted from the
+/// subscription's `billing_anchor` (period 0 starts at the anchor).
+///
+/// Replay protection, [`next_due`] and catch-up all use this numbering, so
+/// "period N" means the same thing everywhere. Timestamps before the anchor are period 0.
+pub fn period_number(sub: &Subscription, timestamp: u64) -> u64 {
+    match sub.interval_kind {
+        IntervalKind::Seconds => timestamp
+            .saturating_sub(sub.billing_anchor)
+            .checked_div(sub.interval_seconds)
+            .unwrap_or(0),
+        _ => {
+            let first = calendar_index(&sub.interval_kind, sub.billing_anchor);
+            (calendar_index(&sub.interval_kind, timestamp) - first).max(0) as u64
+        }
+    }
+}
+
+/// Start of billing period `number`. Period 0 starts at the anchor itself.
+pub fn period_start(sub: &Subscription, number: u64) -> u64 {
+    if number == 0 {
+        return sub.billing_anchor;
+    }
+    match sub.interval_kind {
+        IntervalKind::Seconds => sub
+            .billing_anchor
+            .saturating_add(number.saturating_mul(sub.interval_seconds)),
+        _ => {
+            let first = calendar_index(&sub.interval_kind, sub.billing_anchor);
+            let index = first.saturating_add(i64::try_from(number).unwrap_or(i64::MAX));
+            calendar_boundary(&sub.interval_kind, index)
+        }
+    }
+}
+
+/// Record a payment at `timestamp`.
+///
+/// Sets `last_payment_timestamp`. A payment that is off the fixed-length schedule
+/// (a late charge that restarts the schedule) moves `billing_anchor` to it, so
+/// the paid period becomes period 0. Calendar schedules never move.
+pub fn record_payment(sub: &mut Subscription, timestamp: u64) {
+    sub.last_payment_timestamp = timestamp;
+    let offset = timestamp.saturating_sub(sub.billing_anchor);
+    if sub.interval_kind == IntervalKind::Seconds
+        && offset.checked_rem(sub.interval_seconds).unwrap_or(0) != 0
+    {
+        sub.billing_anchor = timestamp;
+    }
+}
+
+/// Number of periods covered by payments so far: one past the period containing
+/// `last_payment_timestamp`, or 0 while the last payment predates the anchor
+/// (a trial, whose first paid period starts at the anchor).
+fn paid_periods(sub: &Subscription) -> u64 {
+    if sub.last_payment_timestamp < sub.billing_anchor {
+        0
+    } else {
+        period_number(sub, sub.last_payment_timestamp) + 1
+    }
+}
+
+/// When the next interval charge becomes due: the start of the first period
+/// not covered by a payment.
+pub fn next_due(sub: &Subscription) -> u64 {
+    period_start(sub, paid_periods(sub))
+}
+
+/// Periods due since the last payment at `now`, and the start of the latest one.
+///
+/// Returns `(0, last_payment_timestamp)` until [`next_due`] is reached.
+pub fn elapsed_periods(sub: &Subscription, now: u64) -> (u32, u64) {
+    let paid = paid_periods(sub);
+    if now < sub.billing_anchor || period_number(sub, now) < paid {
+        return (0, sub.last_payment_timestamp);
+    }
+    let current = period_number(sub, now);
+    (
+        u32::try_from(current + 1 - paid).unwrap_or(u32::MAX),
+        period_start(sub, current),
+    )
+}
@@ -0,0 +1,84 @@
+//! Catch-up billing: how an interval charge settles periods missed while the
+//! billing engine was down.
+//!
+//! **PRs that only change how arrears are computed or collected should edit this file only.**
+//!
+//! Merchants pick a [`CatchUpPolicy`]. Without one, a late charge collects a single
+//! period and restarts the schedule at the charge time ([`CatchUpMode::LatestOnly`]),
+//! which is the vault's original behaviour.
+
+use crate::calendar::elapsed_periods;
+use crate::merchant::require_config_actor;
+use crate::storage::{load_entry, save_entry};
+use crate::types::{CatchUpMode, CatchUpPolicy, DataKey, Error, OutstandingPeriods, Subscription};
+use soroban_sdk::{Address, Env, Symbol};
+
+pub fn get_catch_up_policy(env: &Env, merchant: &Address) -> CatchUpPolicy {
+    load_entry(env, &DataKey::CatchUpPolicy(merchant.clone())).unwrap_or(CatchUpPolicy {
+        mode: CatchUpMode::LatestOnly,
+        max_periods: 1,
+    })
+}
+
+/// Set the merchant's catch-up policy. Callable by the merchant or a config manager.
+pub fn do_set_catch_up_policy(
+    env: &Env,
+    actor: Address,
+    merchant: Address,
+    policy: CatchUpPolicy,
+) -> Result<(), Error> {
+    require_config_actor(env, &actor, &merchant)?;
+    if policy.mode == CatchUpMode::CollectAll && policy.max_periods == 0 {
+        return Err(Error::InvalidCatchUpPolicy);
+    }
+    save_entry(env, &DataKey::CatchUpPolicy(merchant.clone()), &policy);
+    env.events()
+        .publish((Symbol::new(env, "catch_up_policy"), merchant), policy);
+    Ok(())
+}
+
+/// What the next interval charge of `sub` would settle at the current ledger time.
+///
+/// Reports zero periods until the next charge is due (see [`crate::calendar::next_due`]).
+/// Status and replay protection are not considered.
+pub fn outstanding_periods(env: &Env, sub: &Subscription) -> Result<OutstandingPeriods, Error> {
+    let now = env.ledger().timestamp();
+    // `boundary`: start of the latest due period on the subscription's schedule.
+    let (periods, boundary) = elapsed_periods(sub, now);
+    if periods == 0 {
+        return Ok(OutstandingPeriods {
+            periods: 0,
+            collectible: 0,
+            amount_due: 0,
+            next_anchor: sub.last_payment_timestamp,
+        });
+    }
+
+    let policy = get_catch_up_policy(env, &sub.merchant);
+    let (collectible, next_anchor) = match policy.mode {
+        CatchUpMode::LatestOnly => (1, now),
+        CatchUpMode::CollectAll => (periods.min(policy.max_periods), boundary),
+        CatchUpMode::Skip => (1, boundary),
+    };
+    let amount_due = sub
+        .amount
+        .checked_mul(i128::from(collectible))
+        .ok_or(Error::Overflow)?;
+
+    Ok(OutstandingPeriods {
+        periods,
+        collectible,
+        amount_due,
+        next_anchor,
+    })
+}
+
+/// Missed periods a charge settling `due` forgives under [`CatchUpMode::Skip`]
+/// (every elapsed period but the current one); `0` under the other modes.
+pub fn skipped_periods(env: &Env, sub: &Subscription, due: &OutstandingPeriods) -> u32 {
+    if get_catch_up_policy(env, &sub.merchant).mode == CatchUpMode::Skip {
+        due.periods.saturating_sub(due.collectible)
+    } else {
+        0
+    }
+}
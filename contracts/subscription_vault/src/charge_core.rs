@@ -1,4 +1,4 @@
-//! Single charge logic (no auth). Used by charge_subscription and batch_charge.
+//! Single charge logic. Used by charge_subscription, batch_charge, charge_usage and charge_one_off.
 //!
 //! **PRs that only change how one subscription is charged should edit this file only.**
 //!
@@ -9,23 +9,28 @@
 //!   A charge for the same period is rejected with [`Error::Replay`].
 //! - **Optional idempotency key**: If the caller supplies an idempotency key (e.g. for retries),
 //!   we store one key per subscription. A second call with the same key returns `Ok(())` without
-//!   debiting again (idempotent success). Storage stays bounded (one key and one period per sub).
+//!   debiting again (idempotent success). Storage stays bounded (one key and one period per sub, in persistent entries).
 
+use crate::accounting::adjust_total_prepaid;
+use crate::calendar::{next_due, period_number, record_payment};
+use crate::catch_up::{outstanding_periods, skipped_periods};
+use crate::dunning::{clear_dunning, record_failed_charge};
+use crate::events::publish_subscription_event;
+use crate::merchant::credit_merchant_balance;
+use crate::plan_change::{apply_pending_plan_change, record_paid_through};
 use crate::queries::get_subscription;
-use crate::state_machine::validate_status_transition;
-use crate::types::{Error, SubscriptionChargedEvent, SubscriptionStatus};
-use soroban_sdk::{symbol_short, Env, Symbol};
-
-const KEY_CHARGED_PERIOD: Symbol = symbol_short!("cp");
-const KEY_IDEM: Symbol = symbol_short!("idem");
-
-fn charged_period_key(subscription_id: u32) -> (Symbol, u32) {
-    (KEY_CHARGED_PERIOD, subscription_id)
-}
-
-fn idem_key(subscription_id: u32) -> (Symbol, u32) {
-    (KEY_IDEM, subscription_id)
-}
+use crate::state_machine::{validate_actor_transition, Actor};
+use crate::storage::{
+    get_charged_period, get_idempotency_key, save_subscription, set_charged_period,
+    set_idempotency_key,
+};
+use crate::trial::{is_converting, is_in_trial};
+use crate::types::{
+    BatchChargeResult, Error, InsufficientBalanceEvent, OneOffChargedEvent, PeriodsSkippedEvent,
+    Subscription, SubscriptionChargedEvent, SubscriptionStatus, TrialConvertedEvent,
+    UsageChargedEvent, EVENT_SCHEMA_VERSION,
+};
+use soroban_sdk::{Address, Env};
 
 /// Performs a single interval-based charge with optional replay protection.
 ///
@@ -33,9 +38,20 @@ fn idem_key(subscription_id: u32) -> (Symbol, u32) {
 ///
 /// - If `idempotency_key` is `Some(k)` and we already processed this subscription with key `k`,
 ///   returns `Ok(())` without changing state (idempotent success).
-/// - Otherwise we derive a period from `now / interval_seconds`. If this period was already
+/// - Otherwise we derive the number of the period containing `now`, counted from the
+///   subscription's `billing_anchor` (see [`period_number`]). If this period was already
 ///   charged, returns `Err(Error::Replay)`.
 ///
+/// # Catch-up
+///
+/// How many elapsed periods are collected, and where the schedule restarts, follows the
+/// merchant's [`crate::types::CatchUpPolicy`] (see [`outstanding_periods`]).
+///
+/// # Plan changes
+///
+/// A plan change scheduled for this period is applied first, so the charge
+/// collects the new terms (see [`apply_pending_plan_change`]).
+///
 /// # Storage
 ///
 /// Bounded: one `u64` (last charged period) and optionally one idempotency key per subscription.
@@ -46,20 +62,21 @@ pub fn charge_one(
 ) -> Result<(), Error> {
     let mut sub = get_subscription(env, subscription_id)?;
 
-    if sub.status != SubscriptionStatus::Active {
+    // Past-due subscriptions are retried under the merchant's dunning policy.
+    if sub.status != SubscriptionStatus::Active && sub.status != SubscriptionStatus::PastDue {
         return Err(Error::NotActive);
     }
+    if is_in_trial(env, &sub) {
+        return Err(Error::InTrial);
+    }
+    apply_pending_plan_change(env, subscription_id, &mut sub);
 
     let now = env.ledger().timestamp();
-    let period_index = now / sub.interval_seconds;
+    let period_index = period_number(&sub, now);
 
     // Idempotent return: same idempotency key already processed for this subscription
     if let Some(ref k) = idempotency_key {
-        if let Some(stored) = env
-            .storage()
-            .instance()
-            .get::<_, soroban_sdk::BytesN<32>>(&idem_key(subscription_id))
-        {
+        if let Some(stored) = get_idempotency_key(env, subscription_id) {
             if stored == *k {
                 return Ok(());
             }
@@ -67,68 +84,165 @@ pub fn charge_one(
     }
 
     // Replay: already charged for this billing period (derived key)
-    if let Some(stored_period) = env
-        .storage()
-        .instance()
-        .get::<_, u64>(&charged_period_key(subscription_id))
-    {
+    if let Some(stored_period) = get_charged_period(env, subscription_id) {
         if period_index <= stored_period {
             return Err(Error::Replay);
         }
     }
 
-    let next_allowed = sub
-        .last_payment_timestamp
-        .checked_add(sub.interval_seconds)
-        .ok_or(Error::Overflow)?;
-    if now < next_allowed {
+    if now < next_due(&sub) {
         return Err(Error::IntervalNotElapsed);
     }
 
-    if sub.prepaid_balance < sub.amount {
-        validate_status_transition(&sub.status, &SubscriptionStatus::InsufficientBalance)?;
-        sub.status = SubscriptionStatus::InsufficientBalance;
-        env.storage().instance().set(&subscription_id, &sub);
+    let due = outstanding_periods(env, &sub)?;
+
+    if sub.prepaid_balance < due.amount_due {
+        record_failed_charge(env, subscription_id, &mut sub, due.amount_due)?;
         return Err(Error::InsufficientBalance);
     }
 
+    let converting = is_converting(&sub);
+    if sub.status == SubscriptionStatus::PastDue {
+        validate_actor_transition(Actor::System, &sub.status, &SubscriptionStatus::Active)?;
+        sub.status = SubscriptionStatus::Active;
+        clear_dunning(env, subscription_id);
+    }
+
     sub.prepaid_balance = sub
         .prepaid_balance
-        .checked_sub(sub.amount)
+        .checked_sub(due.amount_due)
         .ok_or(Error::Overflow)?;
-    sub.last_payment_timestamp = now;
-    env.storage().instance().set(&subscription_id, &sub);
+    record_payment(&mut sub, due.next_anchor);
+    save_subscription(env, subscription_id, &sub);
+    adjust_total_prepaid(env, -due.amount_due)?;
+    credit_merchant_balance(env, &sub.merchant, due.amount_due)?;
 
     // Record charged period and optional idempotency key (bounded storage)
-    env.storage()
-        .instance()
-        .set(&charged_period_key(subscription_id), &period_index);
+    set_charged_period(env, subscription_id, period_number(&sub, now));
+    record_paid_through(env, subscription_id, &sub);
     if let Some(k) = idempotency_key {
-        env.storage().instance().set(&idem_key(subscription_id), &k);
+        set_idempotency_key(env, subscription_id, &k);
     }
 
-    env.events().publish(
-        (symbol_short!("charged"),),
+    // Late charge under `CatchUpMode::Skip`: the arrears are forgiven, only the
+    // current period is collected.
+    let skipped = skipped_periods(env, &sub, &due);
+    if skipped > 0 {
+        publish_subscription_event(
+            env,
+            "periods_skipped",
+            subscription_id,
+            &sub,
+            PeriodsSkippedEvent {
+                version: EVENT_SCHEMA_VERSION,
+                subscription_id,
+                periods: skipped,
+                next_anchor: due.next_anchor,
+            },
+        );
+    }
+    publish_subscription_event(
+        env,
+        "charged",
+        subscription_id,
+        &sub,
         SubscriptionChargedEvent {
+            version: EVENT_SCHEMA_VERSION,
             subscription_id,
             merchant: sub.merchant.clone(),
-            amount: sub.amount,
+            amount: due.amount_due,
+            prepaid_balance: sub.prepaid_balance,
+            timestamp: now,
+            periods: due.collectible,
         },
     );
+    if converting {
+        publish_subscription_event(
+            env,
+            "trial_converted",
+            subscription_id,
+            &sub,
+            TrialConvertedEvent {
+                version: EVENT_SCHEMA_VERSION,
+                subscription_id,
+                trial_ends_at: sub.trial_ends_at,
+                amount: due.amount_due,
+            },
+        );
+    }
 
     Ok(())
 }
 
+/// Run [`charge_one`] as a standalone charge whose failure must persist.
+///
+/// A charge that fails for lack of funds has already recorded the failure
+/// (`InsufficientBalance` status or a dunning step). It is reported as an
+/// unsuccessful result instead of an error so the transaction commits; any
+/// other error is returned as is.
+pub fn charge_one_recorded(env: &Env, subscription_id: u32) -> Result<BatchChargeResult, Error> {
+    match charge_one(env, subscription_id, None) {
+        Ok(()) => Ok(BatchChargeResult {
+            success: true,
+            error_code: 0,
+        }),
+        Err(Error::InsufficientBalance) => Ok(BatchChargeResult {
+            success: false,
+            error_code: Error::InsufficientBalance.to_code(),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether an interval charge for the current period would pass the timing and
+/// replay checks of [`charge_one`] (interval elapsed, period not yet charged).
+pub fn is_period_due(env: &Env, subscription_id: u32, sub: &Subscription) -> bool {
+    let now = env.ledger().timestamp();
+    let elapsed = now >= next_due(sub);
+    let period_index = period_number(sub, now);
+    let already_charged = get_charged_period(env, subscription_id)
+        .is_some_and(|stored_period| period_index <= stored_period);
+    elapsed && !already_charged
+}
+
+/// Charge the current period of a freshly funded subscription at creation.
+///
+/// Used by `create_subscription_with_deposit` in pay-upfront mode. Records the
+/// current period as charged so it cannot be billed again, and emits no
+/// `charged` event (the caller emits one combined event). Returns the amount charged.
+pub fn charge_upfront(env: &Env, subscription_id: u32) -> Result<i128, Error> {
+    let mut sub = get_subscription(env, subscription_id)?;
+    if sub.prepaid_balance < sub.amount {
+        return Err(Error::InsufficientBalance);
+    }
+
+    let now = env.ledger().timestamp();
+    sub.prepaid_balance = sub
+        .prepaid_balance
+        .checked_sub(sub.amount)
+        .ok_or(Error::Overflow)?;
+    record_payment(&mut sub, now);
+    save_subscription(env, subscription_id, &sub);
+    adjust_total_prepaid(env, -sub.amount)?;
+    credit_merchant_balance(env, &sub.merchant, sub.amount)?;
+
+    set_charged_period(env, subscription_id, period_number(&sub, now));
+    record_paid_through(env, subscription_id, &sub);
+
+    Ok(sub.amount)
+}
+
 /// Debit a metered `usage_amount` from a subscription's prepaid balance.
 ///
 /// Shared safety checks:
 /// * Subscription must exist (`NotFound`).
-/// * Subscription must be `Active` (`NotActive`).
+/// * Subscription must be `Active` (`NotActive`) and out of its trial (`InTrial`).
 /// * `usage_enabled` must be `true` (`UsageNotEnabled`).
 /// * `usage_amount` must be positive (`InvalidAmount`).
 /// * `prepaid_balance >= usage_amount` (`InsufficientPrepaidBalance`).
 ///
-/// On success the prepaid balance is reduced.  If the balance reaches zero
+/// On success the prepaid balance is reduced and the same amount is credited
+/// to the merchant's earnings ledger.  If the balance reaches zero
 /// the subscription transitions to `InsufficientBalance`, blocking further
 /// charges until the subscriber tops up.
 pub fn charge_usage_one(env: &Env, subscription_id: u32, usage_amount: i128) -> Result<(), Error> {
@@ -138,6 +252,10 @@ pub fn charge_usage_one(env: &Env, subscription_id: u32, usage_amount: i128) ->
         return Err(Error::NotActive);
     }
 
+    if is_in_trial(env, &sub) {
+        return Err(Error::InTrial);
+    }
+
     if !sub.usage_enabled {
         return Err(Error::UsageNotEnabled);
     }
@@ -158,10 +276,107 @@ pub fn charge_usage_one(env: &Env, subscription_id: u32, usage_amount: i128) ->
     // If the vault is now empty, transition to InsufficientBalance so no
     // further charges (interval or usage) can proceed until top-up.
     if sub.prepaid_balance == 0 {
-        validate_status_transition(&sub.status, &SubscriptionStatus::InsufficientBalance)?;
+        validate_actor_transition(
+            Actor::System,
+            &sub.status,
+            &SubscriptionStatus::InsufficientBalance,
+        )?;
         sub.status = SubscriptionStatus::InsufficientBalance;
     }
 
-    env.storage().instance().set(&subscription_id, &sub);
+    save_subscription(env, subscription_id, &sub);
+    adjust_total_prepaid(env, -usage_amount)?;
+    credit_merchant_balance(env, &sub.merchant, usage_amount)?;
+
+    publish_subscription_event(
+        env,
+        "usage_charged",
+        subscription_id,
+        &sub,
+        UsageChargedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            merchant: sub.merchant.clone(),
+            amount: usage_amount,
+            prepaid_balance: sub.prepaid_balance,
+        },
+    );
+    if sub.status == SubscriptionStatus::InsufficientBalance {
+        publish_subscription_event(
+            env,
+            "insufficient_balance",
+            subscription_id,
+            &sub,
+            InsufficientBalanceEvent {
+                version: EVENT_SCHEMA_VERSION,
+                subscription_id,
+                amount_due: 0,
+                prepaid_balance: 0,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Merchant-initiated one-off debit from a subscription's prepaid balance.
+///
+/// * `merchant` must authorize and must be the subscription's merchant (`Unauthorized`).
+/// * Subscription must be `Active` or `Paused` (`NotActive`) and out of its trial (`InTrial`).
+/// * `amount` must be positive (`InvalidAmount`).
+/// * `prepaid_balance >= amount`, no overdraft (`InsufficientBalance`).
+///
+/// The amount is credited to the merchant's earnings ledger. Interval state
+/// (`last_payment_timestamp`, charged period) is left untouched.
+pub fn charge_one_off(
+    env: &Env,
+    subscription_id: u32,
+    merchant: Address,
+    amount: i128,
+) -> Result<(), Error> {
+    merchant.require_auth();
+
+    let mut sub = get_subscription(env, subscription_id)?;
+
+    if merchant != sub.merchant {
+        return Err(Error::Unauthorized);
+    }
+
+    if sub.status != SubscriptionStatus::Active && sub.status != SubscriptionStatus::Paused {
+        return Err(Error::NotActive);
+    }
+
+    if is_in_trial(env, &sub) {
+        return Err(Error::InTrial);
+    }
+
+    if amount <= 0 {
+        return Err(Error::InvalidAmount);
+    }
+
+    if sub.prepaid_balance < amount {
+        return Err(Error::InsufficientBalance);
+    }
+
+    sub.prepaid_balance = sub
+        .prepaid_balance
+        .checked_sub(amount)
+        .ok_or(Error::Overflow)?;
+    save_subscription(env, subscription_id, &sub);
+    adjust_total_prepaid(env, -amount)?;
+    credit_merchant_balance(env, &merchant, amount)?;
+
+    publish_subscription_event(
+        env,
+        "oneoff_ch",
+        subscription_id,
+        &sub,
+        OneOffChargedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            merchant,
+            amount,
+        },
+    );
+
     Ok(())
 }
@@ -0,0 +1,232 @@
+//! Dunning: grace periods and retries for failed interval charges.
+//!
+//! **PRs that only change how failed charges are retried or escalated should edit this file only.**
+//!
+//! Merchants opt in with a [`DunningPolicy`]. Under a policy, the first failed
+//! interval charge moves the subscription to `PastDue`, where it stays entitled
+//! and the billing engine keeps retrying, at most once per
+//! `retry_interval_seconds`. A failed retry past the grace window
+//! or the retry budget applies the policy's final action (`Suspended` or
+//! `Cancelled`). Without a policy a failed charge moves the subscription straight
+//! to `InsufficientBalance`, as before.
+
+use crate::events::publish_subscription_event;
+use crate::merchant::require_config_actor;
+use crate::queries::get_subscription;
+use crate::state_machine::{validate_actor_transition, Actor};
+use crate::storage::{
+    clear_dunning_record, get_dunning_record, load_entry, save_entry, save_subscription,
+    set_dunning_record,
+};
+use crate::types::{
+    DataKey, DunningEvent, DunningFinalAction, DunningPolicy, DunningRecord, DunningState, Error,
+    InsufficientBalanceEvent, Subscription, SubscriptionCancelledEvent, SubscriptionStatus,
+    EVENT_SCHEMA_VERSION,
+};
+use soroban_sdk::{Address, Env, Symbol};
+
+pub fn get_dunning_policy(env: &Env, merchant: &Address) -> Option<DunningPolicy> {
+    load_entry(env, &DataKey::DunningPolicy(merchant.clone()))
+}
+
+/// Set the merchant's dunning policy. Callable by the merchant or a config manager.
+pub fn do_set_dunning_policy(
+    env: &Env,
+    actor: Address,
+    merchant: Address,
+    policy: DunningPolicy,
+) -> Result<(), Error> {
+    require_config_actor(env, &actor, &merchant)?;
+    save_entry(env, &DataKey::DunningPolicy(merchant.clone()), &policy);
+    env.events()
+        .publish((Symbol::new(env, "dunning_policy"), merchant), policy);
+    Ok(())
+}
+
+fn grace_ends_at(record: &DunningRecord, policy: Option<&DunningPolicy>) -> u64 {
+    let grace = policy.map_or(0, |p| p.grace_period_seconds);
+    record.first_failed_at.saturating_add(grace)
+}
+
+fn set_status(
+    env: &Env,
+    subscription_id: u32,
+    sub: &mut Subscription,
+    to: SubscriptionStatus,
+) -> Result<(), Error> {
+    validate_actor_transition(Actor::System, &sub.status, &to)?;
+    sub.status = to;
+    save_subscription(env, subscription_id, sub);
+    Ok(())
+}
+
+fn publish_dunning_event(
+    env: &Env,
+    name: &str,
+    subscription_id: u32,
+    sub: &Subscription,
+    record: &DunningRecord,
+    grace_ends_at: u64,
+) {
+    publish_subscription_event(
+        env,
+        name,
+        subscription_id,
+        sub,
+        DunningEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            attempts: record.attempts,
+            grace_ends_at,
+            prepaid_balance: sub.prepaid_balance,
+        },
+    );
+}
+
+/// Apply the merchant's policy after an interval charge found `sub` underfunded.
+///
+/// `sub` must be `Active` or `PastDue`; `amount_due` is what the charge needed.
+/// The caller still returns `Error::InsufficientBalance`. A retry sooner than
+/// the policy's `retry_interval_seconds` after the previous attempt fails with
+/// `RetryTooSoon` instead, before anything is recorded.
+pub fn record_failed_charge(
+    env: &Env,
+    subscription_id: u32,
+    sub: &mut Subscription,
+    amount_due: i128,
+) -> Result<(), Error> {
+    let Some(policy) = get_dunning_policy(env, &sub.merchant) else {
+        set_status(
+            env,
+            subscription_id,
+            sub,
+            SubscriptionStatus::InsufficientBalance,
+        )?;
+        publish_subscription_event(
+            env,
+            "insufficient_balance",
+            subscription_id,
+            sub,
+            InsufficientBalanceEvent {
+                version: EVENT_SCHEMA_VERSION,
+                subscription_id,
+                amount_due,
+                prepaid_balance: sub.prepaid_balance,
+            },
+        );
+        return Ok(());
+    };
+
+    let now = env.ledger().timestamp();
+    if sub.status != SubscriptionStatus::PastDue {
+        let record = DunningRecord {
+            first_failed_at: now,
+            attempts: 1,
+            last_attempt_at: now,
+        };
+        set_dunning_record(env, subscription_id, &record);
+        set_status(env, subscription_id, sub, SubscriptionStatus::PastDue)?;
+        let ends = grace_ends_at(&record, Some(&policy));
+        publish_dunning_event(env, "past_due", subscription_id, sub, &record, ends);
+        return Ok(());
+    }
+
+    let mut record = get_dunning_record(env, subscription_id).unwrap_or(DunningRecord {
+        first_failed_at: now,
+        attempts: 0,
+        last_attempt_at: 0,
+    });
+    // Spacing retries keeps a keeper from burning the whole retry budget
+    // in a burst before the subscriber has had a chance to top up.
+    if record.attempts > 0
+        && now
+            < record
+                .last_attempt_at
+                .saturating_add(policy.retry_interval_seconds)
+    {
+        return Err(Error::RetryTooSoon);
+    }
+    record.attempts = record.attempts.saturating_add(1);
+    record.last_attempt_at = now;
+    set_dunning_record(env, subscription_id, &record);
+    let ends = grace_ends_at(&record, Some(&policy));
+
+    let retries_used = record.attempts.saturating_sub(1);
+    if retries_used < policy.max_retries && now < ends {
+        publish_dunning_event(env, "past_due", subscription_id, sub, &record, ends);
+        return Ok(());
+    }
+
+    match policy.final_action {
+        DunningFinalAction::Suspend => {
+            set_status(env, subscription_id, sub, SubscriptionStatus::Suspended)?;
+            publish_dunning_event(env, "suspended", subscription_id, sub, &record, ends);
+        }
+        DunningFinalAction::Cancel => {
+            set_status(env, subscription_id, sub, SubscriptionStatus::Cancelled)?;
+            publish_subscription_event(
+                env,
+                "cancelled",
+                subscription_id,
+                sub,
+                SubscriptionCancelledEvent {
+                    version: EVENT_SCHEMA_VERSION,
+                    subscription_id,
+                    authorizer: env.current_contract_address(),
+                    refund_amount: sub.prepaid_balance,
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Forget dunning progress once a subscription is back to `Active`.
+pub fn clear_dunning(env: &Env, subscription_id: u32) {
+    clear_dunning_record(env, subscription_id);
+}
+
+/// Read-only dunning view for a subscription.
+pub fn get_dunning_state(env: &Env, subscription_id: u32) -> Result<DunningState, Error> {
+    let sub = get_subscription(env, subscription_id)?;
+    let in_dunning = matches!(
+        sub.status,
+        SubscriptionStatus::PastDue | SubscriptionStatus::Suspended
+    );
+    let record = if in_dunning {
+        get_dunning_record(env, subscription_id)
+    } else {
+        None
+    };
+
+    let Some(record) = record else {
+        return Ok(DunningState {
+            entitled: sub.status == SubscriptionStatus::Active,
+            status: sub.status,
+            attempts: 0,
+            first_failed_at: 0,
+            grace_ends_at: 0,
+            retries_remaining: 0,
+        });
+    };
+
+    let policy = get_dunning_policy(env, &sub.merchant);
+    let ends = grace_ends_at(&record, policy.as_ref());
+    let past_due = sub.status == SubscriptionStatus::PastDue;
+    let retries_remaining = if past_due {
+        policy
+            .map_or(0, |p| p.max_retries)
+            .saturating_sub(record.attempts.saturating_sub(1))
+    } else {
+        0
+    };
+
+    Ok(DunningState {
+        entitled: past_due && env.ledger().timestamp() < ends,
+        status: sub.status,
+        attempts: record.attempts,
+        first_failed_at: record.first_failed_at,
+        grace_ends_at: ends,
+        retries_remaining,
+    })
+}
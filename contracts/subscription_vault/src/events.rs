@@ -0,0 +1,32 @@
+//! Event publishing helpers.
+//!
+//! **PRs that only change event topics or payloads should edit this file (and the structs in `types.rs`) only.**
+//!
+//! Every subscription event uses the topic layout
+//! `(name, subscription_id, merchant, subscriber)`, so indexers can filter a
+//! single subscription or everything touching one party. Payloads are the typed
+//! structs in `types.rs`, each carrying [`EVENT_SCHEMA_VERSION`](crate::types::EVENT_SCHEMA_VERSION).
+
+use crate::types::Subscription;
+use soroban_sdk::{Env, IntoVal, Symbol, Val};
+
+/// Publish `data` under `name` with the uniform subscription topic layout.
+pub fn publish_subscription_event<D>(
+    env: &Env,
+    name: &str,
+    subscription_id: u32,
+    sub: &Subscription,
+    data: D,
+) where
+    D: IntoVal<Env, Val>,
+{
+    env.events().publish(
+        (
+            Symbol::new(env, name),
+            subscription_id,
+            sub.merchant.clone(),
+            sub.subscriber.clone(),
+        ),
+        data,
+    );
+}
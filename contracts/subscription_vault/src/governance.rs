@@ -0,0 +1,204 @@
+//! Council governance: M-of-N approval for sensitive admin actions.
+//!
+//! **PRs that only change council proposals or their execution should edit this file only.**
+//!
+//! Until the admin configures a council, sensitive actions use their direct
+//! entrypoints (`rotate_admin`, `recover_stranded_funds`, `set_min_topup`).
+//! Once a council exists those entrypoints fail with `GovernanceRequired` and
+//! the actions run only through proposals approved by `threshold` signers.
+
+use crate::admin::{
+    apply_min_topup, apply_recovery, apply_rotate_admin, clear_pending_admin, do_get_admin,
+};
+use crate::roles::require_role;
+use crate::storage::{load_entry, save_entry};
+use crate::types::{
+    Council, DataKey, Error, GovProposal, GovernanceAction, Role, GOV_PROPOSAL_TTL_SECONDS,
+};
+use soroban_sdk::{Address, Env, Symbol, Vec};
+
+pub fn get_council(env: &Env) -> Option<Council> {
+    env.storage().instance().get(&DataKey::Council)
+}
+
+/// Fails with `GovernanceRequired` once a council is configured.
+pub fn require_no_council(env: &Env) -> Result<(), Error> {
+    if env.storage().instance().has(&DataKey::Council) {
+        return Err(Error::GovernanceRequired);
+    }
+    Ok(())
+}
+
+fn require_signer(env: &Env, signer: &Address) -> Result<Council, Error> {
+    signer.require_auth();
+    let council = get_council(env).ok_or(Error::NotFound)?;
+    if !council.signers.contains(signer) {
+        return Err(Error::Unauthorized);
+    }
+    Ok(council)
+}
+
+fn validate_council(signers: &Vec<Address>, threshold: u32) -> Result<(), Error> {
+    if threshold == 0 || threshold > signers.len() {
+        return Err(Error::InvalidCouncil);
+    }
+    for (i, signer) in signers.iter().enumerate() {
+        if signers.first_index_of(&signer) != Some(i as u32) {
+            return Err(Error::InvalidCouncil);
+        }
+    }
+    Ok(())
+}
+
+fn store_council(env: &Env, signers: Vec<Address>, threshold: u32) -> Result<(), Error> {
+    validate_council(&signers, threshold)?;
+    let council = Council { signers, threshold };
+    env.storage().instance().set(&DataKey::Council, &council);
+    env.events()
+        .publish((Symbol::new(env, "council_set"),), council);
+    Ok(())
+}
+
+/// Admin installs the first council. Afterwards only the council can change it.
+///
+/// Discards any pending admin handover, which was proposed without council approval.
+pub fn do_set_council(
+    env: &Env,
+    admin: Address,
+    signers: Vec<Address>,
+    threshold: u32,
+) -> Result<(), Error> {
+    require_role(env, Role::Admin, &admin)?;
+    require_no_council(env)?;
+    store_council(env, signers, threshold)?;
+    clear_pending_admin(env);
+    Ok(())
+}
+
+pub fn get_proposal(env: &Env, proposal_id: u32) -> Result<GovProposal, Error> {
+    load_entry(env, &DataKey::GovProposal(proposal_id)).ok_or(Error::NotFound)
+}
+
+fn save_proposal(env: &Env, proposal: &GovProposal) {
+    save_entry(env, &DataKey::GovProposal(proposal.id), proposal);
+}
+
+/// Load a proposal that can still be approved or executed.
+fn load_open_proposal(env: &Env, proposal_id: u32) -> Result<GovProposal, Error> {
+    let proposal = get_proposal(env, proposal_id)?;
+    if proposal.executed {
+        return Err(Error::ProposalAlreadyExecuted);
+    }
+    if env.ledger().timestamp() > proposal.expires_at {
+        return Err(Error::ProposalExpired);
+    }
+    Ok(proposal)
+}
+
+/// A signer proposes `action`; the proposer's approval is recorded immediately.
+///
+/// The proposal must reach the threshold and be executed within
+/// [`GOV_PROPOSAL_TTL_SECONDS`].
+pub fn do_propose(env: &Env, proposer: Address, action: GovernanceAction) -> Result<u32, Error> {
+    require_signer(env, &proposer)?;
+
+    let id: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::NextGovProposalId)
+        .unwrap_or(0);
+    let next = id.checked_add(1).ok_or(Error::Overflow)?;
+    env.storage()
+        .instance()
+        .set(&DataKey::NextGovProposalId, &next);
+
+    let now = env.ledger().timestamp();
+    let expires_at = now
+        .checked_add(GOV_PROPOSAL_TTL_SECONDS)
+        .ok_or(Error::Overflow)?;
+    let mut approvals = Vec::new(env);
+    approvals.push_back(proposer.clone());
+    let proposal = GovProposal {
+        id,
+        proposer: proposer.clone(),
+        action: action.clone(),
+        approvals,
+        created_at: now,
+        expires_at,
+        executed: false,
+    };
+    save_proposal(env, &proposal);
+
+    env.events()
+        .publish((Symbol::new(env, "gov_proposed"), id, proposer), action);
+    Ok(id)
+}
+
+/// A signer approves a pending proposal. Returns the new approval count.
+pub fn do_approve(env: &Env, signer: Address, proposal_id: u32) -> Result<u32, Error> {
+    require_signer(env, &signer)?;
+
+    let mut proposal = load_open_proposal(env, proposal_id)?;
+    if proposal.approvals.contains(&signer) {
+        return Err(Error::AlreadyApproved);
+    }
+    proposal.approvals.push_back(signer.clone());
+    save_proposal(env, &proposal);
+
+    let count = proposal.approvals.len();
+    env.events().publish(
+        (Symbol::new(env, "gov_approved"), proposal_id, signer),
+        count,
+    );
+    Ok(count)
+}
+
+/// Number of the proposal's approvals that come from current council signers.
+///
+/// Approvals from signers removed by a later `SetCouncil` no longer count.
+fn valid_approvals(council: &Council, proposal: &GovProposal) -> u32 {
+    let mut count = 0u32;
+    for signer in proposal.approvals.iter() {
+        if council.signers.contains(&signer) {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// A signer executes a proposal that has reached the threshold.
+pub fn do_execute(env: &Env, executor: Address, proposal_id: u32) -> Result<(), Error> {
+    let council = require_signer(env, &executor)?;
+
+    let mut proposal = load_open_proposal(env, proposal_id)?;
+    if valid_approvals(&council, &proposal) < council.threshold {
+        return Err(Error::ThresholdNotMet);
+    }
+
+    proposal.executed = true;
+    save_proposal(env, &proposal);
+
+    match proposal.action.clone() {
+        GovernanceAction::RotateAdmin(new_admin) => {
+            apply_rotate_admin(env, do_get_admin(env)?, new_admin);
+        }
+        GovernanceAction::RecoverStrandedFunds(recipient, amount, reason) => {
+            apply_recovery(env, executor.clone(), recipient, amount, reason)?;
+        }
+        GovernanceAction::SetMinTopup(min_topup) => {
+            apply_min_topup(env, min_topup)?;
+        }
+        GovernanceAction::Upgrade(wasm_hash) => {
+            env.deployer().update_current_contract_wasm(wasm_hash);
+        }
+        GovernanceAction::SetCouncil(signers, threshold) => {
+            store_council(env, signers, threshold)?;
+        }
+    }
+
+    env.events().publish(
+        (Symbol::new(env, "gov_executed"), proposal_id, executor),
+        proposal.action,
+    );
+    Ok(())
+}
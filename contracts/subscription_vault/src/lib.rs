@@ -1,16 +1,30 @@
 #![no_std]
 
 // ── Modules ──────────────────────────────────────────────────────────────────
+mod accounting;
 mod admin;
+mod calendar;
+mod catch_up;
 mod charge_core;
+mod dunning;
+mod events;
+mod governance;
 mod merchant;
+mod plan_change;
+mod plans;
 mod queries;
+mod roles;
 mod state_machine;
+mod storage;
 mod subscription;
+mod trial;
 pub mod types;
 
 // ── Re-exports (used by tests and external consumers) ────────────────────────
-pub use state_machine::{can_transition, get_allowed_transitions, validate_status_transition};
+pub use state_machine::{
+    can_actor_transition, can_transition, get_allowed_transitions, validate_actor_transition,
+    validate_status_transition, Actor,
+};
 pub use types::*;
 
 pub use queries::compute_next_charge_info;
@@ -26,22 +40,45 @@ impl SubscriptionVault {
     // ── Admin / Config ───────────────────────────────────────────────────
 
     /// Initialize the contract: set token address, admin, and minimum top-up.
+    ///
+    /// Can only be called once; later calls fail with `AlreadyInitialized`.
+    /// Every other entrypoint fails with `NotInitialized` until this has run.
     pub fn init(env: Env, token: Address, admin: Address, min_topup: i128) -> Result<(), Error> {
         admin::do_init(&env, token, admin, min_topup)
     }
 
-    /// Update the minimum top-up threshold. Only callable by admin.
+    /// Update the minimum top-up threshold. Requires the `ConfigManager` role.
     pub fn set_min_topup(env: Env, admin: Address, min_topup: i128) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         admin::do_set_min_topup(&env, admin, min_topup)
     }
 
     /// Get the current minimum top-up threshold.
     pub fn get_min_topup(env: Env) -> Result<i128, Error> {
+        admin::require_initialized(&env)?;
         admin::get_min_topup(&env)
     }
 
+    /// Update the global amount/interval bounds for new subscriptions.
+    /// Requires the `ConfigManager` role. Emits `limits_updated`.
+    pub fn set_subscription_limits(
+        env: Env,
+        admin: Address,
+        limits: SubscriptionLimits,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        admin::do_set_subscription_limits(&env, admin, limits)
+    }
+
+    /// Get the global bounds applied by `create_subscription`.
+    pub fn get_subscription_limits(env: Env) -> Result<SubscriptionLimits, Error> {
+        admin::require_initialized(&env)?;
+        Ok(admin::get_subscription_limits(&env))
+    }
+
     /// Get the current admin address.
     pub fn get_admin(env: Env) -> Result<Address, Error> {
+        admin::require_initialized(&env)?;
         admin::do_get_admin(&env)
     }
 
@@ -49,18 +86,60 @@ impl SubscriptionVault {
     ///
     /// # Security
     ///
-    /// - Immediate effect — old admin loses access instantly.
+    /// - Immediate effect — old admin loses access instantly. Prefer the
+    ///   two-step `propose_admin` / `accept_admin` flow.
+    /// - Clears any pending admin proposal.
     /// - Irreversible without the new admin's cooperation.
     /// - Emits an `admin_rotation` event for audit trail.
     pub fn rotate_admin(env: Env, current_admin: Address, new_admin: Address) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         admin::do_rotate_admin(&env, current_admin, new_admin)
     }
 
-    /// **ADMIN ONLY**: Recover stranded funds from the contract.
+    /// Propose `new_admin` as the next admin. Only callable by the current admin.
+    ///
+    /// The proposal expires `ADMIN_PROPOSAL_TTL_SECONDS` after it is made and
+    /// replaces any earlier proposal. Emits `admin_proposed`.
+    pub fn propose_admin(
+        env: Env,
+        current_admin: Address,
+        new_admin: Address,
+    ) -> Result<PendingAdmin, Error> {
+        admin::require_initialized(&env)?;
+        admin::do_propose_admin(&env, current_admin, new_admin)
+    }
+
+    /// Accept a pending admin proposal. Requires the proposed admin's auth.
+    ///
+    /// Fails with `NoPendingAdmin`, `Unauthorized` (caller is not the nominee)
+    /// or `AdminProposalExpired`. Emits `admin_accepted`.
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        admin::do_accept_admin(&env, new_admin)
+    }
+
+    /// Withdraw the pending admin proposal. Only callable by the current admin.
+    ///
+    /// Emits `admin_proposal_cancelled`.
+    pub fn cancel_admin_proposal(env: Env, current_admin: Address) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        admin::do_cancel_admin_proposal(&env, current_admin)
+    }
+
+    /// Get the pending admin proposal, if any (including expired ones not yet replaced).
+    pub fn get_pending_admin(env: Env) -> Result<Option<PendingAdmin>, Error> {
+        admin::require_initialized(&env)?;
+        Ok(admin::get_pending_admin(&env))
+    }
+
+    /// **RECOVERER ONLY**: Recover stranded funds from the contract.
     ///
     /// Tightly-scoped mechanism for recovering funds that have become
-    /// inaccessible through normal operations. Each recovery emits a
-    /// `RecoveryEvent` with full audit details.
+    /// inaccessible through normal operations. Only the vault's surplus
+    /// (token balance minus subscriber prepaid balances and unpaid merchant
+    /// earnings) can be recovered; larger amounts fail with
+    /// `RecoveryExceedsSurplus`. Each recovery emits a `RecoveryEvent` with
+    /// full audit details.
     pub fn recover_stranded_funds(
         env: Env,
         admin: Address,
@@ -68,23 +147,146 @@ impl SubscriptionVault {
         amount: i128,
         reason: RecoveryReason,
     ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         admin::do_recover_stranded_funds(&env, admin, recipient, amount, reason)
     }
 
-    /// Charge a batch of subscriptions in one transaction. Admin only.
+    /// Lift protective mode once the vault is fully backed again. Requires the `Pauser` role.
+    ///
+    /// Fails with `VaultInsolvent` while token reserves are below liabilities.
+    pub fn exit_protective_mode(env: Env, admin: Address) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        accounting::do_exit_protective_mode(&env, admin)
+    }
+
+    /// Charge a batch of subscriptions in one transaction. Requires the `BillingOperator` role.
     ///
     /// Returns a per-subscription result vector so callers can identify
     /// which charges succeeded and which failed (with error codes).
     pub fn batch_charge(
         env: Env,
+        operator: Address,
         subscription_ids: Vec<u32>,
     ) -> Result<Vec<BatchChargeResult>, Error> {
-        admin::do_batch_charge(&env, &subscription_ids)
+        admin::require_initialized(&env)?;
+        admin::do_batch_charge(&env, operator, &subscription_ids)
+    }
+
+    // ── Roles ────────────────────────────────────────────────────────────
+
+    /// Grant `role` to `account`. Admin only.
+    ///
+    /// `Role::Admin` cannot be granted (use `rotate_admin`). Emits `role_granted`.
+    pub fn grant_role(env: Env, admin: Address, role: Role, account: Address) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        roles::do_grant_role(&env, admin, role, account)
+    }
+
+    /// Revoke `role` from `account`. Admin only. Emits `role_revoked`.
+    pub fn revoke_role(
+        env: Env,
+        admin: Address,
+        role: Role,
+        account: Address,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        roles::do_revoke_role(&env, admin, role, account)
+    }
+
+    /// Whether `account` holds `role`. The admin holds every role.
+    pub fn has_role(env: Env, role: Role, account: Address) -> Result<bool, Error> {
+        admin::require_initialized(&env)?;
+        Ok(roles::has_role(&env, role, &account))
+    }
+
+    /// Addresses explicitly granted `role` (for `Role::Admin`, the current admin).
+    pub fn get_role_members(env: Env, role: Role) -> Result<Vec<Address>, Error> {
+        admin::require_initialized(&env)?;
+        Ok(roles::get_role_members(&env, role))
+    }
+
+    // ── Governance ───────────────────────────────────────────────────────
+
+    /// Install the governance council (M-of-N signers). Admin only, once.
+    ///
+    /// Afterwards `rotate_admin`, `propose_admin`, `recover_stranded_funds`
+    /// and `set_min_topup` fail with `GovernanceRequired`; use
+    /// `propose_action` instead. Emits `council_set`.
+    pub fn set_council(
+        env: Env,
+        admin: Address,
+        signers: Vec<Address>,
+        threshold: u32,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        governance::do_set_council(&env, admin, signers, threshold)
+    }
+
+    /// Get the governance council, if one is configured.
+    pub fn get_council(env: Env) -> Result<Option<Council>, Error> {
+        admin::require_initialized(&env)?;
+        Ok(governance::get_council(&env))
+    }
+
+    /// Council signer proposes a sensitive action. Returns the proposal ID.
+    ///
+    /// The proposer's approval is recorded. The proposal expires
+    /// `GOV_PROPOSAL_TTL_SECONDS` after it is made. Emits `gov_proposed`.
+    pub fn propose_action(
+        env: Env,
+        proposer: Address,
+        action: GovernanceAction,
+    ) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        governance::do_propose(&env, proposer, action)
+    }
+
+    /// Council signer approves a proposal. Returns the approval count. Emits `gov_approved`.
+    ///
+    /// Fails with `ProposalExpired` once the proposal's `expires_at` has passed.
+    pub fn approve_proposal(env: Env, signer: Address, proposal_id: u32) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        governance::do_approve(&env, signer, proposal_id)
+    }
+
+    /// Council signer executes a proposal once it has `threshold` approvals.
+    ///
+    /// Fails with `ProposalExpired` once the proposal's `expires_at` has passed.
+    /// Emits `gov_executed` in addition to the action's own event.
+    pub fn execute_proposal(env: Env, executor: Address, proposal_id: u32) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        governance::do_execute(&env, executor, proposal_id)
+    }
+
+    /// Get a governance proposal with its approvals and execution state.
+    pub fn get_proposal(env: Env, proposal_id: u32) -> Result<GovProposal, Error> {
+        admin::require_initialized(&env)?;
+        governance::get_proposal(&env, proposal_id)
+    }
+
+    // ── Storage ──────────────────────────────────────────────────────────
+
+    /// Extend the storage TTL of the given subscriptions. Permissionless.
+    ///
+    /// Extends each subscription's record, its replay-protection entries and
+    /// its merchant's index. Fails with `NotFound` if any ID does not exist.
+    /// Lets keepers keep dormant subscriptions from being archived.
+    pub fn extend_ttl(env: Env, subscription_ids: Vec<u32>) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        storage::do_extend_ttl(&env, &subscription_ids)
     }
 
     // ── Subscription lifecycle ───────────────────────────────────────────
 
-    /// Create a new subscription. Caller deposits initial USDC; contract stores agreement.
+    /// Create a new subscription. The contract stores the agreement with a zero
+    /// prepaid balance; use `create_subscription_with_deposit` to fund it in the same call.
+    ///
+    /// Applies the merchant's config: `amount` must meet `min_subscription_amount`,
+    /// and `interval_seconds = 0` falls back to `default_interval_seconds`.
+    ///
+    /// Fails with `SelfSubscription` if `subscriber == merchant`, `InvalidAmount`
+    /// if `amount <= 0` or above the global maximum, and `InvalidInterval` if the
+    /// resolved interval is zero or outside the global bounds.
     pub fn create_subscription(
         env: Env,
         subscriber: Address,
@@ -93,6 +295,7 @@ impl SubscriptionVault {
         interval_seconds: u64,
         usage_enabled: bool,
     ) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
         subscription::do_create_subscription(
             &env,
             subscriber,
@@ -103,25 +306,160 @@ impl SubscriptionVault {
         )
     }
 
+    /// Create a subscription billed on calendar months or years.
+    ///
+    /// `interval_kind` is `Months(anchor_day)` or `Years(anchor_month, anchor_day)`;
+    /// charges fall due at 00:00 UTC on the anchor date, clamped to the last day of
+    /// shorter months. Otherwise the same validation as `create_subscription`, using
+    /// a nominal interval of 30 or 365 days. Invalid anchors fail with `InvalidInterval`.
+    pub fn create_calendar_subscription(
+        env: Env,
+        subscriber: Address,
+        merchant: Address,
+        amount: i128,
+        interval_kind: IntervalKind,
+        usage_enabled: bool,
+    ) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        subscription::do_create_calendar_subscription(
+            &env,
+            subscriber,
+            merchant,
+            amount,
+            interval_kind,
+            usage_enabled,
+        )
+    }
+
+    /// Create a subscription that starts with a free trial.
+    ///
+    /// `trial` is `EndsAt(timestamp)` or `Periods(n)` billing intervals. No charge
+    /// succeeds before the trial ends (`InTrial`); the first paid period starts at
+    /// the trial end. Fails with `InvalidTrial` for a trial that does not end in the
+    /// future or is longer than the merchant's maximum trial length, and with
+    /// `TrialLimitReached` once the subscriber has used the merchant's trial
+    /// allowance (none by default). Emits `created` and `trial_started`.
+    pub fn create_subscription_with_trial(
+        env: Env,
+        subscriber: Address,
+        merchant: Address,
+        amount: i128,
+        interval_seconds: u64,
+        usage_enabled: bool,
+        trial: TrialTerms,
+    ) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        subscription::do_create_subscription_with_trial(
+            &env,
+            subscriber,
+            merchant,
+            amount,
+            interval_seconds,
+            usage_enabled,
+            trial,
+        )
+    }
+
+    /// Subscribe to a merchant's plan on the plan's current terms.
+    ///
+    /// The subscription records `plan_id`. Fails with `PlanArchived` for an
+    /// archived plan, and with `InvalidAmount` if the merchant has since raised
+    /// `min_subscription_amount` above the plan's price. The plan's trial is granted while the subscriber has
+    /// trials left with the merchant, otherwise billing starts immediately.
+    /// Emits `created`, then `trial_started` if a trial was granted.
+    pub fn subscribe_to_plan(env: Env, subscriber: Address, plan_id: u32) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        subscription::do_subscribe_to_plan(&env, subscriber, plan_id)
+    }
+
+    /// Change an active subscription's price and interval, or move it to another
+    /// of the merchant's plans. Requires the subscriber's auth; custom
+    /// `PlanChangeTarget::Terms` also require the merchant's.
+    ///
+    /// `Immediate` credits the unused part of an already charged period at the
+    /// old price, charges the same time at the new price and settles the
+    /// difference against the prepaid balance (refunds come from the merchant's
+    /// unwithdrawn earnings); the next charge stays at the current period's end.
+    /// `NextPeriod` applies the new terms, unprorated, from the next unpaid
+    /// period. Fails with `NotActive` unless the subscription is `Active`, and
+    /// with `InsufficientBalance` if the difference cannot be settled. Emits
+    /// `plan_changed` or `plan_change_scheduled` and returns the quote applied.
+    pub fn change_subscription_plan(
+        env: Env,
+        subscription_id: u32,
+        subscriber: Address,
+        target: PlanChangeTarget,
+        timing: PlanChangeTiming,
+    ) -> Result<PlanChangeQuote, Error> {
+        admin::require_initialized(&env)?;
+        plan_change::do_change_subscription_plan(&env, subscription_id, subscriber, target, timing)
+    }
+
+    /// Create a subscription and make its initial deposit in one call.
+    ///
+    /// Same validation as `create_subscription`. Pulls `initial_deposit` tokens
+    /// from the subscriber (must meet `min_topup`). With `pay_upfront`, the first
+    /// period is charged immediately (`InsufficientBalance` if the deposit does
+    /// not cover `amount`). Emits a single `created_funded` event.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_subscription_with_deposit(
+        env: Env,
+        subscriber: Address,
+        merchant: Address,
+        amount: i128,
+        interval_seconds: u64,
+        usage_enabled: bool,
+        initial_deposit: i128,
+        pay_upfront: bool,
+    ) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        subscription::do_create_subscription_with_deposit(
+            &env,
+            subscriber,
+            merchant,
+            amount,
+            interval_seconds,
+            usage_enabled,
+            initial_deposit,
+            pay_upfront,
+        )
+    }
+
     /// Subscriber deposits more USDC into their prepaid vault.
     ///
-    /// Rejects deposits below the configured minimum threshold.
+    /// Rejects deposits below the configured minimum threshold. A subscription in
+    /// `InsufficientBalance` is reactivated once the balance covers one interval.
     pub fn deposit_funds(
         env: Env,
         subscription_id: u32,
         subscriber: Address,
         amount: i128,
     ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         subscription::do_deposit_funds(&env, subscription_id, subscriber, amount)
     }
 
+    /// Deposit like `deposit_funds`; if that reactivates the subscription, also
+    /// charge the period that failed (when still due) in the same call.
+    pub fn deposit_funds_and_collect(
+        env: Env,
+        subscription_id: u32,
+        subscriber: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        subscription::do_deposit_funds_and_collect(&env, subscription_id, subscriber, amount)
+    }
+
     /// Cancel the subscription. Allowed from Active, Paused, or InsufficientBalance.
-    /// Transitions to the terminal `Cancelled` state.
+    /// Transitions to the terminal `Cancelled` state. `authorizer` must be the
+    /// subscriber or the merchant.
     pub fn cancel_subscription(
         env: Env,
         subscription_id: u32,
         authorizer: Address,
     ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         subscription::do_cancel_subscription(&env, subscription_id, authorizer)
     }
 
@@ -131,24 +469,29 @@ impl SubscriptionVault {
         subscription_id: u32,
         subscriber: Address,
     ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         subscription::do_withdraw_subscriber_funds(&env, subscription_id, subscriber)
     }
 
-    /// Pause subscription (no charges until resumed). Allowed from Active.
+    /// Pause subscription (no charges until resumed). Allowed from Active, by the
+    /// subscriber or the merchant.
     pub fn pause_subscription(
         env: Env,
         subscription_id: u32,
         authorizer: Address,
     ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         subscription::do_pause_subscription(&env, subscription_id, authorizer)
     }
 
-    /// Resume a subscription to Active. Allowed from Paused or InsufficientBalance.
+    /// Resume a subscription to Active. Allowed from Paused (subscriber or merchant)
+    /// or InsufficientBalance (subscriber only).
     pub fn resume_subscription(
         env: Env,
         subscription_id: u32,
         authorizer: Address,
     ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         subscription::do_resume_subscription(&env, subscription_id, authorizer)
     }
 
@@ -156,9 +499,21 @@ impl SubscriptionVault {
 
     /// Billing engine calls this to charge one interval.
     ///
+    /// `operator` must authorize and hold the `BillingOperator` role.
     /// Enforces strict interval timing and replay protection.
-    pub fn charge_subscription(env: Env, subscription_id: u32) -> Result<(), Error> {
-        charge_core::charge_one(&env, subscription_id, None)
+    ///
+    /// A charge the prepaid balance cannot cover returns `success: false` with
+    /// `error_code` 1003 (`InsufficientBalance`) instead of an error, so the
+    /// recorded failure (status change, dunning attempt, grace clock) is kept.
+    /// Every other failure is returned as an error and changes nothing.
+    pub fn charge_subscription(
+        env: Env,
+        subscription_id: u32,
+        operator: Address,
+    ) -> Result<BatchChargeResult, Error> {
+        admin::require_initialized(&env)?;
+        roles::require_role(&env, Role::BillingOperator, &operator)?;
+        charge_core::charge_one_recorded(&env, subscription_id)
     }
 
     /// Charge a metered usage amount against the subscription's prepaid balance.
@@ -169,6 +524,7 @@ impl SubscriptionVault {
     ///
     /// # Requirements
     ///
+    /// * `operator` must authorize and hold the `BillingOperator` role.
     /// * The subscription must be `Active`.
     /// * `usage_enabled` must be `true` on the subscription.
     /// * `usage_amount` must be positive (`> 0`).
@@ -185,42 +541,334 @@ impl SubscriptionVault {
     ///
     /// | Variant | Reason |
     /// |---------|--------|
+    /// | `Unauthorized` | `operator` does not hold the `BillingOperator` role. |
     /// | `NotFound` | Subscription ID does not exist. |
     /// | `NotActive` | Subscription is not `Active`. |
     /// | `UsageNotEnabled` | `usage_enabled` is `false`. |
     /// | `InvalidAmount` | `usage_amount` is zero or negative. |
     /// | `InsufficientPrepaidBalance` | Prepaid balance cannot cover the debit. |
-    pub fn charge_usage(env: Env, subscription_id: u32, usage_amount: i128) -> Result<(), Error> {
+    pub fn charge_usage(
+        env: Env,
+        subscription_id: u32,
+        operator: Address,
+        usage_amount: i128,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        roles::require_role(&env, Role::BillingOperator, &operator)?;
         charge_core::charge_usage_one(&env, subscription_id, usage_amount)
     }
 
+    /// Merchant-initiated one-off charge (e.g. overage, add-on fee).
+    ///
+    /// Debits `amount` from the subscription's prepaid balance and credits the
+    /// merchant's earnings. Requires merchant auth; allowed from `Active` or
+    /// `Paused`; never overdraws; does not affect the billing interval.
+    /// Emits `oneoff_ch`.
+    pub fn charge_one_off(
+        env: Env,
+        subscription_id: u32,
+        merchant: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        charge_core::charge_one_off(&env, subscription_id, merchant, amount)
+    }
+
     // ── Merchant ─────────────────────────────────────────────────────────
 
     /// Merchant withdraws accumulated USDC to their wallet.
+    ///
+    /// Fails with `InvalidAmount` if `amount <= 0` and with `InsufficientBalance`
+    /// if `amount` exceeds the merchant's accrued earnings.
     pub fn withdraw_merchant_funds(env: Env, merchant: Address, amount: i128) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
         merchant::withdraw_merchant_funds(&env, merchant, amount)
     }
 
+    /// Set a merchant's config (full overwrite). Callable by admin or that merchant.
+    pub fn set_merchant_config(
+        env: Env,
+        actor: Address,
+        merchant: Address,
+        min_subscription_amount: i128,
+        default_interval_seconds: u64,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        merchant::do_set_merchant_config(
+            &env,
+            actor,
+            merchant,
+            min_subscription_amount,
+            default_interval_seconds,
+        )
+    }
+
+    /// Partially update a merchant's config; `None` leaves a field unchanged.
+    /// Callable by admin or that merchant.
+    pub fn update_merchant_config(
+        env: Env,
+        actor: Address,
+        merchant: Address,
+        min_subscription_amount: Option<i128>,
+        default_interval_seconds: Option<u64>,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        merchant::do_update_merchant_config(
+            &env,
+            actor,
+            merchant,
+            min_subscription_amount,
+            default_interval_seconds,
+        )
+    }
+
+    /// Get a merchant's config, or the default config if none was set.
+    pub fn get_merchant_config(env: Env, merchant: Address) -> Result<MerchantConfig, Error> {
+        admin::require_initialized(&env)?;
+        Ok(merchant::get_merchant_config(&env, &merchant))
+    }
+
+    /// Set the merchant's dunning policy (grace window, retry budget, final action).
+    ///
+    /// Callable by the merchant or a config manager. Without a policy, a failed
+    /// interval charge moves the subscription straight to `InsufficientBalance`.
+    pub fn set_dunning_policy(
+        env: Env,
+        actor: Address,
+        merchant: Address,
+        policy: DunningPolicy,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        dunning::do_set_dunning_policy(&env, actor, merchant, policy)
+    }
+
+    /// Get the merchant's dunning policy, if one is set.
+    pub fn get_dunning_policy(env: Env, merchant: Address) -> Result<Option<DunningPolicy>, Error> {
+        admin::require_initialized(&env)?;
+        Ok(dunning::get_dunning_policy(&env, &merchant))
+    }
+
+    /// Set how the merchant's interval charges settle missed periods.
+    ///
+    /// Callable by the merchant or a config manager. `CollectAll` needs a
+    /// `max_periods` of at least 1 (`InvalidCatchUpPolicy`).
+    pub fn set_catch_up_policy(
+        env: Env,
+        actor: Address,
+        merchant: Address,
+        policy: CatchUpPolicy,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        catch_up::do_set_catch_up_policy(&env, actor, merchant, policy)
+    }
+
+    /// Get the merchant's catch-up policy (`LatestOnly` when none is set).
+    pub fn get_catch_up_policy(env: Env, merchant: Address) -> Result<CatchUpPolicy, Error> {
+        admin::require_initialized(&env)?;
+        Ok(catch_up::get_catch_up_policy(&env, &merchant))
+    }
+
+    /// Set how many free trials the merchant grants each subscriber (`0` disables
+    /// trials). Callable by the merchant or a config manager.
+    pub fn set_trial_limit(
+        env: Env,
+        actor: Address,
+        merchant: Address,
+        max_trials_per_subscriber: u32,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        trial::do_set_trial_limit(&env, actor, merchant, max_trials_per_subscriber)
+    }
+
+    /// Get the merchant's per-subscriber trial limit (0 when none is set).
+    pub fn get_trial_limit(env: Env, merchant: Address) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        Ok(trial::get_trial_limit(&env, &merchant))
+    }
+
+    /// Set the longest free trial the merchant grants, in seconds. Longer trials
+    /// fail with `InvalidTrial`. Callable by the merchant or a config manager.
+    pub fn set_max_trial_length(
+        env: Env,
+        actor: Address,
+        merchant: Address,
+        max_trial_seconds: u64,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        trial::do_set_max_trial_length(&env, actor, merchant, max_trial_seconds)
+    }
+
+    /// Get the merchant's maximum trial length in seconds (0 when none is set).
+    pub fn get_max_trial_length(env: Env, merchant: Address) -> Result<u64, Error> {
+        admin::require_initialized(&env)?;
+        Ok(trial::get_max_trial_length(&env, &merchant))
+    }
+
+    /// Create a plan: reusable terms subscribers join with `subscribe_to_plan`.
+    ///
+    /// Requires merchant auth. Terms are checked against the global limits and
+    /// the merchant's `min_subscription_amount` (`InvalidAmount`,
+    /// `InvalidInterval`); `trial_periods` of `0` means no trial.
+    pub fn create_plan(
+        env: Env,
+        merchant: Address,
+        amount: i128,
+        interval_seconds: u64,
+        usage_enabled: bool,
+        trial_periods: u32,
+    ) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        plans::do_create_plan(
+            &env,
+            merchant,
+            amount,
+            interval_seconds,
+            usage_enabled,
+            trial_periods,
+        )
+    }
+
+    /// Replace a plan's terms (full overwrite). Applies to new subscribers only.
+    ///
+    /// Requires auth of the owning merchant (`Unauthorized` otherwise); fails
+    /// with `PlanArchived` once the plan is archived. The new terms are checked
+    /// like `create_plan`'s.
+    pub fn update_plan(
+        env: Env,
+        merchant: Address,
+        plan_id: u32,
+        amount: i128,
+        interval_seconds: u64,
+        usage_enabled: bool,
+        trial_periods: u32,
+    ) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        plans::do_update_plan(
+            &env,
+            merchant,
+            plan_id,
+            amount,
+            interval_seconds,
+            usage_enabled,
+            trial_periods,
+        )
+    }
+
+    /// Archive a plan so it accepts no new subscribers. Existing subscriptions
+    /// continue unchanged. Requires auth of the owning merchant.
+    pub fn archive_plan(env: Env, merchant: Address, plan_id: u32) -> Result<(), Error> {
+        admin::require_initialized(&env)?;
+        plans::do_archive_plan(&env, merchant, plan_id)
+    }
+
+    /// Get a plan by ID.
+    pub fn get_plan(env: Env, plan_id: u32) -> Result<Plan, Error> {
+        admin::require_initialized(&env)?;
+        plans::get_plan(&env, plan_id)
+    }
+
+    /// List the IDs of every plan the merchant has created, archived included.
+    pub fn list_plans(env: Env, merchant: Address) -> Result<Vec<u32>, Error> {
+        admin::require_initialized(&env)?;
+        Ok(plans::list_plans(&env, &merchant))
+    }
+
+    /// Get the merchant's accrued earnings that have not been withdrawn yet.
+    pub fn get_merchant_balance(env: Env, merchant: Address) -> Result<i128, Error> {
+        admin::require_initialized(&env)?;
+        Ok(merchant::get_merchant_balance(&env, &merchant))
+    }
+
+    // ── Solvency ─────────────────────────────────────────────────────────
+
+    /// Compare the vault's token balance with its recorded liabilities
+    /// (subscriber prepaid balances plus unpaid merchant earnings).
+    pub fn get_solvency_report(env: Env) -> Result<SolvencyReport, Error> {
+        admin::require_initialized(&env)?;
+        accounting::get_solvency_report(&env)
+    }
+
+    /// Permissionless reserve check. If the token balance has fallen below
+    /// liabilities (e.g. after an issuer clawback), the vault enters
+    /// protective mode: refunds, merchant withdrawals and recovery are blocked
+    /// with `ProtectiveMode` until the admin lifts it. Deposits stay open.
+    pub fn reconcile(env: Env) -> Result<SolvencyReport, Error> {
+        admin::require_initialized(&env)?;
+        accounting::do_reconcile(&env)
+    }
+
     // ── Queries ──────────────────────────────────────────────────────────
 
     /// Read subscription by id.
     pub fn get_subscription(env: Env, subscription_id: u32) -> Result<Subscription, Error> {
+        admin::require_initialized(&env)?;
         queries::get_subscription(&env, subscription_id)
     }
 
+    /// Dunning view: whether the subscription is still entitled, how many
+    /// charge attempts have failed, when the grace window ends, and how many
+    /// retries remain before the merchant's final action applies.
+    pub fn get_dunning_state(env: Env, subscription_id: u32) -> Result<DunningState, Error> {
+        admin::require_initialized(&env)?;
+        dunning::get_dunning_state(&env, subscription_id)
+    }
+
+    /// Arrears view: billing intervals elapsed since the last payment, how many
+    /// the next interval charge collects under the merchant's catch-up policy,
+    /// and the prepaid balance it needs.
+    pub fn get_outstanding_periods(
+        env: Env,
+        subscription_id: u32,
+    ) -> Result<OutstandingPeriods, Error> {
+        admin::require_initialized(&env)?;
+        let sub = queries::get_subscription(&env, subscription_id)?;
+        catch_up::outstanding_periods(&env, &sub)
+    }
+
+    /// Current billing period: its number (counted from `billing_anchor`), start,
+    /// end, and whether an interval charge has been recorded for it.
+    pub fn get_current_period(env: Env, subscription_id: u32) -> Result<BillingPeriod, Error> {
+        admin::require_initialized(&env)?;
+        queries::get_current_period(&env, subscription_id)
+    }
+
     /// Estimate how much a subscriber needs to deposit to cover N future intervals.
     pub fn estimate_topup_for_intervals(
         env: Env,
         subscription_id: u32,
         num_intervals: u32,
     ) -> Result<i128, Error> {
+        admin::require_initialized(&env)?;
         queries::estimate_topup_for_intervals(&env, subscription_id, num_intervals)
     }
 
     /// Get estimated next charge info (timestamp + whether charge is expected).
     pub fn get_next_charge_info(env: Env, subscription_id: u32) -> Result<NextChargeInfo, Error> {
+        admin::require_initialized(&env)?;
         let sub = queries::get_subscription(&env, subscription_id)?;
-        Ok(compute_next_charge_info(&sub))
+        Ok(compute_next_charge_info(&env, &sub))
+    }
+
+    /// Preview `change_subscription_plan` at the current ledger time: prorated
+    /// credit, charge, net difference and when the new terms apply.
+    pub fn quote_plan_change(
+        env: Env,
+        subscription_id: u32,
+        target: PlanChangeTarget,
+        timing: PlanChangeTiming,
+    ) -> Result<PlanChangeQuote, Error> {
+        admin::require_initialized(&env)?;
+        plan_change::quote_plan_change(&env, subscription_id, target, timing)
+    }
+
+    /// Get the plan change scheduled for the subscription's next period, if any.
+    pub fn get_pending_plan_change(
+        env: Env,
+        subscription_id: u32,
+    ) -> Result<Option<PendingPlanChange>, Error> {
+        admin::require_initialized(&env)?;
+        queries::get_subscription(&env, subscription_id)?;
+        Ok(storage::get_pending_plan_change(&env, subscription_id))
     }
 
     /// Return subscriptions for a merchant, paginated.
@@ -229,13 +877,38 @@ impl SubscriptionVault {
         merchant: Address,
         start: u32,
         limit: u32,
-    ) -> Vec<Subscription> {
-        queries::get_subscriptions_by_merchant(&env, merchant, start, limit)
+    ) -> Result<Vec<Subscription>, Error> {
+        admin::require_initialized(&env)?;
+        Ok(queries::get_subscriptions_by_merchant(
+            &env, merchant, start, limit,
+        ))
     }
 
     /// Return the total number of subscriptions for a merchant.
-    pub fn get_merchant_subscription_count(env: Env, merchant: Address) -> u32 {
-        queries::get_merchant_subscription_count(&env, merchant)
+    pub fn get_merchant_subscription_count(env: Env, merchant: Address) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        Ok(queries::get_merchant_subscription_count(&env, merchant))
+    }
+
+    /// Return the subscriptions currently on a plan, paginated over the plan's
+    /// index. A page may hold fewer than `limit` entries; continue from `start + limit`.
+    pub fn get_subscriptions_by_plan(
+        env: Env,
+        plan_id: u32,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<Subscription>, Error> {
+        admin::require_initialized(&env)?;
+        Ok(queries::get_subscriptions_by_plan(
+            &env, plan_id, start, limit,
+        ))
+    }
+
+    /// Return the number of entries in a plan's index (every subscription that
+    /// has been on the plan); the offset bound for `get_subscriptions_by_plan`.
+    pub fn get_plan_subscription_count(env: Env, plan_id: u32) -> Result<u32, Error> {
+        admin::require_initialized(&env)?;
+        Ok(queries::get_plan_subscription_count(&env, plan_id))
     }
 
     /// List all subscription IDs for a given subscriber with pagination support.
@@ -252,7 +925,10 @@ impl SubscriptionVault {
     /// A `SubscriptionsPage` containing subscription IDs and pagination metadata
     ///
     /// # Performance Notes
-    /// - Time complexity: O(n) where n = total subscriptions in contract
+    /// - Reads only this subscriber's paged index (k = their subscription count):
+    ///   O(log(k / 64)) page reads to find `start_from_id`, then the pages holding
+    ///   the next `limit` IDs
+    /// - Independent of the total number of subscriptions in the contract
     /// - Space complexity: O(limit)
     /// - Suitable for off-chain indexers and UI pagination
     ///
@@ -275,6 +951,7 @@ impl SubscriptionVault {
         start_from_id: u32,
         limit: u32,
     ) -> Result<crate::queries::SubscriptionsPage, Error> {
+        admin::require_initialized(&env)?;
         crate::queries::list_subscriptions_by_subscriber(&env, subscriber, start_from_id, limit)
     }
 
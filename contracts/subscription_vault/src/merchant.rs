@@ -1,13 +1,138 @@
-//! Merchant entrypoints: withdraw_merchant_funds.
+//! Merchant entrypoints: withdraw_merchant_funds, merchant earnings ledger, merchant config.
 //!
 //! **PRs that only change merchant payouts should edit this file only.**
 
-use crate::types::Error;
+use crate::accounting::{adjust_total_merchant_balance, require_not_protective};
+use crate::admin::get_token;
+use crate::roles::has_role;
+use crate::storage::{load_entry, save_entry};
+use crate::types::{
+    DataKey, Error, MerchantConfig, MerchantWithdrawalEvent, Role, EVENT_SCHEMA_VERSION,
+    MERCHANT_CONFIG_VERSION,
+};
 use soroban_sdk::{Address, Env, Symbol};
 
-pub fn withdraw_merchant_funds(_env: &Env, merchant: Address, _amount: i128) -> Result<(), Error> {
+/// Returns the merchant's accrued, not yet withdrawn earnings (0 if none).
+pub fn get_merchant_balance(env: &Env, merchant: &Address) -> i128 {
+    load_entry(env, &DataKey::MerchantBalance(merchant.clone())).unwrap_or(0)
+}
+
+fn set_merchant_balance(env: &Env, merchant: &Address, balance: i128) {
+    save_entry(env, &DataKey::MerchantBalance(merchant.clone()), &balance);
+}
+
+/// Credit `amount` to the merchant's internal earnings ledger.
+///
+/// Called by every successful charge (interval, usage, one-off). Funds stay in
+/// vault custody until the merchant withdraws them.
+pub fn credit_merchant_balance(env: &Env, merchant: &Address, amount: i128) -> Result<(), Error> {
+    let balance = get_merchant_balance(env, merchant)
+        .checked_add(amount)
+        .ok_or(Error::Overflow)?;
+    set_merchant_balance(env, merchant, balance);
+    adjust_total_merchant_balance(env, amount)
+}
+
+/// Withdraw `amount` of accrued earnings from vault custody to the merchant wallet.
+///
+/// Debits the internal ledger before transferring; if the transfer fails the
+/// whole transaction reverts, so the ledger can never drift from custody.
+pub fn withdraw_merchant_funds(env: &Env, merchant: Address, amount: i128) -> Result<(), Error> {
     merchant.require_auth();
-    _env.events()
-        .publish((Symbol::new(_env, "withdrawn"), merchant.clone()), _amount);
+    require_not_protective(env)?;
+
+    if amount <= 0 {
+        return Err(Error::InvalidAmount);
+    }
+
+    let balance = get_merchant_balance(env, &merchant);
+    if balance < amount {
+        return Err(Error::InsufficientBalance);
+    }
+
+    let remaining = balance.checked_sub(amount).ok_or(Error::Overflow)?;
+    set_merchant_balance(env, &merchant, remaining);
+    adjust_total_merchant_balance(env, -amount)?;
+
+    let token_client = soroban_sdk::token::Client::new(env, &get_token(env)?);
+    token_client.transfer(&env.current_contract_address(), &merchant, &amount);
+
+    env.events().publish(
+        (Symbol::new(env, "withdrawn"), merchant.clone()),
+        MerchantWithdrawalEvent {
+            version: EVENT_SCHEMA_VERSION,
+            merchant,
+            amount,
+            remaining_balance: remaining,
+        },
+    );
     Ok(())
 }
+
+/// Returns the merchant's config, or the default config if none is stored.
+pub fn get_merchant_config(env: &Env, merchant: &Address) -> MerchantConfig {
+    load_entry(env, &DataKey::MerchantConfig(merchant.clone()))
+        .unwrap_or(MerchantConfig::default_config())
+}
+
+/// Config may be written by the merchant itself or by a config manager.
+pub fn require_config_actor(env: &Env, actor: &Address, merchant: &Address) -> Result<(), Error> {
+    actor.require_auth();
+    if actor == merchant || has_role(env, Role::ConfigManager, actor) {
+        return Ok(());
+    }
+    Err(Error::Unauthorized)
+}
+
+fn store_merchant_config(
+    env: &Env,
+    merchant: Address,
+    config: &MerchantConfig,
+) -> Result<(), Error> {
+    if config.min_subscription_amount < 0 {
+        return Err(Error::InvalidAmount);
+    }
+    save_entry(env, &DataKey::MerchantConfig(merchant.clone()), config);
+    env.events().publish(
+        (Symbol::new(env, "merchant_config"), merchant),
+        config.clone(),
+    );
+    Ok(())
+}
+
+/// Overwrite the merchant's config. Callable by the admin or the merchant.
+pub fn do_set_merchant_config(
+    env: &Env,
+    actor: Address,
+    merchant: Address,
+    min_subscription_amount: i128,
+    default_interval_seconds: u64,
+) -> Result<(), Error> {
+    require_config_actor(env, &actor, &merchant)?;
+    let config = MerchantConfig {
+        version: MERCHANT_CONFIG_VERSION,
+        min_subscription_amount,
+        default_interval_seconds,
+    };
+    store_merchant_config(env, merchant, &config)
+}
+
+/// Partially update the merchant's config; `None` leaves a field unchanged.
+pub fn do_update_merchant_config(
+    env: &Env,
+    actor: Address,
+    merchant: Address,
+    min_subscription_amount: Option<i128>,
+    default_interval_seconds: Option<u64>,
+) -> Result<(), Error> {
+    require_config_actor(env, &actor, &merchant)?;
+    let mut config = get_merchant_config(env, &merchant);
+    if let Some(min) = min_subscription_amount {
+        config.min_subscription_amount = min;
+    }
+    if let Some(interval) = default_interval_seconds {
+        config.default_interval_seconds = interval;
+    }
+    config.version = MERCHANT_CONFIG_VERSION;
+    store_merchant_config(env, merchant, &config)
+}
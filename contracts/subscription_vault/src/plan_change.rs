@@ -0,0 +1,334 @@
+//! Plan changes: switch a subscription to new terms, with proration.
+//!
+//! **PRs that only change upgrade/downgrade rules should edit this file only.**
+//!
+//! An immediate change settles the rest of the current period: the unused part
+//! of an already charged period is credited at the old price and the same time
+//! is charged at the new price. The current period's end stays the next charge
+//! date. A change for the next period is stored and applied by the first
+//! interval charge at or after the next unpaid period's start, without proration.
+//! Either way the schedule is re-anchored, so period numbers restart there.
+//!
+//! Proration is decided by [`PaidThrough`], which every interval charge records
+//! and an immediate change rewrites at the new price, so a second change in the
+//! same period settles against what the first one left paid.
+
+use crate::accounting::adjust_total_prepaid;
+use crate::calendar::{next_due, period_number, period_start};
+use crate::events::publish_subscription_event;
+use crate::merchant::{credit_merchant_balance, get_merchant_balance, get_merchant_config};
+use crate::queries::get_subscription;
+use crate::storage::{
+    clear_charged_period, clear_pending_plan_change, get_paid_through, get_pending_plan_change,
+    push_plan_sub, save_subscription, set_paid_through, set_pending_plan_change,
+};
+use crate::subscription::validate_terms;
+use crate::types::{
+    Error, IntervalKind, PaidThrough, PendingPlanChange, PlanChangeQuote, PlanChangeTarget,
+    PlanChangeTiming, PlanChangedEvent, Subscription, SubscriptionStatus, EVENT_SCHEMA_VERSION,
+};
+use soroban_sdk::{Address, Env};
+
+/// Resolve `target` to concrete terms for `sub`, taking effect at `effective_at`.
+fn resolve_target(
+    env: &Env,
+    sub: &Subscription,
+    target: &PlanChangeTarget,
+    effective_at: u64,
+) -> Result<PendingPlanChange, Error> {
+    let change = match *target {
+        PlanChangeTarget::Terms(amount, interval_seconds) => {
+            let (interval_seconds, interval_kind) = if interval_seconds == 0 {
+                (sub.interval_seconds, sub.interval_kind.clone())
+            } else {
+                (interval_seconds, IntervalKind::Seconds)
+            };
+            PendingPlanChange {
+                amount,
+                interval_seconds,
+                interval_kind,
+                usage_enabled: sub.usage_enabled,
+                plan_id: None,
+                effective_at,
+            }
+        }
+        PlanChangeTarget::Plan(plan_id) => {
+            let plan = crate::plans::get_active_plan(env, plan_id)?;
+            if plan.merchant != sub.merchant {
+                return Err(Error::Unauthorized);
+            }
+            PendingPlanChange {
+                amount: plan.amount,
+                interval_seconds: plan.interval_seconds,
+                interval_kind: IntervalKind::Seconds,
+                usage_enabled: plan.usage_enabled,
+                plan_id: Some(plan_id),
+                effective_at,
+            }
+        }
+    };
+
+    validate_terms(env, change.amount, change.interval_seconds)?;
+    get_merchant_config(env, &sub.merchant).check_amount(change.amount)?;
+    Ok(change)
+}
+
+/// `amount` for `part` out of `whole` seconds, rounded down.
+fn prorate(amount: i128, part: u64, whole: u64) -> Result<i128, Error> {
+    if whole == 0 {
+        return Ok(0);
+    }
+    amount
+        .checked_mul(i128::from(part))
+        .map(|scaled| scaled / i128::from(whole))
+        .ok_or(Error::Overflow)
+}
+
+/// Record what the interval charge just made on `sub` paid for: up to its next
+/// due date, at its current price per period. Called after every successful charge.
+pub fn record_paid_through(env: &Env, subscription_id: u32, sub: &Subscription) {
+    let until = next_due(sub);
+    let start = period_start(sub, period_number(sub, env.ledger().timestamp()));
+    set_paid_through(
+        env,
+        subscription_id,
+        &PaidThrough {
+            until,
+            amount: sub.amount,
+            length: until.saturating_sub(start),
+        },
+    );
+}
+
+/// Quote an immediate change of `sub` to `change` at the current ledger time,
+/// with the paid time left afterwards (re-priced at the new terms).
+///
+/// Proration applies only while paid time remains; a period not yet due for
+/// the first time (after creation or in a trial) was never paid, and an overdue
+/// one is billed in full at the new price.
+fn quote_immediate(
+    env: &Env,
+    subscription_id: u32,
+    sub: &Subscription,
+    change: &PendingPlanChange,
+) -> Result<(PlanChangeQuote, Option<PaidThrough>), Error> {
+    let now = env.ledger().timestamp();
+    let unpaid = PlanChangeQuote {
+        credit: 0,
+        charge: 0,
+        net: 0,
+        effective_at: now,
+    };
+    let due = next_due(sub);
+    if now >= due {
+        // Overdue: the pending charge, due now, bills a full period at the new price.
+        return Ok((unpaid, None));
+    }
+    let Some(paid) = get_paid_through(env, subscription_id).filter(|paid| now < paid.until) else {
+        return Ok((unpaid, None));
+    };
+
+    let remaining = paid.until.min(due) - now;
+    let new_length = match change.interval_kind {
+        IntervalKind::Seconds => change.interval_seconds,
+        _ => paid.length,
+    };
+    let credit = prorate(paid.amount, remaining, paid.length)?;
+    let charge = prorate(change.amount, remaining, new_length)?;
+    let quote = PlanChangeQuote {
+        credit,
+        charge,
+        net: charge.checked_sub(credit).ok_or(Error::Overflow)?,
+        effective_at: now,
+    };
+    let repriced = PaidThrough {
+        until: paid.until,
+        amount: change.amount,
+        length: new_length,
+    };
+    Ok((quote, Some(repriced)))
+}
+
+fn quote(
+    env: &Env,
+    subscription_id: u32,
+    sub: &Subscription,
+    target: &PlanChangeTarget,
+    timing: PlanChangeTiming,
+) -> Result<(PendingPlanChange, PlanChangeQuote, Option<PaidThrough>), Error> {
+    match timing {
+        PlanChangeTiming::Immediate => {
+            let change = resolve_target(env, sub, target, env.ledger().timestamp())?;
+            let (quote, paid) = quote_immediate(env, subscription_id, sub, &change)?;
+            Ok((change, quote, paid))
+        }
+        PlanChangeTiming::NextPeriod => {
+            let effective_at = next_due(sub);
+            let change = resolve_target(env, sub, target, effective_at)?;
+            let quote = PlanChangeQuote {
+                credit: 0,
+                charge: 0,
+                net: 0,
+                effective_at,
+            };
+            Ok((change, quote, None))
+        }
+    }
+}
+
+/// Switch `sub` to `change` and restart its schedule at `anchor`.
+///
+/// `last_payment_timestamp` stays before `anchor`, so the next charge is due at
+/// `anchor` itself. The charged period is cleared because period numbers restart.
+fn apply_terms(
+    env: &Env,
+    subscription_id: u32,
+    sub: &mut Subscription,
+    change: &PendingPlanChange,
+    anchor: u64,
+) {
+    if let Some(plan_id) = change.plan_id {
+        if sub.plan_id != Some(plan_id) {
+            push_plan_sub(env, plan_id, subscription_id);
+        }
+    }
+    sub.amount = change.amount;
+    sub.interval_seconds = change.interval_seconds;
+    sub.interval_kind = change.interval_kind.clone();
+    sub.usage_enabled = change.usage_enabled;
+    sub.plan_id = change.plan_id;
+    sub.billing_anchor = anchor;
+    save_subscription(env, subscription_id, sub);
+    clear_charged_period(env, subscription_id);
+}
+
+fn publish_plan_changed(
+    env: &Env,
+    name: &str,
+    subscription_id: u32,
+    sub: &Subscription,
+    change: &PendingPlanChange,
+    quote: &PlanChangeQuote,
+) {
+    publish_subscription_event(
+        env,
+        name,
+        subscription_id,
+        sub,
+        PlanChangedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            amount: change.amount,
+            interval_seconds: change.interval_seconds,
+            plan_id: change.plan_id,
+            credit: quote.credit,
+            charge: quote.charge,
+            effective_at: quote.effective_at,
+        },
+    );
+}
+
+/// Preview `change_subscription_plan` without changing state.
+pub fn quote_plan_change(
+    env: &Env,
+    subscription_id: u32,
+    target: PlanChangeTarget,
+    timing: PlanChangeTiming,
+) -> Result<PlanChangeQuote, Error> {
+    let sub = get_subscription(env, subscription_id)?;
+    quote(env, subscription_id, &sub, &target, timing).map(|(_, quote, _)| quote)
+}
+
+/// Change an active subscription's terms. Requires the subscriber's auth, and
+/// the merchant's too for custom `Terms`: only a merchant's own active plans
+/// may be picked by the subscriber alone.
+///
+/// `Immediate` settles the prorated difference against the prepaid balance
+/// (`InsufficientBalance` if it cannot cover an upgrade) or refunds it from the
+/// merchant's unwithdrawn earnings (`InsufficientBalance` if they cannot cover
+/// a downgrade). `NextPeriod` replaces any previously scheduled change.
+pub fn do_change_subscription_plan(
+    env: &Env,
+    subscription_id: u32,
+    subscriber: Address,
+    target: PlanChangeTarget,
+    timing: PlanChangeTiming,
+) -> Result<PlanChangeQuote, Error> {
+    subscriber.require_auth();
+    let mut sub = get_subscription(env, subscription_id)?;
+    if sub.subscriber != subscriber {
+        return Err(Error::Unauthorized);
+    }
+    if let PlanChangeTarget::Terms(..) = target {
+        sub.merchant.require_auth();
+    }
+    if sub.status != SubscriptionStatus::Active {
+        return Err(Error::NotActive);
+    }
+    let (change, quote, paid) = quote(env, subscription_id, &sub, &target, timing)?;
+
+    if timing == PlanChangeTiming::NextPeriod {
+        set_pending_plan_change(env, subscription_id, &change);
+        publish_plan_changed(
+            env,
+            "plan_change_scheduled",
+            subscription_id,
+            &sub,
+            &change,
+            &quote,
+        );
+        return Ok(quote);
+    }
+
+    if quote.net > 0 {
+        if sub.prepaid_balance < quote.net {
+            return Err(Error::InsufficientBalance);
+        }
+        sub.prepaid_balance -= quote.net;
+        adjust_total_prepaid(env, -quote.net)?;
+        credit_merchant_balance(env, &sub.merchant, quote.net)?;
+    } else if quote.net < 0 {
+        let refund = -quote.net;
+        if get_merchant_balance(env, &sub.merchant) < refund {
+            return Err(Error::InsufficientBalance);
+        }
+        credit_merchant_balance(env, &sub.merchant, quote.net)?;
+        sub.prepaid_balance = sub
+            .prepaid_balance
+            .checked_add(refund)
+            .ok_or(Error::Overflow)?;
+        adjust_total_prepaid(env, refund)?;
+    }
+
+    // A covered period keeps its end as the next charge date; an overdue one
+    // restarts the schedule now, so the new price is due immediately.
+    let now = env.ledger().timestamp();
+    let anchor = next_due(&sub).max(now);
+    clear_pending_plan_change(env, subscription_id);
+    apply_terms(env, subscription_id, &mut sub, &change, anchor);
+    if let Some(paid) = paid {
+        set_paid_through(env, subscription_id, &paid);
+    }
+    publish_plan_changed(env, "plan_changed", subscription_id, &sub, &change, &quote);
+    Ok(quote)
+}
+
+/// Apply a scheduled plan change once its period has started. Called by the
+/// interval charge before anything is collected.
+pub fn apply_pending_plan_change(env: &Env, subscription_id: u32, sub: &mut Subscription) {
+    let Some(change) = get_pending_plan_change(env, subscription_id) else {
+        return;
+    };
+    if env.ledger().timestamp() < change.effective_at {
+        return;
+    }
+    clear_pending_plan_change(env, subscription_id);
+    apply_terms(env, subscription_id, sub, &change, change.effective_at);
+    let quote = PlanChangeQuote {
+        credit: 0,
+        charge: 0,
+        net: 0,
+        effective_at: change.effective_at,
+    };
+    publish_plan_changed(env, "plan_changed", subscription_id, sub, &change, &quote);
+}
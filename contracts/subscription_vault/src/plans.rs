@@ -0,0 +1,123 @@
+//! Merchant plan catalog: create, update, archive and list plans.
+//!
+//! **PRs that only change plan management should edit this file only.**
+//!
+//! A plan is a reusable set of terms (price, interval, usage flag, trial) owned
+//! by one merchant. Subscriptions created with `subscribe_to_plan` copy the
+//! plan's terms and remember its ID; later plan updates apply to new
+//! subscribers only. Archived plans stay readable but accept no subscribers.
+
+use crate::merchant::get_merchant_config;
+use crate::storage::{get_merchant_plans, load_plan, push_merchant_plan, save_plan};
+use crate::subscription::validate_terms;
+use crate::types::{Error, Plan, PlanStatus};
+use soroban_sdk::{Address, Env, Symbol, Vec};
+
+fn next_plan_id(env: &Env) -> u32 {
+    let key = Symbol::new(env, "next_plan_id");
+    let id: u32 = env.storage().instance().get(&key).unwrap_or(0);
+    env.storage().instance().set(&key, &(id + 1));
+    id
+}
+
+fn publish_plan_event(env: &Env, name: &str, plan_id: u32, plan: &Plan) {
+    env.events().publish(
+        (Symbol::new(env, name), plan.merchant.clone(), plan_id),
+        plan.clone(),
+    );
+}
+
+pub fn get_plan(env: &Env, plan_id: u32) -> Result<Plan, Error> {
+    load_plan(env, plan_id).ok_or(Error::NotFound)
+}
+
+/// The plan, provided it still accepts subscribers (`PlanArchived` otherwise).
+pub fn get_active_plan(env: &Env, plan_id: u32) -> Result<Plan, Error> {
+    let plan = get_plan(env, plan_id)?;
+    if plan.status == PlanStatus::Archived {
+        return Err(Error::PlanArchived);
+    }
+    Ok(plan)
+}
+
+/// Load a plan for modification by `merchant`: it must own the plan
+/// (`Unauthorized`) and the plan must not be archived (`PlanArchived`).
+fn load_owned_plan(env: &Env, merchant: &Address, plan_id: u32) -> Result<Plan, Error> {
+    merchant.require_auth();
+    let plan = get_plan(env, plan_id)?;
+    if plan.merchant != *merchant {
+        return Err(Error::Unauthorized);
+    }
+    if plan.status == PlanStatus::Archived {
+        return Err(Error::PlanArchived);
+    }
+    Ok(plan)
+}
+
+pub fn do_create_plan(
+    env: &Env,
+    merchant: Address,
+    amount: i128,
+    interval_seconds: u64,
+    usage_enabled: bool,
+    trial_periods: u32,
+) -> Result<u32, Error> {
+    merchant.require_auth();
+    validate_terms(env, amount, interval_seconds)?;
+    get_merchant_config(env, &merchant).check_amount(amount)?;
+
+    let plan = Plan {
+        merchant: merchant.clone(),
+        amount,
+        interval_seconds,
+        usage_enabled,
+        trial_periods,
+        status: PlanStatus::Active,
+    };
+    let plan_id = next_plan_id(env);
+    save_plan(env, plan_id, &plan);
+    push_merchant_plan(env, &merchant, plan_id);
+
+    publish_plan_event(env, "plan_created", plan_id, &plan);
+    Ok(plan_id)
+}
+
+/// Replace a plan's terms (full overwrite). Existing subscriptions keep the
+/// terms they were created with.
+pub fn do_update_plan(
+    env: &Env,
+    merchant: Address,
+    plan_id: u32,
+    amount: i128,
+    interval_seconds: u64,
+    usage_enabled: bool,
+    trial_periods: u32,
+) -> Result<(), Error> {
+    let mut plan = load_owned_plan(env, &merchant, plan_id)?;
+    validate_terms(env, amount, interval_seconds)?;
+    get_merchant_config(env, &merchant).check_amount(amount)?;
+
+    plan.amount = amount;
+    plan.interval_seconds = interval_seconds;
+    plan.usage_enabled = usage_enabled;
+    plan.trial_periods = trial_periods;
+    save_plan(env, plan_id, &plan);
+
+    publish_plan_event(env, "plan_updated", plan_id, &plan);
+    Ok(())
+}
+
+/// Close a plan to new subscribers. Archiving is permanent.
+pub fn do_archive_plan(env: &Env, merchant: Address, plan_id: u32) -> Result<(), Error> {
+    let mut plan = load_owned_plan(env, &merchant, plan_id)?;
+    plan.status = PlanStatus::Archived;
+    save_plan(env, plan_id, &plan);
+
+    publish_plan_event(env, "plan_archived", plan_id, &plan);
+    Ok(())
+}
+
+/// IDs of every plan `merchant` has created, archived ones included.
+pub fn list_plans(env: &Env, merchant: &Address) -> Vec<u32> {
+    get_merchant_plans(env, merchant)
+}
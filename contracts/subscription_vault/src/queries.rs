@@ -4,14 +4,31 @@
 
 #![allow(dead_code)]
 
-use crate::types::{DataKey, Error, NextChargeInfo, Subscription, SubscriptionStatus};
-use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+use crate::calendar::{period_number, period_start};
+use crate::storage::{
+    find_subscriber_sub, get_charged_period, get_merchant_sub_count, get_merchant_sub_ids,
+    get_plan_sub_count, get_plan_sub_ids, get_subscriber_sub_count, get_subscriber_sub_ids,
+    load_subscription,
+};
+use crate::types::{BillingPeriod, Error, NextChargeInfo, Subscription, SubscriptionStatus};
+use soroban_sdk::{contracttype, Address, Env, Vec};
 
 pub fn get_subscription(env: &Env, subscription_id: u32) -> Result<Subscription, Error> {
-    env.storage()
-        .instance()
-        .get(&subscription_id)
-        .ok_or(Error::NotFound)
+    load_subscription(env, subscription_id).ok_or(Error::NotFound)
+}
+
+/// The billing period containing the current ledger time, numbered from the
+/// subscription's `billing_anchor`, and whether it has been charged.
+pub fn get_current_period(env: &Env, subscription_id: u32) -> Result<BillingPeriod, Error> {
+    let sub = get_subscription(env, subscription_id)?;
+    let number = period_number(&sub, env.ledger().timestamp());
+    let charged = get_charged_period(env, subscription_id).is_some_and(|p| p >= number);
+    Ok(BillingPeriod {
+        number,
+        start: period_start(&sub, number),
+        end: period_start(&sub, number.saturating_add(1)),
+        charged,
+    })
 }
 
 pub fn estimate_topup_for_intervals(
@@ -53,28 +70,13 @@ pub fn get_subscriptions_by_merchant(
     start: u32,
     limit: u32,
 ) -> Vec<Subscription> {
-    let key = DataKey::MerchantSubs(merchant);
-    let ids: Vec<u32> = env.storage().instance().get(&key).unwrap_or(Vec::new(env));
-
-    let len = ids.len();
-    if start >= len || limit == 0 {
-        return Vec::new(env);
-    }
-
-    let end = if start + limit > len {
-        len
-    } else {
-        start + limit
-    };
+    let ids = get_merchant_sub_ids(env, &merchant, start, limit);
 
     let mut result = Vec::new(env);
-    let mut i = start;
-    while i < end {
-        let sub_id = ids.get(i).unwrap();
-        if let Some(sub) = env.storage().instance().get::<u32, Subscription>(&sub_id) {
+    for sub_id in ids.iter() {
+        if let Some(sub) = load_subscription(env, sub_id) {
             result.push_back(sub);
         }
-        i += 1;
     }
     result
 }
@@ -83,23 +85,54 @@ pub fn get_subscriptions_by_merchant(
 ///
 /// Useful for dashboards and pagination metadata.
 pub fn get_merchant_subscription_count(env: &Env, merchant: Address) -> u32 {
-    let key = DataKey::MerchantSubs(merchant);
-    let ids: Vec<u32> = env.storage().instance().get(&key).unwrap_or(Vec::new(env));
-    ids.len()
+    get_merchant_sub_count(env, &merchant)
+}
+
+/// Returns the subscriptions currently on a plan, paginated by offset over the
+/// plan's index.
+///
+/// Same ordering and bounds as [`get_subscriptions_by_merchant`]. Includes
+/// subscriptions in every status, cancelled ones too. Index entries whose
+/// subscription has moved to other terms are skipped, so a page may hold
+/// fewer than `limit` subscriptions; continue from `start + limit`.
+pub fn get_subscriptions_by_plan(
+    env: &Env,
+    plan_id: u32,
+    start: u32,
+    limit: u32,
+) -> Vec<Subscription> {
+    let ids = get_plan_sub_ids(env, plan_id, start, limit);
+
+    let mut result = Vec::new(env);
+    for sub_id in ids.iter() {
+        if let Some(sub) = load_subscription(env, sub_id) {
+            if sub.plan_id == Some(plan_id) {
+                result.push_back(sub);
+            }
+        }
+    }
+    result
+}
+
+/// Returns the number of entries in a plan's index: every subscription that
+/// has been on the plan, including ones that have since moved away.
+pub fn get_plan_subscription_count(env: &Env, plan_id: u32) -> u32 {
+    get_plan_sub_count(env, plan_id)
 }
 
 /// Computes the estimated next charge timestamp for a subscription.
 ///
 /// This is a readonly helper that does not mutate contract state. It provides
-/// information for off-chain scheduling systems and UX displays.
-pub fn compute_next_charge_info(subscription: &Subscription) -> NextChargeInfo {
-    let next_charge_timestamp = subscription
-        .last_payment_timestamp
-        .saturating_add(subscription.interval_seconds);
+/// information for off-chain scheduling systems and UX displays. `in_trial`
+/// is evaluated at the current ledger time.
+pub fn compute_next_charge_info(env: &Env, subscription: &Subscription) -> NextChargeInfo {
+    let next_charge_timestamp = crate::calendar::next_due(subscription);
 
     let is_charge_expected = match subscription.status {
         SubscriptionStatus::Active => true,
         SubscriptionStatus::InsufficientBalance => true,
+        SubscriptionStatus::PastDue => true,
+        SubscriptionStatus::Suspended => false,
         SubscriptionStatus::Paused => false,
         SubscriptionStatus::Cancelled => false,
     };
@@ -107,6 +140,8 @@ pub fn compute_next_charge_info(subscription: &Subscription) -> NextChargeInfo {
     NextChargeInfo {
         next_charge_timestamp,
         is_charge_expected,
+        in_trial: crate::trial::is_in_trial(env, subscription),
+        trial_ends_at: subscription.trial_ends_at,
     }
 }
 
@@ -140,9 +175,11 @@ pub struct SubscriptionsPage {
 /// - `has_next`: True if there are more subscriptions after the last returned ID
 ///
 /// # Performance Notes
-/// - Time complexity: O(n) where n = total number of subscriptions in the contract
+/// - Served from the subscriber's paged index (`SubscriberSubsPage`, maintained on
+///   creation): O(log(k / 64)) page reads to find `start_from_id` among the
+///   subscriber's k IDs, then the pages holding the next `limit` IDs —
+///   independent of the total number of subscriptions in the contract
 /// - Space complexity: O(limit)
-/// - On-chain storage usage is minimal (only subscription objects are stored)
 /// - Suitable for off-chain indexers and UI pagination
 ///
 /// # Pagination Example
@@ -166,49 +203,11 @@ pub fn list_subscriptions_by_subscriber(
         return Err(Error::NotFound);
     }
 
-    // Get the next_id counter to determine the range of valid subscription IDs
-    let next_id_key = Symbol::new(env, "next_id");
-    let next_id: u32 = env.storage().instance().get(&next_id_key).unwrap_or(0);
-
-    let mut subscription_ids = Vec::new(env);
-    let mut count = 0u32;
-    let mut last_found_id = start_from_id;
-
-    // Iterate through all subscription IDs from start_from_id (inclusive) and filter by subscriber
-    for id in start_from_id..next_id {
-        match env.storage().instance().get::<u32, Subscription>(&id) {
-            Some(sub) => {
-                if sub.subscriber == subscriber {
-                    subscription_ids.push_back(id);
-                    count += 1;
-                    last_found_id = id;
-                    if count >= limit {
-                        break;
-                    }
-                }
-            }
-            None => {
-                // Subscription was deleted or ID skipped; continue to next
-            }
-        }
-    }
-
-    // Determine if there are more subscriptions by checking beyond the last found
-    let has_next = if count >= limit {
-        // We hit the limit; check if there is at least one more subscriber match
-        let mut found_next = false;
-        for id in (last_found_id + 1)..next_id {
-            if let Some(sub) = env.storage().instance().get::<u32, Subscription>(&id) {
-                if sub.subscriber == subscriber {
-                    found_next = true;
-                    break;
-                }
-            }
-        }
-        found_next
-    } else {
-        false
-    };
+    // The index is in ascending ID order, so locate the first ID >= start_from_id
+    // and read at most `limit` entries from there.
+    let start = find_subscriber_sub(env, &subscriber, start_from_id);
+    let subscription_ids = get_subscriber_sub_ids(env, &subscriber, start, limit);
+    let has_next = start + subscription_ids.len() < get_subscriber_sub_count(env, &subscriber);
 
     Ok(SubscriptionsPage {
         subscription_ids,
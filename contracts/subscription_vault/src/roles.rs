@@ -0,0 +1,87 @@
+//! Role-based access control: grant, revoke and check roles.
+//!
+//! **PRs that only change who may call privileged entrypoints should edit this file only.**
+//!
+//! The stored admin holds [`Role::Admin`] and implicitly satisfies every other
+//! role check. All other roles are explicit membership lists keyed by
+//! [`DataKey::RoleMembers`].
+
+use crate::admin::require_admin;
+use crate::storage::{load_entry, save_entry};
+use crate::types::{DataKey, Error, Role};
+use soroban_sdk::{Address, Env, Symbol, Vec};
+
+fn load_members(env: &Env, role: Role) -> Vec<Address> {
+    load_entry(env, &DataKey::RoleMembers(role)).unwrap_or(Vec::new(env))
+}
+
+/// Whether `account` holds `role`, either explicitly or as the admin.
+pub fn has_role(env: &Env, role: Role, account: &Address) -> bool {
+    if require_admin(env).is_ok_and(|admin| admin == *account) {
+        return true;
+    }
+    if role == Role::Admin {
+        return false;
+    }
+    load_members(env, role).contains(account)
+}
+
+/// Require `account` to authorize and to hold `role`.
+pub fn require_role(env: &Env, role: Role, account: &Address) -> Result<(), Error> {
+    account.require_auth();
+    if !has_role(env, role, account) {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Addresses holding `role`. For [`Role::Admin`] this is the stored admin.
+pub fn get_role_members(env: &Env, role: Role) -> Vec<Address> {
+    if role == Role::Admin {
+        let mut members = Vec::new(env);
+        if let Ok(admin) = require_admin(env) {
+            members.push_back(admin);
+        }
+        return members;
+    }
+    load_members(env, role)
+}
+
+pub fn do_grant_role(env: &Env, admin: Address, role: Role, account: Address) -> Result<(), Error> {
+    require_role(env, Role::Admin, &admin)?;
+    if role == Role::Admin {
+        return Err(Error::InvalidRole);
+    }
+
+    let mut members = load_members(env, role);
+    if !members.contains(&account) {
+        members.push_back(account.clone());
+        save_entry(env, &DataKey::RoleMembers(role), &members);
+    }
+
+    env.events()
+        .publish((Symbol::new(env, "role_granted"), role, account), admin);
+    Ok(())
+}
+
+pub fn do_revoke_role(
+    env: &Env,
+    admin: Address,
+    role: Role,
+    account: Address,
+) -> Result<(), Error> {
+    require_role(env, Role::Admin, &admin)?;
+    if role == Role::Admin {
+        return Err(Error::InvalidRole);
+    }
+
+    let mut members = load_members(env, role);
+    if let Some(idx) = members.first_index_of(&account) {
+        members.remove(idx);
+        save_entry(env, &DataKey::RoleMembers(role), &members);
+    }
+
+    env.events()
+        .publish((Symbol::new(env, "role_revoked"), role, account), admin);
+    Ok(())
+}
@@ -3,7 +3,8 @@
 //! Kept in a separate module so PRs touching state transitions do not conflict
 //! with PRs touching billing, batch charge, or top-up estimation.
 
-use crate::types::{Error, SubscriptionStatus};
+use crate::types::{Error, Subscription, SubscriptionStatus};
+use soroban_sdk::Address;
 
 /// Validates if a status transition is allowed by the state machine.
 ///
@@ -16,8 +17,14 @@ use crate::types::{Error, SubscriptionStatus};
 /// | Active            | InsufficientBalance | Yes     |
 /// | Paused            | Active              | Yes     |
 /// | Paused            | Cancelled           | Yes     |
+/// | Active            | PastDue             | Yes     |
 /// | InsufficientBalance | Active            | Yes     |
 /// | InsufficientBalance | Cancelled         | Yes     |
+/// | PastDue           | Active              | Yes     |
+/// | PastDue           | Suspended           | Yes     |
+/// | PastDue           | Cancelled           | Yes     |
+/// | Suspended         | Active              | Yes     |
+/// | Suspended         | Cancelled           | Yes     |
 /// | Cancelled         | *any*               | No      |
 /// | *any*             | Same status         | Yes (idempotent) |
 ///
@@ -42,6 +49,7 @@ pub fn validate_status_transition(
             SubscriptionStatus::Paused
                 | SubscriptionStatus::Cancelled
                 | SubscriptionStatus::InsufficientBalance
+                | SubscriptionStatus::PastDue
         ),
         SubscriptionStatus::Paused => {
             matches!(
@@ -50,12 +58,18 @@ pub fn validate_status_transition(
             )
         }
         SubscriptionStatus::Cancelled => false,
-        SubscriptionStatus::InsufficientBalance => {
+        SubscriptionStatus::InsufficientBalance | SubscriptionStatus::Suspended => {
             matches!(
                 to,
                 SubscriptionStatus::Active | SubscriptionStatus::Cancelled
             )
         }
+        SubscriptionStatus::PastDue => matches!(
+            to,
+            SubscriptionStatus::Active
+                | SubscriptionStatus::Suspended
+                | SubscriptionStatus::Cancelled
+        ),
     };
 
     if valid {
@@ -74,12 +88,18 @@ pub fn get_allowed_transitions(status: &SubscriptionStatus) -> &'static [Subscri
             SubscriptionStatus::Paused,
             SubscriptionStatus::Cancelled,
             SubscriptionStatus::InsufficientBalance,
+            SubscriptionStatus::PastDue,
         ],
         SubscriptionStatus::Paused => &[SubscriptionStatus::Active, SubscriptionStatus::Cancelled],
         SubscriptionStatus::Cancelled => &[],
-        SubscriptionStatus::InsufficientBalance => {
+        SubscriptionStatus::InsufficientBalance | SubscriptionStatus::Suspended => {
             &[SubscriptionStatus::Active, SubscriptionStatus::Cancelled]
         }
+        SubscriptionStatus::PastDue => &[
+            SubscriptionStatus::Active,
+            SubscriptionStatus::Suspended,
+            SubscriptionStatus::Cancelled,
+        ],
     }
 }
 
@@ -89,3 +109,94 @@ pub fn get_allowed_transitions(status: &SubscriptionStatus) -> &'static [Subscri
 pub fn can_transition(from: &SubscriptionStatus, to: &SubscriptionStatus) -> bool {
     validate_status_transition(from, to).is_ok()
 }
+
+/// Party requesting a status transition.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Actor {
+    /// The subscription's subscriber.
+    Subscriber,
+    /// The subscription's merchant.
+    Merchant,
+    /// The contract itself, e.g. the charge path on a failed debit.
+    System,
+}
+
+/// Resolves `caller` to its party on `sub`, or `None` if it is neither the
+/// subscriber nor the merchant.
+pub fn actor_for(sub: &Subscription, caller: &Address) -> Option<Actor> {
+    if *caller == sub.subscriber {
+        Some(Actor::Subscriber)
+    } else if *caller == sub.merchant {
+        Some(Actor::Merchant)
+    } else {
+        None
+    }
+}
+
+/// Whether `actor` may move a subscription from `from` to `to`.
+///
+/// Only transitions allowed by [`validate_status_transition`] are considered.
+///
+/// # Permission Matrix
+///
+/// | From                | To                  | Subscriber | Merchant | System |
+/// |---------------------|---------------------|------------|----------|--------|
+/// | Active              | Paused              | Yes        | Yes      | No     |
+/// | Active              | Cancelled           | Yes        | Yes      | No     |
+/// | Active              | InsufficientBalance | No         | No       | Yes    |
+/// | Paused              | Active              | Yes        | Yes      | No     |
+/// | Paused              | Cancelled           | Yes        | Yes      | No     |
+/// | InsufficientBalance | Active              | Yes        | No       | Yes    |
+/// | InsufficientBalance | Cancelled           | Yes        | Yes      | No     |
+/// | Active              | PastDue             | No         | No       | Yes    |
+/// | PastDue             | Active              | Yes        | No       | Yes    |
+/// | PastDue             | Suspended           | No         | No       | Yes    |
+/// | PastDue             | Cancelled           | Yes        | Yes      | Yes    |
+/// | Suspended           | Active              | Yes        | No       | Yes    |
+/// | Suspended           | Cancelled           | Yes        | Yes      | No     |
+/// | *any*               | Same status         | Yes        | Yes      | Yes    |
+///
+/// A merchant cannot reactivate a subscription that ran out of funds: only the
+/// subscriber or the contract may do so. `resume_subscription` additionally
+/// requires the balance to cover the next charge. Dunning statuses are
+/// entered only by the contract, which may also cancel a past-due subscription
+/// when the merchant's [`DunningPolicy`](crate::types::DunningPolicy) says so.
+pub fn can_actor_transition(
+    actor: Actor,
+    from: &SubscriptionStatus,
+    to: &SubscriptionStatus,
+) -> bool {
+    if !can_transition(from, to) {
+        return false;
+    }
+    if from == to {
+        return true;
+    }
+    match to {
+        SubscriptionStatus::InsufficientBalance
+        | SubscriptionStatus::PastDue
+        | SubscriptionStatus::Suspended => actor == Actor::System,
+        SubscriptionStatus::Active if *from != SubscriptionStatus::Paused => {
+            matches!(actor, Actor::Subscriber | Actor::System)
+        }
+        SubscriptionStatus::Cancelled if *from == SubscriptionStatus::PastDue => true,
+        _ => matches!(actor, Actor::Subscriber | Actor::Merchant),
+    }
+}
+
+/// Validates a transition requested by `actor`.
+///
+/// # Returns
+/// * `Err(Error::InvalidStatusTransition)` if the state machine forbids it
+/// * `Err(Error::Unauthorized)` if `actor` may not perform it
+pub fn validate_actor_transition(
+    actor: Actor,
+    from: &SubscriptionStatus,
+    to: &SubscriptionStatus,
+) -> Result<(), Error> {
+    validate_status_transition(from, to)?;
+    if !can_actor_transition(actor, from, to) {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
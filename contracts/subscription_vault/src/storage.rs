@@ -0,0 +1,379 @@
+//! Persistent per-subscription storage and TTL management.
+//!
+//! **PRs that only change where or how long subscription data is stored should edit this file only.**
+//!
+//! Subscriptions, their replay-protection keys, the merchant/subscriber indexes and
+//! every per-merchant, per-role or per-proposal record grow with usage, so they live
+//! in persistent storage (one entry each) rather than in instance storage, which is
+//! loaded in full on every call and size-capped. Instance storage keeps fixed-size
+//! singletons only.
+//! Every read or write of an entry extends its TTL; `extend_ttl` lets keepers
+//! keep dormant subscriptions alive.
+
+use crate::types::{
+    DataKey, DunningRecord, Error, PaidThrough, PendingPlanChange, Plan, Subscription,
+};
+use soroban_sdk::{Address, BytesN, Env, IntoVal, TryFromVal, Val, Vec};
+
+/// Approximate ledgers per day (5 second close time).
+pub const DAY_IN_LEDGERS: u32 = 17_280;
+/// TTL, in ledgers, that touched entries are extended to.
+pub const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+/// Entries are only extended once their remaining TTL falls below this.
+pub const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+/// Subscription IDs per merchant, subscriber or plan index page. Bounds the size
+/// of every index entry and the cost of each write, however many subscriptions
+/// an index holds.
+pub const MERCHANT_SUBS_PAGE_SIZE: u32 = 64;
+
+/// Extend the TTL of the contract instance (config, totals, council).
+pub fn bump_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+}
+
+fn bump(env: &Env, key: &DataKey) {
+    env.storage().persistent().extend_ttl(
+        key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
+
+fn bump_if_present(env: &Env, key: &DataKey) {
+    if env.storage().persistent().has(key) {
+        bump(env, key);
+    }
+}
+
+/// Read a persistent entry, extending its TTL when present. Every persistent
+/// read and write below goes through this and [`save_entry`], so the TTL policy
+/// lives in one place.
+pub fn load_entry<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        bump(env, key);
+    }
+    value
+}
+
+pub fn save_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    env.storage().persistent().set(key, value);
+    bump(env, key);
+}
+
+pub fn load_subscription(env: &Env, subscription_id: u32) -> Option<Subscription> {
+    load_entry(env, &DataKey::Subscription(subscription_id))
+}
+
+pub fn save_subscription(env: &Env, subscription_id: u32, sub: &Subscription) {
+    save_entry(env, &DataKey::Subscription(subscription_id), sub);
+}
+
+/// Last billing period index charged for a subscription (replay protection).
+pub fn get_charged_period(env: &Env, subscription_id: u32) -> Option<u64> {
+    load_entry(env, &DataKey::ChargedPeriod(subscription_id))
+}
+
+pub fn set_charged_period(env: &Env, subscription_id: u32, period_index: u64) {
+    save_entry(env, &DataKey::ChargedPeriod(subscription_id), &period_index);
+}
+
+/// Forget the charged period after the schedule is re-anchored by a plan change,
+/// so period numbers restart from the new anchor.
+pub fn clear_charged_period(env: &Env, subscription_id: u32) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::ChargedPeriod(subscription_id));
+}
+
+/// Idempotency key of the last successful charge for a subscription.
+pub fn get_idempotency_key(env: &Env, subscription_id: u32) -> Option<BytesN<32>> {
+    load_entry(env, &DataKey::IdempotencyKey(subscription_id))
+}
+
+pub fn set_idempotency_key(env: &Env, subscription_id: u32, idempotency_key: &BytesN<32>) {
+    save_entry(
+        env,
+        &DataKey::IdempotencyKey(subscription_id),
+        idempotency_key,
+    );
+}
+
+/// Dunning progress of a past-due or suspended subscription.
+pub fn get_dunning_record(env: &Env, subscription_id: u32) -> Option<DunningRecord> {
+    load_entry(env, &DataKey::Dunning(subscription_id))
+}
+
+pub fn set_dunning_record(env: &Env, subscription_id: u32, record: &DunningRecord) {
+    save_entry(env, &DataKey::Dunning(subscription_id), record);
+}
+
+pub fn clear_dunning_record(env: &Env, subscription_id: u32) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Dunning(subscription_id));
+}
+
+/// Plan change scheduled for the subscription's next period boundary.
+pub fn get_pending_plan_change(env: &Env, subscription_id: u32) -> Option<PendingPlanChange> {
+    load_entry(env, &DataKey::PendingPlanChange(subscription_id))
+}
+
+pub fn set_pending_plan_change(env: &Env, subscription_id: u32, change: &PendingPlanChange) {
+    save_entry(env, &DataKey::PendingPlanChange(subscription_id), change);
+}
+
+pub fn clear_pending_plan_change(env: &Env, subscription_id: u32) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::PendingPlanChange(subscription_id));
+}
+
+/// How far the subscription's charges have paid; `None` before the first charge.
+pub fn get_paid_through(env: &Env, subscription_id: u32) -> Option<PaidThrough> {
+    load_entry(env, &DataKey::PaidThrough(subscription_id))
+}
+
+pub fn set_paid_through(env: &Env, subscription_id: u32, paid: &PaidThrough) {
+    save_entry(env, &DataKey::PaidThrough(subscription_id), paid);
+}
+
+/// Trials `subscriber` has started with `merchant`.
+pub fn get_trials_used(env: &Env, merchant: &Address, subscriber: &Address) -> u32 {
+    load_entry(
+        env,
+        &DataKey::TrialsUsed(merchant.clone(), subscriber.clone()),
+    )
+    .unwrap_or(0)
+}
+
+pub fn set_trials_used(env: &Env, merchant: &Address, subscriber: &Address, used: u32) {
+    save_entry(
+        env,
+        &DataKey::TrialsUsed(merchant.clone(), subscriber.clone()),
+        &used,
+    );
+}
+
+/// A paged, append-only list of subscription IDs (merchant, subscriber and plan indexes).
+enum SubIndex<'a> {
+    Merchant(&'a Address),
+    Subscriber(&'a Address),
+    Plan(u32),
+}
+
+impl SubIndex<'_> {
+    fn count_key(&self) -> DataKey {
+        match self {
+            SubIndex::Merchant(merchant) => DataKey::MerchantSubCount((*merchant).clone()),
+            SubIndex::Subscriber(subscriber) => DataKey::SubscriberSubCount((*subscriber).clone()),
+            SubIndex::Plan(plan_id) => DataKey::PlanSubCount(*plan_id),
+        }
+    }
+
+    fn page_key(&self, page: u32) -> DataKey {
+        match self {
+            SubIndex::Merchant(merchant) => DataKey::MerchantSubsPage((*merchant).clone(), page),
+            SubIndex::Subscriber(subscriber) => {
+                DataKey::SubscriberSubsPage((*subscriber).clone(), page)
+            }
+            SubIndex::Plan(plan_id) => DataKey::PlanSubsPage(*plan_id, page),
+        }
+    }
+}
+
+fn load_index_page(env: &Env, index: &SubIndex, page: u32) -> Vec<u32> {
+    load_entry(env, &index.page_key(page)).unwrap_or_else(|| Vec::new(env))
+}
+
+fn index_count(env: &Env, index: &SubIndex) -> u32 {
+    load_entry(env, &index.count_key()).unwrap_or(0)
+}
+
+/// Up to `limit` IDs of `index`, in creation order, starting at offset `start`.
+/// Only the pages overlapping the range are read.
+fn index_ids(env: &Env, index: &SubIndex, start: u32, limit: u32) -> Vec<u32> {
+    let mut ids = Vec::new(env);
+    let count = index_count(env, index);
+    if start >= count || limit == 0 {
+        return ids;
+    }
+    let end = start.saturating_add(limit).min(count);
+
+    let mut pos = start;
+    while pos < end {
+        let page_index = pos / MERCHANT_SUBS_PAGE_SIZE;
+        let page = load_index_page(env, index, page_index);
+        let page_start = page_index * MERCHANT_SUBS_PAGE_SIZE;
+        let page_end = (page_start + page.len()).min(end);
+        for offset in (pos - page_start)..(page_end - page_start) {
+            ids.push_back(page.get_unchecked(offset));
+        }
+        pos = page_start + MERCHANT_SUBS_PAGE_SIZE;
+    }
+    ids
+}
+
+/// Append `subscription_id` to the last page of `index`, opening a new page
+/// once the current one holds [`MERCHANT_SUBS_PAGE_SIZE`] IDs. Returns the page index.
+fn index_push(env: &Env, index: &SubIndex, subscription_id: u32) -> u32 {
+    let count = index_count(env, index);
+    let page_index = count / MERCHANT_SUBS_PAGE_SIZE;
+    let mut page = load_index_page(env, index, page_index);
+    page.push_back(subscription_id);
+    save_entry(env, &index.page_key(page_index), &page);
+    save_entry(env, &index.count_key(), &(count + 1));
+    page_index
+}
+
+/// Offset in `index` of the first ID `>= subscription_id` (the count if none).
+///
+/// Only for indexes filled in ID order (merchant and subscriber indexes, pushed
+/// at creation with sequential IDs): pages are sorted, so a binary search on
+/// each page's first ID finds the right one in O(log pages) reads. The page
+/// holding `subscription_id` is read last, which extends its TTL.
+fn index_lower_bound(env: &Env, index: &SubIndex, subscription_id: u32) -> u32 {
+    let count = index_count(env, index);
+    if count == 0 {
+        return 0;
+    }
+    let mut lo = 0u32;
+    let mut hi = (count - 1) / MERCHANT_SUBS_PAGE_SIZE;
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        match load_index_page(env, index, mid).first() {
+            Some(first) if first <= subscription_id => lo = mid,
+            _ => hi = mid - 1,
+        }
+    }
+    let page = load_index_page(env, index, lo);
+    let offset = match page.binary_search(subscription_id) {
+        Ok(offset) | Err(offset) => offset,
+    };
+    lo * MERCHANT_SUBS_PAGE_SIZE + offset
+}
+
+/// Number of subscriptions in `merchant`'s index.
+pub fn get_merchant_sub_count(env: &Env, merchant: &Address) -> u32 {
+    index_count(env, &SubIndex::Merchant(merchant))
+}
+
+/// Up to `limit` of `merchant`'s subscription IDs, in creation order, starting
+/// at offset `start`.
+pub fn get_merchant_sub_ids(env: &Env, merchant: &Address, start: u32, limit: u32) -> Vec<u32> {
+    index_ids(env, &SubIndex::Merchant(merchant), start, limit)
+}
+
+pub fn push_merchant_sub(env: &Env, merchant: &Address, subscription_id: u32) {
+    index_push(env, &SubIndex::Merchant(merchant), subscription_id);
+}
+
+/// Number of entries in `plan_id`'s index: every subscription that has been
+/// on the plan, including ones that have since moved to other terms.
+pub fn get_plan_sub_count(env: &Env, plan_id: u32) -> u32 {
+    index_count(env, &SubIndex::Plan(plan_id))
+}
+
+/// Up to `limit` entries of `plan_id`'s index, in the order subscriptions
+/// joined, starting at offset `start`.
+pub fn get_plan_sub_ids(env: &Env, plan_id: u32, start: u32, limit: u32) -> Vec<u32> {
+    index_ids(env, &SubIndex::Plan(plan_id), start, limit)
+}
+
+/// Add `subscription_id` to `plan_id`'s index, unless it is already there (a
+/// subscription returning to a plan keeps its original entry).
+pub fn push_plan_sub(env: &Env, plan_id: u32, subscription_id: u32) {
+    let entry = DataKey::PlanSubEntry(plan_id, subscription_id);
+    if load_entry::<u32>(env, &entry).is_some() {
+        return;
+    }
+    let page = index_push(env, &SubIndex::Plan(plan_id), subscription_id);
+    save_entry(env, &entry, &page);
+}
+
+/// Extend the TTL of the page of `plan_id`'s index holding `subscription_id`,
+/// found through its [`DataKey::PlanSubEntry`] (plan pages are not sorted).
+fn bump_plan_page_of(env: &Env, plan_id: u32, subscription_id: u32) {
+    if let Some(page) = load_entry::<u32>(env, &DataKey::PlanSubEntry(plan_id, subscription_id)) {
+        // Reading the page extends its TTL.
+        load_index_page(env, &SubIndex::Plan(plan_id), page);
+    }
+}
+
+/// Plan record, if it exists.
+pub fn load_plan(env: &Env, plan_id: u32) -> Option<Plan> {
+    load_entry(env, &DataKey::Plan(plan_id))
+}
+
+pub fn save_plan(env: &Env, plan_id: u32, plan: &Plan) {
+    save_entry(env, &DataKey::Plan(plan_id), plan);
+}
+
+/// Plan IDs created by `merchant`, in creation order.
+pub fn get_merchant_plans(env: &Env, merchant: &Address) -> Vec<u32> {
+    load_entry(env, &DataKey::MerchantPlans(merchant.clone())).unwrap_or_else(|| Vec::new(env))
+}
+
+pub fn push_merchant_plan(env: &Env, merchant: &Address, plan_id: u32) {
+    let mut ids = get_merchant_plans(env, merchant);
+    ids.push_back(plan_id);
+    save_entry(env, &DataKey::MerchantPlans(merchant.clone()), &ids);
+}
+
+/// Number of subscriptions in `subscriber`'s index.
+pub fn get_subscriber_sub_count(env: &Env, subscriber: &Address) -> u32 {
+    index_count(env, &SubIndex::Subscriber(subscriber))
+}
+
+/// Offset in `subscriber`'s index of their first subscription with an ID
+/// `>= subscription_id`.
+pub fn find_subscriber_sub(env: &Env, subscriber: &Address, subscription_id: u32) -> u32 {
+    index_lower_bound(env, &SubIndex::Subscriber(subscriber), subscription_id)
+}
+
+/// Up to `limit` of `subscriber`'s subscription IDs, in creation (ascending ID)
+/// order, starting at offset `start`.
+pub fn get_subscriber_sub_ids(env: &Env, subscriber: &Address, start: u32, limit: u32) -> Vec<u32> {
+    index_ids(env, &SubIndex::Subscriber(subscriber), start, limit)
+}
+
+pub fn push_subscriber_sub(env: &Env, subscriber: &Address, subscription_id: u32) {
+    index_push(env, &SubIndex::Subscriber(subscriber), subscription_id);
+}
+
+/// Extend the TTL of every entry belonging to the given subscriptions,
+/// including their merchant and subscriber index pages and merchant records. Fails with `NotFound` for unknown IDs.
+pub fn do_extend_ttl(env: &Env, subscription_ids: &Vec<u32>) -> Result<(), Error> {
+    for id in subscription_ids.iter() {
+        let sub = load_subscription(env, id).ok_or(Error::NotFound)?;
+        bump_if_present(env, &DataKey::ChargedPeriod(id));
+        bump_if_present(env, &DataKey::IdempotencyKey(id));
+        bump_if_present(env, &DataKey::Dunning(id));
+        bump_if_present(env, &DataKey::PendingPlanChange(id));
+        bump_if_present(env, &DataKey::PaidThrough(id));
+        index_lower_bound(env, &SubIndex::Merchant(&sub.merchant), id);
+        index_lower_bound(env, &SubIndex::Subscriber(&sub.subscriber), id);
+        if let Some(plan_id) = sub.plan_id {
+            bump_if_present(env, &DataKey::Plan(plan_id));
+            bump_plan_page_of(env, plan_id, id);
+        }
+        bump_if_present(
+            env,
+            &DataKey::TrialsUsed(sub.merchant.clone(), sub.subscriber.clone()),
+        );
+        bump_merchant_entries(env, &sub.merchant);
+    }
+    Ok(())
+}
+
+/// Extend the TTL of the merchant's earnings, config and billing policies.
+fn bump_merchant_entries(env: &Env, merchant: &Address) {
+    bump_if_present(env, &DataKey::MerchantBalance(merchant.clone()));
+    bump_if_present(env, &DataKey::MerchantConfig(merchant.clone()));
+    bump_if_present(env, &DataKey::DunningPolicy(merchant.clone()));
+    bump_if_present(env, &DataKey::CatchUpPolicy(merchant.clone()));
+    bump_if_present(env, &DataKey::TrialLimit(merchant.clone()));
+    bump_if_present(env, &DataKey::MaxTrialLength(merchant.clone()));
+}
@@ -2,10 +2,19 @@
 //!
 //! **PRs that only change subscription lifecycle or billing should edit this file only.**
 
+use crate::accounting::{adjust_total_prepaid, require_not_protective};
+use crate::calendar::{is_valid_anchor, NOMINAL_MONTH_SECONDS, NOMINAL_YEAR_SECONDS};
+use crate::events::publish_subscription_event;
 use crate::queries::get_subscription;
-use crate::state_machine::validate_status_transition;
-use crate::types::{DataKey, Error, Subscription, SubscriptionStatus};
-use soroban_sdk::{Address, Env, Symbol, Vec};
+use crate::state_machine::{actor_for, validate_actor_transition, Actor};
+use crate::storage::{push_merchant_sub, push_plan_sub, push_subscriber_sub, save_subscription};
+use crate::types::{
+    Error, FundsDepositedEvent, IntervalKind, SubscriberWithdrawalEvent, Subscription,
+    SubscriptionCancelledEvent, SubscriptionCreatedAndFundedEvent, SubscriptionCreatedEvent,
+    SubscriptionPausedEvent, SubscriptionReactivatedEvent, SubscriptionResumedEvent,
+    SubscriptionStatus, TrialStartedEvent, TrialTerms, EVENT_SCHEMA_VERSION,
+};
+use soroban_sdk::{Address, Env, Symbol};
 
 pub fn next_id(env: &Env) -> u32 {
     let key = Symbol::new(env, "next_id");
@@ -14,6 +23,21 @@ pub fn next_id(env: &Env) -> u32 {
     id
 }
 
+/// Check `amount` and `interval_seconds` against the global [`SubscriptionLimits`].
+pub fn validate_terms(env: &Env, amount: i128, interval_seconds: u64) -> Result<(), Error> {
+    let limits = crate::admin::get_subscription_limits(env);
+    if amount <= 0 || (limits.max_amount > 0 && amount > limits.max_amount) {
+        return Err(Error::InvalidAmount);
+    }
+    if interval_seconds == 0
+        || interval_seconds < limits.min_interval_seconds
+        || (limits.max_interval_seconds > 0 && interval_seconds > limits.max_interval_seconds)
+    {
+        return Err(Error::InvalidInterval);
+    }
+    Ok(())
+}
+
 pub fn do_create_subscription(
     env: &Env,
     subscriber: Address,
@@ -23,33 +47,336 @@ pub fn do_create_subscription(
     usage_enabled: bool,
 ) -> Result<u32, Error> {
     subscriber.require_auth();
+    let id = create_subscription_record(
+        env,
+        subscriber,
+        merchant,
+        amount,
+        interval_seconds,
+        usage_enabled,
+        IntervalKind::Seconds,
+        None,
+        None,
+    )?;
+    publish_created(env, id)?;
+    Ok(id)
+}
+
+/// Create a subscription that starts with a free trial.
+///
+/// `trial` is an end timestamp or a number of billing intervals; the trial must
+/// end in the future (`InvalidTrial`) and the subscriber must have trials left
+/// with this merchant (`TrialLimitReached`). Emits `created` then `trial_started`.
+#[allow(clippy::too_many_arguments)]
+pub fn do_create_subscription_with_trial(
+    env: &Env,
+    subscriber: Address,
+    merchant: Address,
+    amount: i128,
+    interval_seconds: u64,
+    usage_enabled: bool,
+    trial: TrialTerms,
+) -> Result<u32, Error> {
+    subscriber.require_auth();
+    let id = create_subscription_record(
+        env,
+        subscriber,
+        merchant,
+        amount,
+        interval_seconds,
+        usage_enabled,
+        IntervalKind::Seconds,
+        Some(trial),
+        None,
+    )?;
+    publish_created(env, id)?;
+    publish_trial_started(env, id)?;
+    Ok(id)
+}
+
+/// Subscribe to a merchant's plan on the plan's current terms.
+///
+/// Fails with `PlanArchived` if the plan no longer accepts subscribers. A plan
+/// trial is granted only while the subscriber has trials left with the merchant
+/// and the trial fits the merchant's maximum trial length; otherwise the
+/// subscription starts without one. Emits `created`, then
+/// `trial_started` when a trial was granted.
+pub fn do_subscribe_to_plan(env: &Env, subscriber: Address, plan_id: u32) -> Result<u32, Error> {
+    subscriber.require_auth();
+    let plan = crate::plans::get_active_plan(env, plan_id)?;
+    let trial = Some(TrialTerms::Periods(plan.trial_periods)).filter(|terms| {
+        plan.trial_periods > 0
+            && crate::trial::has_trial_left(env, &plan.merchant, &subscriber)
+            && crate::trial::trial_end(env, &plan.merchant, terms, plan.interval_seconds).is_ok()
+    });
+    let with_trial = trial.is_some();
+
+    let id = create_subscription_record(
+        env,
+        subscriber,
+        plan.merchant,
+        plan.amount,
+        plan.interval_seconds,
+        plan.usage_enabled,
+        IntervalKind::Seconds,
+        trial,
+        Some(plan_id),
+    )?;
+    publish_created(env, id)?;
+    if with_trial {
+        publish_trial_started(env, id)?;
+    }
+    Ok(id)
+}
+
+fn publish_trial_started(env: &Env, id: u32) -> Result<(), Error> {
+    let sub = get_subscription(env, id)?;
+    publish_subscription_event(
+        env,
+        "trial_started",
+        id,
+        &sub,
+        TrialStartedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id: id,
+            trial_ends_at: sub.trial_ends_at,
+        },
+    );
+    Ok(())
+}
+
+/// Create a subscription billed on calendar months or years (see [`IntervalKind`]).
+///
+/// `interval_kind` must be `Months` or `Years` with a valid anchor, otherwise
+/// `InvalidInterval`. The nominal length (30 or 365 days) is stored as
+/// `interval_seconds` and checked against the global interval limits.
+pub fn do_create_calendar_subscription(
+    env: &Env,
+    subscriber: Address,
+    merchant: Address,
+    amount: i128,
+    interval_kind: IntervalKind,
+    usage_enabled: bool,
+) -> Result<u32, Error> {
+    subscriber.require_auth();
+    let interval_seconds = match interval_kind {
+        IntervalKind::Months(_) => NOMINAL_MONTH_SECONDS,
+        IntervalKind::Years(_, _) => NOMINAL_YEAR_SECONDS,
+        IntervalKind::Seconds => return Err(Error::InvalidInterval),
+    };
+    if !is_valid_anchor(&interval_kind) {
+        return Err(Error::InvalidInterval);
+    }
+    let id = create_subscription_record(
+        env,
+        subscriber,
+        merchant,
+        amount,
+        interval_seconds,
+        usage_enabled,
+        interval_kind,
+        None,
+        None,
+    )?;
+    publish_created(env, id)?;
+    Ok(id)
+}
+
+fn publish_created(env: &Env, id: u32) -> Result<(), Error> {
+    let sub = get_subscription(env, id)?;
+    publish_subscription_event(
+        env,
+        "created",
+        id,
+        &sub,
+        SubscriptionCreatedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id: id,
+            subscriber: sub.subscriber.clone(),
+            merchant: sub.merchant.clone(),
+            amount: sub.amount,
+            interval_seconds: sub.interval_seconds,
+            usage_enabled: sub.usage_enabled,
+            interval_kind: sub.interval_kind.clone(),
+            plan_id: sub.plan_id,
+        },
+    );
+    Ok(())
+}
+
+/// Validate terms and store a new, unfunded subscription. Callers handle auth.
+#[allow(clippy::too_many_arguments)]
+fn create_subscription_record(
+    env: &Env,
+    subscriber: Address,
+    merchant: Address,
+    amount: i128,
+    interval_seconds: u64,
+    usage_enabled: bool,
+    interval_kind: IntervalKind,
+    trial: Option<TrialTerms>,
+    plan_id: Option<u32>,
+) -> Result<u32, Error> {
+    if subscriber == merchant {
+        return Err(Error::SelfSubscription);
+    }
+
+    let config = crate::merchant::get_merchant_config(env, &merchant);
+    let interval_seconds = if interval_seconds == 0 {
+        config.default_interval_seconds
+    } else {
+        interval_seconds
+    };
+    validate_terms(env, amount, interval_seconds)?;
+    config.check_amount(amount)?;
+
+    let now = env.ledger().timestamp();
+    // A trial anchors the schedule at its end: the first paid period starts there.
+    let trial_ends_at = match trial {
+        Some(terms) => {
+            crate::trial::consume_trial(env, &merchant, &subscriber)?;
+            crate::trial::trial_end(env, &merchant, &terms, interval_seconds)?
+        }
+        None => 0,
+    };
     let sub = Subscription {
         subscriber: subscriber.clone(),
         merchant: merchant.clone(),
         amount,
         interval_seconds,
-        last_payment_timestamp: env.ledger().timestamp(),
+        last_payment_timestamp: now,
         status: SubscriptionStatus::Active,
         prepaid_balance: 0i128,
         usage_enabled,
+        interval_kind,
+        billing_anchor: now.max(trial_ends_at),
+        trial_ends_at,
+        plan_id,
     };
     let id = next_id(env);
-    env.storage().instance().set(&id, &sub);
+    save_subscription(env, id, &sub);
 
-    // Maintain merchant → subscription-ID index
-    let key = DataKey::MerchantSubs(sub.merchant.clone());
-    let mut ids: Vec<u32> = env.storage().instance().get(&key).unwrap_or(Vec::new(env));
-    ids.push_back(id);
-    env.storage().instance().set(&key, &ids);
+    // Maintain merchant and subscriber → subscription-ID indexes
+    push_merchant_sub(env, &sub.merchant, id);
+    push_subscriber_sub(env, &sub.subscriber, id);
+    if let Some(plan_id) = plan_id {
+        push_plan_sub(env, plan_id, id);
+    }
 
     Ok(id)
 }
 
+/// Create a subscription and fund it from the subscriber in one call.
+///
+/// `initial_deposit` must meet `min_topup`. With `pay_upfront`, the first
+/// period is charged immediately and the next charge is due one interval later.
+#[allow(clippy::too_many_arguments)]
+pub fn do_create_subscription_with_deposit(
+    env: &Env,
+    subscriber: Address,
+    merchant: Address,
+    amount: i128,
+    interval_seconds: u64,
+    usage_enabled: bool,
+    initial_deposit: i128,
+    pay_upfront: bool,
+) -> Result<u32, Error> {
+    subscriber.require_auth();
+
+    let id = create_subscription_record(
+        env,
+        subscriber.clone(),
+        merchant.clone(),
+        amount,
+        interval_seconds,
+        usage_enabled,
+        IntervalKind::Seconds,
+        None,
+        None,
+    )?;
+
+    if initial_deposit <= 0 {
+        return Err(Error::InvalidAmount);
+    }
+    if initial_deposit < crate::admin::get_min_topup(env)? {
+        return Err(Error::BelowMinimumTopup);
+    }
+    if pay_upfront && initial_deposit < amount {
+        return Err(Error::InsufficientBalance);
+    }
+
+    let token_client = soroban_sdk::token::Client::new(env, &crate::admin::get_token(env)?);
+    token_client.transfer(
+        &subscriber,
+        &env.current_contract_address(),
+        &initial_deposit,
+    );
+
+    let mut sub = get_subscription(env, id)?;
+    sub.prepaid_balance = initial_deposit;
+    save_subscription(env, id, &sub);
+    adjust_total_prepaid(env, initial_deposit)?;
+
+    let upfront_charge = if pay_upfront {
+        crate::charge_core::charge_upfront(env, id)?
+    } else {
+        0
+    };
+
+    let sub = get_subscription(env, id)?;
+    publish_subscription_event(
+        env,
+        "created_funded",
+        id,
+        &sub,
+        SubscriptionCreatedAndFundedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id: id,
+            subscriber,
+            merchant,
+            amount: sub.amount,
+            interval_seconds: sub.interval_seconds,
+            initial_deposit,
+            upfront_charge,
+            prepaid_balance: sub.prepaid_balance,
+        },
+    );
+    Ok(id)
+}
+
+/// Top up a subscription's prepaid balance.
+///
+/// If the subscription is `InsufficientBalance` and the new balance covers one
+/// interval, it is moved back to `Active` (see [`reactivate_if_funded`]).
 pub fn do_deposit_funds(
     env: &Env,
     subscription_id: u32,
     subscriber: Address,
     amount: i128,
+) -> Result<(), Error> {
+    deposit(env, subscription_id, subscriber, amount)?;
+    reactivate_if_funded(env, subscription_id, false)?;
+    Ok(())
+}
+
+/// Like [`do_deposit_funds`], and when the deposit reactivates the subscription
+/// also collects the charge for the period that failed, if it is still due.
+pub fn do_deposit_funds_and_collect(
+    env: &Env,
+    subscription_id: u32,
+    subscriber: Address,
+    amount: i128,
+) -> Result<(), Error> {
+    deposit(env, subscription_id, subscriber, amount)?;
+    reactivate_if_funded(env, subscription_id, true)?;
+    Ok(())
+}
+
+fn deposit(
+    env: &Env,
+    subscription_id: u32,
+    subscriber: Address,
+    amount: i128,
 ) -> Result<(), Error> {
     subscriber.require_auth();
 
@@ -73,14 +400,73 @@ pub fn do_deposit_funds(
 
     token_client.transfer(&subscriber, &env.current_contract_address(), &amount);
 
-    env.storage().instance().set(&subscription_id, &sub);
-    env.events().publish(
-        (Symbol::new(env, "deposited"), subscription_id),
-        (subscriber, amount, sub.prepaid_balance),
+    save_subscription(env, subscription_id, &sub);
+    adjust_total_prepaid(env, amount)?;
+    publish_subscription_event(
+        env,
+        "deposited",
+        subscription_id,
+        &sub,
+        FundsDepositedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            subscriber,
+            amount,
+            prepaid_balance: sub.prepaid_balance,
+        },
     );
     Ok(())
 }
 
+/// Move an `InsufficientBalance`, `PastDue` or `Suspended` subscription back to
+/// `Active` once `prepaid_balance >= amount`, optionally charging the failed period.
+///
+/// Returns whether the subscription was reactivated. Emits `reactivated`.
+fn reactivate_if_funded(env: &Env, subscription_id: u32, collect: bool) -> Result<bool, Error> {
+    let mut sub = get_subscription(env, subscription_id)?;
+    let underfunded = matches!(
+        sub.status,
+        SubscriptionStatus::InsufficientBalance
+            | SubscriptionStatus::PastDue
+            | SubscriptionStatus::Suspended
+    );
+    if !underfunded || sub.prepaid_balance < sub.amount {
+        return Ok(false);
+    }
+
+    validate_actor_transition(Actor::System, &sub.status, &SubscriptionStatus::Active)?;
+    sub.status = SubscriptionStatus::Active;
+    save_subscription(env, subscription_id, &sub);
+    crate::dunning::clear_dunning(env, subscription_id);
+
+    let due = crate::catch_up::outstanding_periods(env, &sub)?;
+    let collectible = due.collectible > 0 && sub.prepaid_balance >= due.amount_due;
+    let collected = if collect
+        && collectible
+        && crate::charge_core::is_period_due(env, subscription_id, &sub)
+    {
+        crate::charge_core::charge_one(env, subscription_id, None)?;
+        due.amount_due
+    } else {
+        0
+    };
+
+    let sub = get_subscription(env, subscription_id)?;
+    publish_subscription_event(
+        env,
+        "reactivated",
+        subscription_id,
+        &sub,
+        SubscriptionReactivatedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            collected,
+            prepaid_balance: sub.prepaid_balance,
+        },
+    );
+    Ok(true)
+}
+
 pub fn do_cancel_subscription(
     env: &Env,
     subscription_id: u32,
@@ -89,15 +475,23 @@ pub fn do_cancel_subscription(
     authorizer.require_auth();
 
     let mut sub = get_subscription(env, subscription_id)?;
-
-    if authorizer != sub.subscriber && authorizer != sub.merchant {
-        return Err(Error::Unauthorized);
-    }
-
-    validate_status_transition(&sub.status, &SubscriptionStatus::Cancelled)?;
+    let actor = actor_for(&sub, &authorizer).ok_or(Error::Unauthorized)?;
+    validate_actor_transition(actor, &sub.status, &SubscriptionStatus::Cancelled)?;
     sub.status = SubscriptionStatus::Cancelled;
 
-    env.storage().instance().set(&subscription_id, &sub);
+    save_subscription(env, subscription_id, &sub);
+    publish_subscription_event(
+        env,
+        "cancelled",
+        subscription_id,
+        &sub,
+        SubscriptionCancelledEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            authorizer,
+            refund_amount: sub.prepaid_balance,
+        },
+    );
     Ok(())
 }
 
@@ -109,10 +503,22 @@ pub fn do_pause_subscription(
     authorizer.require_auth();
 
     let mut sub = get_subscription(env, subscription_id)?;
-    validate_status_transition(&sub.status, &SubscriptionStatus::Paused)?;
+    let actor = actor_for(&sub, &authorizer).ok_or(Error::Unauthorized)?;
+    validate_actor_transition(actor, &sub.status, &SubscriptionStatus::Paused)?;
     sub.status = SubscriptionStatus::Paused;
 
-    env.storage().instance().set(&subscription_id, &sub);
+    save_subscription(env, subscription_id, &sub);
+    publish_subscription_event(
+        env,
+        "paused",
+        subscription_id,
+        &sub,
+        SubscriptionPausedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            authorizer,
+        },
+    );
     Ok(())
 }
 
@@ -124,10 +530,34 @@ pub fn do_resume_subscription(
     authorizer.require_auth();
 
     let mut sub = get_subscription(env, subscription_id)?;
-    validate_status_transition(&sub.status, &SubscriptionStatus::Active)?;
+    let actor = actor_for(&sub, &authorizer).ok_or(Error::Unauthorized)?;
+    validate_actor_transition(actor, &sub.status, &SubscriptionStatus::Active)?;
+    // Leaving an underfunded status (and dunning) needs a balance that covers
+    // the next charge; otherwise resuming would restart the grace period for free.
+    let underfunded = matches!(
+        sub.status,
+        SubscriptionStatus::InsufficientBalance
+            | SubscriptionStatus::PastDue
+            | SubscriptionStatus::Suspended
+    );
+    if underfunded && sub.prepaid_balance < sub.amount {
+        return Err(Error::InsufficientBalance);
+    }
     sub.status = SubscriptionStatus::Active;
 
-    env.storage().instance().set(&subscription_id, &sub);
+    save_subscription(env, subscription_id, &sub);
+    crate::dunning::clear_dunning(env, subscription_id);
+    publish_subscription_event(
+        env,
+        "resumed",
+        subscription_id,
+        &sub,
+        SubscriptionResumedEvent {
+            version: EVENT_SCHEMA_VERSION,
+            subscription_id,
+            authorizer,
+        },
+    );
     Ok(())
 }
 
@@ -147,11 +577,13 @@ pub fn do_withdraw_subscriber_funds(
     if sub.status != SubscriptionStatus::Cancelled {
         return Err(Error::InvalidStatusTransition); // Or Unauthorized/InvalidState
     }
+    require_not_protective(env)?;
 
     let amount_to_refund = sub.prepaid_balance;
     if amount_to_refund > 0 {
         sub.prepaid_balance = 0;
-        env.storage().instance().set(&subscription_id, &sub);
+        save_subscription(env, subscription_id, &sub);
+        adjust_total_prepaid(env, -amount_to_refund)?;
 
         let token_addr: Address = env
             .storage()
@@ -165,6 +597,19 @@ pub fn do_withdraw_subscriber_funds(
             &subscriber,
             &amount_to_refund,
         );
+
+        publish_subscription_event(
+            env,
+            "subscriber_withdrawn",
+            subscription_id,
+            &sub,
+            SubscriberWithdrawalEvent {
+                version: EVENT_SCHEMA_VERSION,
+                subscription_id,
+                subscriber,
+                amount: amount_to_refund,
+            },
+        );
     }
 
     Ok(())
@@ -1,7 +1,16 @@
+use crate::storage::{
+    DAY_IN_LEDGERS, MERCHANT_SUBS_PAGE_SIZE, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD,
+};
 use crate::{
-    can_transition, get_allowed_transitions, validate_status_transition, Error, RecoveryReason,
-    Subscription, SubscriptionStatus, SubscriptionVault, SubscriptionVaultClient,
+    can_actor_transition, can_transition, get_allowed_transitions, validate_actor_transition,
+    validate_status_transition, Actor, BatchChargeResult, CatchUpMode, CatchUpPolicy, Council,
+    DataKey, DunningEvent, DunningFinalAction, DunningPolicy, Error, GovernanceAction,
+    IntervalKind, Plan, PlanChangeTarget, PlanChangeTiming, PlanChangedEvent, PlanStatus,
+    RecoveryReason, Role, Subscription, SubscriptionCancelledEvent,
+    SubscriptionCreatedAndFundedEvent, SubscriptionLimits, SubscriptionStatus, SubscriptionVault,
+    SubscriptionVaultClient, TrialTerms, ADMIN_PROPOSAL_TTL_SECONDS, GOV_PROPOSAL_TTL_SECONDS,
 };
+use soroban_sdk::testutils::storage::Persistent as _;
 use soroban_sdk::testutils::{Address as _, Events as _, Ledger as _};
 use soroban_sdk::{Address, Env, IntoVal, Vec as SorobanVec};
 
@@ -166,10 +175,11 @@ fn test_can_transition_helper() {
 fn test_get_allowed_transitions() {
     // Active
     let active_targets = get_allowed_transitions(&SubscriptionStatus::Active);
-    assert_eq!(active_targets.len(), 3);
+    assert_eq!(active_targets.len(), 4);
     assert!(active_targets.contains(&SubscriptionStatus::Paused));
     assert!(active_targets.contains(&SubscriptionStatus::Cancelled));
     assert!(active_targets.contains(&SubscriptionStatus::InsufficientBalance));
+    assert!(active_targets.contains(&SubscriptionStatus::PastDue));
 
     // Paused
     let paused_targets = get_allowed_transitions(&SubscriptionStatus::Paused);
@@ -186,6 +196,118 @@ fn test_get_allowed_transitions() {
     assert_eq!(ib_targets.len(), 2);
     assert!(ib_targets.contains(&SubscriptionStatus::Active));
     assert!(ib_targets.contains(&SubscriptionStatus::Cancelled));
+
+    // PastDue
+    let past_due_targets = get_allowed_transitions(&SubscriptionStatus::PastDue);
+    assert_eq!(past_due_targets.len(), 3);
+    assert!(past_due_targets.contains(&SubscriptionStatus::Active));
+    assert!(past_due_targets.contains(&SubscriptionStatus::Suspended));
+    assert!(past_due_targets.contains(&SubscriptionStatus::Cancelled));
+
+    // Suspended
+    let suspended_targets = get_allowed_transitions(&SubscriptionStatus::Suspended);
+    assert_eq!(suspended_targets.len(), 2);
+    assert!(suspended_targets.contains(&SubscriptionStatus::Active));
+    assert!(suspended_targets.contains(&SubscriptionStatus::Cancelled));
+}
+
+#[test]
+fn test_validate_dunning_transitions() {
+    use SubscriptionStatus::*;
+    assert!(validate_status_transition(&Active, &PastDue).is_ok());
+    assert!(validate_status_transition(&PastDue, &Suspended).is_ok());
+    assert!(validate_status_transition(&PastDue, &Active).is_ok());
+    assert!(validate_status_transition(&Suspended, &Active).is_ok());
+    assert_eq!(
+        validate_status_transition(&Paused, &PastDue),
+        Err(Error::InvalidStatusTransition)
+    );
+    assert_eq!(
+        validate_status_transition(&Suspended, &PastDue),
+        Err(Error::InvalidStatusTransition)
+    );
+    assert_eq!(
+        validate_status_transition(&PastDue, &Paused),
+        Err(Error::InvalidStatusTransition)
+    );
+    // Only the billing engine drives a subscription into dunning states.
+    assert_eq!(
+        validate_actor_transition(Actor::Merchant, &PastDue, &Suspended),
+        Err(Error::Unauthorized)
+    );
+    assert!(validate_actor_transition(Actor::Subscriber, &PastDue, &Cancelled).is_ok());
+}
+
+#[test]
+fn test_actor_permission_matrix() {
+    use SubscriptionStatus::*;
+
+    // Parties may pause, resume from Paused and cancel.
+    for actor in [Actor::Subscriber, Actor::Merchant] {
+        assert!(can_actor_transition(actor, &Active, &Paused));
+        assert!(can_actor_transition(actor, &Active, &Cancelled));
+        assert!(can_actor_transition(actor, &Paused, &Active));
+        assert!(can_actor_transition(actor, &Paused, &Cancelled));
+        assert!(can_actor_transition(
+            actor,
+            &InsufficientBalance,
+            &Cancelled
+        ));
+        assert!(!can_actor_transition(actor, &Active, &InsufficientBalance));
+    }
+
+    // Only the subscriber (or the contract) may reactivate an unfunded subscription.
+    assert!(can_actor_transition(
+        Actor::Subscriber,
+        &InsufficientBalance,
+        &Active
+    ));
+    assert!(!can_actor_transition(
+        Actor::Merchant,
+        &InsufficientBalance,
+        &Active
+    ));
+    assert!(can_actor_transition(
+        Actor::System,
+        &InsufficientBalance,
+        &Active
+    ));
+
+    // The charge path is the only one that marks a subscription unfunded.
+    assert!(can_actor_transition(
+        Actor::System,
+        &Active,
+        &InsufficientBalance
+    ));
+    assert!(!can_actor_transition(Actor::System, &Active, &Paused));
+
+    // Same-status is idempotent for everyone; the state machine still applies.
+    assert!(can_actor_transition(Actor::Merchant, &Paused, &Paused));
+    assert!(!can_actor_transition(
+        Actor::Subscriber,
+        &Cancelled,
+        &Active
+    ));
+}
+
+#[test]
+fn test_validate_actor_transition_errors() {
+    assert_eq!(
+        validate_actor_transition(
+            Actor::Merchant,
+            &SubscriptionStatus::InsufficientBalance,
+            &SubscriptionStatus::Active
+        ),
+        Err(Error::Unauthorized)
+    );
+    assert_eq!(
+        validate_actor_transition(
+            Actor::Subscriber,
+            &SubscriptionStatus::Cancelled,
+            &SubscriptionStatus::Active
+        ),
+        Err(Error::InvalidStatusTransition)
+    );
 }
 
 // =============================================================================
@@ -236,7 +358,7 @@ fn create_test_subscription(
         let mut sub = client.get_subscription(&id);
         sub.status = status;
         env.as_contract(&client.address, || {
-            env.storage().instance().set(&id, &sub);
+            crate::storage::save_subscription(env, id, &sub);
         });
     }
 
@@ -377,6 +499,95 @@ fn test_state_transition_idempotent_same_status() {
     assert_eq!(sub.status, SubscriptionStatus::Cancelled);
 }
 
+// =============================================================================
+// Lifecycle Authorization Tests
+// =============================================================================
+
+#[test]
+fn test_pause_and_resume_reject_third_party() {
+    let (env, client, _, _) = setup_test_env();
+    let (id, _, _) = create_test_subscription(&env, &client, SubscriptionStatus::Active);
+    let stranger = Address::generate(&env);
+
+    assert_eq!(
+        client.try_pause_subscription(&id, &stranger),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Active
+    );
+
+    let (id, _, _) = create_test_subscription(&env, &client, SubscriptionStatus::Paused);
+    assert_eq!(
+        client.try_resume_subscription(&id, &stranger),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Paused
+    );
+}
+
+#[test]
+fn test_merchant_can_pause_and_resume() {
+    let (env, client, _, _) = setup_test_env();
+    let (id, _, merchant) = create_test_subscription(&env, &client, SubscriptionStatus::Active);
+
+    client.pause_subscription(&id, &merchant);
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Paused
+    );
+    client.resume_subscription(&id, &merchant);
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Active
+    );
+}
+
+#[test]
+fn test_only_subscriber_resumes_from_insufficient_balance() {
+    let (env, client, _, _) = setup_test_env();
+    let (id, subscriber, merchant) =
+        create_test_subscription(&env, &client, SubscriptionStatus::InsufficientBalance);
+
+    assert_eq!(
+        client.try_resume_subscription(&id, &merchant),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::InsufficientBalance
+    );
+
+    // The subscriber needs a balance covering the next charge to resume.
+    assert_eq!(
+        client.try_resume_subscription(&id, &subscriber),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    let mut sub = client.get_subscription(&id);
+    sub.prepaid_balance = sub.amount;
+    env.as_contract(&client.address, || {
+        crate::storage::save_subscription(&env, id, &sub);
+    });
+
+    client.resume_subscription(&id, &subscriber);
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Active
+    );
+}
+
+#[test]
+fn test_pause_requires_authorizer_signature() {
+    let (env, client, _, _) = setup_test_env();
+    let (id, subscriber, _) = create_test_subscription(&env, &client, SubscriptionStatus::Active);
+
+    env.set_auths(&[]);
+    assert!(client.try_pause_subscription(&id, &subscriber).is_err());
+}
+
 // =============================================================================
 // Complex State Transition Sequences
 // =============================================================================
@@ -454,7 +665,7 @@ fn test_all_valid_transitions_coverage() {
         let mut sub = client.get_subscription(&id);
         sub.status = SubscriptionStatus::InsufficientBalance;
         env.as_contract(&client.address, || {
-            env.storage().instance().set(&id, &sub);
+            crate::storage::save_subscription(&env, id, &sub);
         });
 
         assert_eq!(
@@ -495,11 +706,12 @@ fn test_all_valid_transitions_coverage() {
         let (id, subscriber, _) =
             create_test_subscription(&env, &client, SubscriptionStatus::Active);
 
-        // Set to InsufficientBalance
+        // Set to InsufficientBalance, then top up (resuming needs funds)
         let mut sub = client.get_subscription(&id);
         sub.status = SubscriptionStatus::InsufficientBalance;
+        sub.prepaid_balance = sub.amount;
         env.as_contract(&client.address, || {
-            env.storage().instance().set(&id, &sub);
+            crate::storage::save_subscription(&env, id, &sub);
         });
 
         // Resume to Active
@@ -520,7 +732,7 @@ fn test_all_valid_transitions_coverage() {
         let mut sub = client.get_subscription(&id);
         sub.status = SubscriptionStatus::InsufficientBalance;
         env.as_contract(&client.address, || {
-            env.storage().instance().set(&id, &sub);
+            crate::storage::save_subscription(&env, id, &sub);
         });
 
         // Cancel
@@ -556,7 +768,7 @@ fn test_invalid_insufficient_balance_to_paused() {
     let mut sub = client.get_subscription(&id);
     sub.status = SubscriptionStatus::InsufficientBalance;
     env.as_contract(&client.address, || {
-        env.storage().instance().set(&id, &sub);
+        crate::storage::save_subscription(&env, id, &sub);
     });
 
     // Can't pause from InsufficientBalance - only resume to Active or cancel
@@ -576,6 +788,10 @@ fn test_subscription_struct_status_field() {
         status: SubscriptionStatus::Active,
         prepaid_balance: 500_000_000,
         usage_enabled: false,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: 0,
+        trial_ends_at: 0,
+        plan_id: None,
     };
     assert_eq!(sub.status, SubscriptionStatus::Active);
 }
@@ -611,6 +827,67 @@ fn test_init_and_struct() {
     // Basic initialization test
 }
 
+#[test]
+fn test_init_twice_fails() {
+    let (env, client, token, admin) = setup_test_env();
+    let attacker = Address::generate(&env);
+
+    assert_eq!(
+        client.try_init(&token, &attacker, &0i128),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+    assert_eq!(client.get_admin(), admin);
+    assert_eq!(client.get_min_topup(), 1_000000i128);
+}
+
+#[test]
+fn test_entrypoints_fail_before_init() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionVault, ());
+    let client = SubscriptionVaultClient::new(&env, &contract_id);
+    let someone = Address::generate(&env);
+
+    assert_eq!(client.try_get_admin(), Err(Ok(Error::NotInitialized)));
+    assert_eq!(client.try_get_min_topup(), Err(Ok(Error::NotInitialized)));
+    assert_eq!(
+        client.try_set_min_topup(&someone, &1i128),
+        Err(Ok(Error::NotInitialized))
+    );
+    assert_eq!(
+        client.try_create_subscription(&someone, &someone, &1i128, &INTERVAL, &false),
+        Err(Ok(Error::NotInitialized))
+    );
+    assert!(matches!(
+        client.try_get_subscription(&0u32),
+        Err(Ok(Error::NotInitialized))
+    ));
+    assert!(matches!(
+        client.try_batch_charge(&someone, &SorobanVec::new(&env)),
+        Err(Ok(Error::NotInitialized))
+    ));
+    assert_eq!(
+        client.try_charge_subscription(&0u32, &someone),
+        Err(Ok(Error::NotInitialized))
+    );
+    assert_eq!(
+        client.try_get_merchant_balance(&someone),
+        Err(Ok(Error::NotInitialized))
+    );
+    assert_eq!(
+        client.try_has_role(&Role::BillingOperator, &someone),
+        Err(Ok(Error::NotInitialized))
+    );
+    assert!(matches!(
+        client.try_reconcile(),
+        Err(Ok(Error::NotInitialized))
+    ));
+    assert!(matches!(
+        client.try_get_solvency_report(),
+        Err(Ok(Error::NotInitialized))
+    ));
+}
+
 #[test]
 fn test_min_topup_below_threshold() {
     let env = Env::default();
@@ -716,6 +993,13 @@ fn test_set_min_topup_by_admin() {
 
 // -- Usage-based charge tests ------------------------------------------------
 
+/// Helper: register a fresh billing operator and return it.
+fn add_operator(env: &Env, client: &SubscriptionVaultClient) -> Address {
+    let operator = Address::generate(env);
+    client.grant_role(&client.get_admin(), &Role::BillingOperator, &operator);
+    operator
+}
+
 const PREPAID: i128 = 50_000_000; // 50 USDC
 
 /// Helper: create a subscription with `usage_enabled = false` and a known
@@ -744,7 +1028,7 @@ fn setup(env: &Env, interval: u64) -> (SubscriptionVaultClient<'_>, u32) {
     let mut sub = client.get_subscription(&id);
     sub.prepaid_balance = PREPAID;
     env.as_contract(&contract_id, || {
-        env.storage().instance().set(&id, &sub);
+        crate::storage::save_subscription(env, id, &sub);
     });
 
     (client, id)
@@ -776,7 +1060,7 @@ fn setup_usage(env: &Env) -> (SubscriptionVaultClient<'_>, u32) {
     let mut sub = client.get_subscription(&id);
     sub.prepaid_balance = PREPAID;
     env.as_contract(&contract_id, || {
-        env.storage().instance().set(&id, &sub);
+        crate::storage::save_subscription(env, id, &sub);
     });
 
     (client, id)
@@ -788,8 +1072,9 @@ fn test_usage_charge_debits_balance() {
     let env = Env::default();
     env.mock_all_auths();
     let (client, id) = setup_usage(&env);
+    let operator = add_operator(&env, &client);
 
-    client.charge_usage(&id, &10_000_000i128);
+    client.charge_usage(&id, &operator, &10_000_000i128);
 
     let sub = client.get_subscription(&id);
     assert_eq!(sub.prepaid_balance, PREPAID - 10_000_000);
@@ -802,8 +1087,9 @@ fn test_usage_charge_drains_balance_to_insufficient() {
     let env = Env::default();
     env.mock_all_auths();
     let (client, id) = setup_usage(&env);
+    let operator = add_operator(&env, &client);
 
-    client.charge_usage(&id, &PREPAID);
+    client.charge_usage(&id, &operator, &PREPAID);
 
     let sub = client.get_subscription(&id);
     assert_eq!(sub.prepaid_balance, 0);
@@ -817,8 +1103,9 @@ fn test_usage_charge_rejected_when_disabled() {
     env.mock_all_auths();
     // Use the regular setup helper which creates usage_enabled = false.
     let (client, id) = setup(&env, INTERVAL);
+    let operator = add_operator(&env, &client);
 
-    let res = client.try_charge_usage(&id, &1_000_000i128);
+    let res = client.try_charge_usage(&id, &operator, &1_000_000i128);
     assert_eq!(res, Err(Ok(Error::UsageNotEnabled)));
 }
 
@@ -828,8 +1115,9 @@ fn test_usage_charge_rejected_insufficient_balance() {
     let env = Env::default();
     env.mock_all_auths();
     let (client, id) = setup_usage(&env);
+    let operator = add_operator(&env, &client);
 
-    let res = client.try_charge_usage(&id, &(PREPAID + 1));
+    let res = client.try_charge_usage(&id, &operator, &(PREPAID + 1));
     assert_eq!(res, Err(Ok(Error::InsufficientPrepaidBalance)));
 
     // Balance unchanged.
@@ -843,11 +1131,12 @@ fn test_usage_charge_rejected_invalid_amount() {
     let env = Env::default();
     env.mock_all_auths();
     let (client, id) = setup_usage(&env);
+    let operator = add_operator(&env, &client);
 
-    let res_zero = client.try_charge_usage(&id, &0i128);
+    let res_zero = client.try_charge_usage(&id, &operator, &0i128);
     assert_eq!(res_zero, Err(Ok(Error::InvalidAmount)));
 
-    let res_neg = client.try_charge_usage(&id, &(-1i128));
+    let res_neg = client.try_charge_usage(&id, &operator, &(-1i128));
     assert_eq!(res_neg, Err(Ok(Error::InvalidAmount)));
 
     // Balance unchanged.
@@ -896,9 +1185,13 @@ fn test_compute_next_charge_info_active_subscription() {
         status: SubscriptionStatus::Active,
         prepaid_balance: 100_000_000i128,
         usage_enabled: false,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: last_payment,
+        trial_ends_at: 0,
+        plan_id: None,
     };
 
-    let info = compute_next_charge_info(&subscription);
+    let info = compute_next_charge_info(&env, &subscription);
 
     // Active subscription: charge is expected
     assert!(info.is_charge_expected);
@@ -926,9 +1219,13 @@ fn test_compute_next_charge_info_paused_subscription() {
         status: SubscriptionStatus::Paused,
         prepaid_balance: 50_000_000i128,
         usage_enabled: false,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: last_payment,
+        trial_ends_at: 0,
+        plan_id: None,
     };
 
-    let info = compute_next_charge_info(&subscription);
+    let info = compute_next_charge_info(&env, &subscription);
 
     // Paused subscription: charge is NOT expected
     assert!(!info.is_charge_expected);
@@ -956,9 +1253,13 @@ fn test_compute_next_charge_info_cancelled_subscription() {
         status: SubscriptionStatus::Cancelled,
         prepaid_balance: 0i128,
         usage_enabled: false,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: last_payment,
+        trial_ends_at: 0,
+        plan_id: None,
     };
 
-    let info = compute_next_charge_info(&subscription);
+    let info = compute_next_charge_info(&env, &subscription);
 
     // Cancelled subscription: charge is NOT expected (terminal state)
     assert!(!info.is_charge_expected);
@@ -986,9 +1287,13 @@ fn test_compute_next_charge_info_insufficient_balance_subscription() {
         status: SubscriptionStatus::InsufficientBalance,
         prepaid_balance: 1_000_000i128, // Not enough for next charge
         usage_enabled: false,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: last_payment,
+        trial_ends_at: 0,
+        plan_id: None,
     };
 
-    let info = compute_next_charge_info(&subscription);
+    let info = compute_next_charge_info(&env, &subscription);
 
     // InsufficientBalance subscription: charge IS expected (will retry after funding)
     assert!(info.is_charge_expected);
@@ -1016,9 +1321,13 @@ fn test_compute_next_charge_info_short_interval() {
         status: SubscriptionStatus::Active,
         prepaid_balance: 10_000i128,
         usage_enabled: true,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: last_payment,
+        trial_ends_at: 0,
+        plan_id: None,
     };
 
-    let info = compute_next_charge_info(&subscription);
+    let info = compute_next_charge_info(&env, &subscription);
 
     assert!(info.is_charge_expected);
     assert_eq!(info.next_charge_timestamp, last_payment + interval);
@@ -1044,9 +1353,13 @@ fn test_compute_next_charge_info_long_interval() {
         status: SubscriptionStatus::Active,
         prepaid_balance: 1_000_000_000i128,
         usage_enabled: false,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: last_payment,
+        trial_ends_at: 0,
+        plan_id: None,
     };
 
-    let info = compute_next_charge_info(&subscription);
+    let info = compute_next_charge_info(&env, &subscription);
 
     assert!(info.is_charge_expected);
     assert_eq!(info.next_charge_timestamp, last_payment + interval);
@@ -1073,9 +1386,13 @@ fn test_compute_next_charge_info_overflow_protection() {
         status: SubscriptionStatus::Active,
         prepaid_balance: 100_000_000i128,
         usage_enabled: false,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: last_payment,
+        trial_ends_at: 0,
+        plan_id: None,
     };
 
-    let info = compute_next_charge_info(&subscription);
+    let info = compute_next_charge_info(&env, &subscription);
 
     assert!(info.is_charge_expected);
     // Should saturate to u64::MAX instead of wrapping
@@ -1168,7 +1485,7 @@ fn test_get_next_charge_info_insufficient_balance_status() {
     let mut sub = client.get_subscription(&id);
     sub.status = SubscriptionStatus::InsufficientBalance;
     env.as_contract(&client.address, || {
-        env.storage().instance().set(&id, &sub);
+        crate::storage::save_subscription(&env, id, &sub);
     });
 
     // Get next charge info
@@ -1262,9 +1579,13 @@ fn test_get_next_charge_info_zero_interval() {
         status: SubscriptionStatus::Active,
         prepaid_balance: 10_000_000i128,
         usage_enabled: false,
+        interval_kind: IntervalKind::Seconds,
+        billing_anchor: 5000,
+        trial_ends_at: 0,
+        plan_id: None,
     };
 
-    let info = compute_next_charge_info(&subscription);
+    let info = compute_next_charge_info(&env, &subscription);
 
     assert!(info.is_charge_expected);
     assert_eq!(info.next_charge_timestamp, 5000); // 5000 + 0 = 5000
@@ -1276,7 +1597,9 @@ fn test_get_next_charge_info_zero_interval() {
 
 #[test]
 fn test_recover_stranded_funds_successful() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    // Tokens sent to the vault outside any subscription (recoverable surplus).
+    mint_tokens(&env, &token, &client.address, 50_000_000i128);
 
     let recipient = Address::generate(&env);
     let amount = 50_000_000i128; // 50 USDC
@@ -1398,7 +1721,8 @@ fn test_recover_stranded_funds_negative_amount() {
 
 #[test]
 fn test_recover_stranded_funds_all_recovery_reasons() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 30_000_000i128);
 
     let recipient = Address::generate(&env);
     let amount = 10_000_000i128;
@@ -1431,7 +1755,8 @@ fn test_recover_stranded_funds_all_recovery_reasons() {
 
 #[test]
 fn test_recover_stranded_funds_event_emission() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 25_000_000i128);
 
     let recipient = Address::generate(&env);
     let amount = 25_000_000i128;
@@ -1452,7 +1777,8 @@ fn test_recover_stranded_funds_event_emission() {
 
 #[test]
 fn test_recover_stranded_funds_large_amount() {
-    let (_, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 1_000_000_000_000i128);
 
     let recipient = Address::generate(admin.env());
     let amount = 1_000_000_000_000i128; // 1 million USDC (with 6 decimals)
@@ -1465,7 +1791,8 @@ fn test_recover_stranded_funds_large_amount() {
 
 #[test]
 fn test_recover_stranded_funds_small_amount() {
-    let (_, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 1i128);
 
     let recipient = Address::generate(admin.env());
     let amount = 1i128; // Minimal amount (1 stroops)
@@ -1478,7 +1805,8 @@ fn test_recover_stranded_funds_small_amount() {
 
 #[test]
 fn test_recover_stranded_funds_multiple_recoveries() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 60_000_000i128);
 
     let recipient1 = Address::generate(&env);
     let recipient2 = Address::generate(&env);
@@ -1517,7 +1845,8 @@ fn test_recover_stranded_funds_multiple_recoveries() {
 
 #[test]
 fn test_recover_stranded_funds_different_recipients() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 15_000_000i128);
 
     // Test recovery to different recipient types
     let treasury = Address::generate(&env);
@@ -1562,7 +1891,8 @@ fn test_recovery_reason_enum_values() {
 
 #[test]
 fn test_recover_stranded_funds_timestamp_recorded() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 15_000_000i128);
 
     let recipient = Address::generate(&env);
     let amount = 15_000_000i128;
@@ -1584,7 +1914,8 @@ fn test_recover_stranded_funds_timestamp_recorded() {
 
 #[test]
 fn test_recover_stranded_funds_admin_authorization_required() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 10_000_000i128);
 
     let recipient = Address::generate(&env);
     let amount = 10_000_000i128;
@@ -1597,7 +1928,8 @@ fn test_recover_stranded_funds_admin_authorization_required() {
 
 #[test]
 fn test_recover_stranded_funds_does_not_affect_subscriptions() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 5_000_000i128);
 
     // Create a subscription
     let subscriber = Address::generate(&env);
@@ -1628,7 +1960,8 @@ fn test_recover_stranded_funds_does_not_affect_subscriptions() {
 
 #[test]
 fn test_recover_stranded_funds_with_cancelled_subscription() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 5_000_000i128);
 
     // Create and cancel a subscription
     let subscriber = Address::generate(&env);
@@ -1659,6 +1992,98 @@ fn test_recover_stranded_funds_with_cancelled_subscription() {
     );
 }
 
+/// Helper: register a Stellar asset contract to act as the vault token.
+fn create_token(env: &Env) -> Address {
+    env.register_stellar_asset_contract_v2(Address::generate(env))
+        .address()
+}
+
+/// Helper: mint `amount` of `token` to `to` (requires mocked auths).
+fn mint_tokens(env: &Env, token: &Address, to: &Address, amount: i128) {
+    soroban_sdk::token::StellarAssetClient::new(env, token).mint(to, &amount);
+}
+
+#[test]
+fn test_recover_stranded_funds_transfers_tokens_to_recipient() {
+    let (env, client, token, admin) = setup_test_env();
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    mint_tokens(&env, &token, &client.address, 8_000_000i128);
+
+    let recipient = Address::generate(&env);
+    client.recover_stranded_funds(
+        &admin,
+        &recipient,
+        &8_000_000i128,
+        &RecoveryReason::AccidentalTransfer,
+    );
+
+    assert_eq!(token_client.balance(&recipient), 8_000_000);
+    assert_eq!(token_client.balance(&client.address), 0);
+}
+
+#[test]
+fn test_recover_stranded_funds_cannot_take_prepaid_balances() {
+    let (env, client, token, admin) = setup_test_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+
+    // The vault holds 50 USDC, all of it owed to the subscriber.
+    let recipient = Address::generate(&env);
+    let result = client.try_recover_stranded_funds(
+        &admin,
+        &recipient,
+        &1i128,
+        &RecoveryReason::AccidentalTransfer,
+    );
+    assert_eq!(result, Err(Ok(Error::RecoveryExceedsSurplus)));
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 50_000_000);
+}
+
+#[test]
+fn test_recover_stranded_funds_bounded_by_surplus() {
+    let (env, client, token, admin) = setup_test_env();
+    let operator = add_operator(&env, &client);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let merchant = Address::generate(&env);
+    env.ledger().set_timestamp(T0);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+    mint_tokens(&env, &token, &client.address, 3_000_000i128);
+
+    // Charging moves funds from prepaid to merchant earnings; neither is surplus.
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+
+    let recipient = Address::generate(&env);
+    let result = client.try_recover_stranded_funds(
+        &admin,
+        &recipient,
+        &3_000_001i128,
+        &RecoveryReason::DeprecatedFlow,
+    );
+    assert_eq!(result, Err(Ok(Error::RecoveryExceedsSurplus)));
+
+    client.recover_stranded_funds(
+        &admin,
+        &recipient,
+        &3_000_000i128,
+        &RecoveryReason::DeprecatedFlow,
+    );
+    assert_eq!(token_client.balance(&recipient), 3_000_000);
+    assert_eq!(token_client.balance(&client.address), 50_000_000);
+
+    // Surplus is exhausted; liabilities remain fully backed.
+    let result = client.try_recover_stranded_funds(
+        &admin,
+        &recipient,
+        &1i128,
+        &RecoveryReason::DeprecatedFlow,
+    );
+    assert_eq!(result, Err(Ok(Error::RecoveryExceedsSurplus)));
+    client.withdraw_merchant_funds(&merchant, &10_000_000i128);
+}
+
 // =============================================================================
 // Comprehensive Batch Operations Tests (Issue #45)
 // =============================================================================
@@ -1670,10 +2095,11 @@ fn setup_batch_env(env: &Env) -> (SubscriptionVaultClient<'static>, Address, u32
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(env, &contract_id);
-    let token = Address::generate(env);
+    let token = create_token(env);
     let admin = Address::generate(env);
     client.init(&token, &admin, &1_000000i128);
     let subscriber = Address::generate(env);
+    mint_tokens(env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(env);
     let id0 = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
     client.deposit_funds(&id0, &subscriber, &10_000000i128);
@@ -1687,11 +2113,11 @@ fn setup_batch_env(env: &Env) -> (SubscriptionVaultClient<'static>, Address, u32
 #[test]
 fn test_batch_charge_single_subscription() {
     let env = Env::default();
-    let (client, _admin, id0, _id1) = setup_batch_env(&env);
+    let (client, admin, id0, _id1) = setup_batch_env(&env);
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id0 as u32);
+    ids.push_back(id0);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 1);
     assert!(results.get(0).unwrap().success);
@@ -1705,11 +2131,12 @@ fn test_batch_charge_small_batch_5_subscriptions() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let mut ids = SorobanVec::<u32>::new(&env);
 
@@ -1717,11 +2144,11 @@ fn test_batch_charge_small_batch_5_subscriptions() {
     for _ in 0..5 {
         let id = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
         client.deposit_funds(&id, &subscriber, &10_000000i128);
-        ids.push_back(id as u32);
+        ids.push_back(id);
     }
 
     env.ledger().set_timestamp(T0 + INTERVAL);
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 5);
     for i in 0..5 {
@@ -1737,11 +2164,12 @@ fn test_batch_charge_medium_batch_20_subscriptions() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let mut ids = SorobanVec::<u32>::new(&env);
 
@@ -1749,11 +2177,11 @@ fn test_batch_charge_medium_batch_20_subscriptions() {
     for _ in 0..20 {
         let id = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
         client.deposit_funds(&id, &subscriber, &10_000000i128);
-        ids.push_back(id as u32);
+        ids.push_back(id);
     }
 
     env.ledger().set_timestamp(T0 + INTERVAL);
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 20);
     for i in 0..20 {
@@ -1768,11 +2196,12 @@ fn test_batch_charge_large_batch_50_subscriptions() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let mut ids = SorobanVec::<u32>::new(&env);
 
@@ -1780,11 +2209,11 @@ fn test_batch_charge_large_batch_50_subscriptions() {
     for _ in 0..50 {
         let id = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
         client.deposit_funds(&id, &subscriber, &10_000000i128);
-        ids.push_back(id as u32);
+        ids.push_back(id);
     }
 
     env.ledger().set_timestamp(T0 + INTERVAL);
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 50);
     for i in 0..50 {
@@ -1803,11 +2232,12 @@ fn test_batch_charge_mixed_success_and_insufficient_balance() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let mut ids = SorobanVec::<u32>::new(&env);
 
@@ -1818,11 +2248,11 @@ fn test_batch_charge_mixed_success_and_insufficient_balance() {
             client.deposit_funds(&id, &subscriber, &10_000000i128);
         }
         // Odd indices have no funds
-        ids.push_back(id as u32);
+        ids.push_back(id);
     }
 
     env.ledger().set_timestamp(T0 + INTERVAL);
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 4);
     // Even indices should succeed
@@ -1848,11 +2278,12 @@ fn test_batch_charge_mixed_interval_not_elapsed() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
 
     // Create subscriptions with different intervals
@@ -1869,7 +2300,7 @@ fn test_batch_charge_mixed_interval_not_elapsed() {
     ids.push_back(id_short);
     ids.push_back(id_long);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 2);
     assert!(results.get(0).unwrap().success); // Short interval elapsed
@@ -1887,11 +2318,12 @@ fn test_batch_charge_mixed_paused_and_active() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
 
     let id0 = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
@@ -1904,10 +2336,10 @@ fn test_batch_charge_mixed_paused_and_active() {
     env.ledger().set_timestamp(T0 + INTERVAL);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id0 as u32);
-    ids.push_back(id1 as u32);
+    ids.push_back(id0);
+    ids.push_back(id1);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 2);
     assert!(results.get(0).unwrap().success); // Active subscription charges
@@ -1925,11 +2357,12 @@ fn test_batch_charge_mixed_cancelled_and_active() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
 
     let id0 = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
@@ -1942,10 +2375,10 @@ fn test_batch_charge_mixed_cancelled_and_active() {
     env.ledger().set_timestamp(T0 + INTERVAL);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id0 as u32);
-    ids.push_back(id1 as u32);
+    ids.push_back(id0);
+    ids.push_back(id1);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 2);
     assert!(results.get(0).unwrap().success);
@@ -1959,14 +2392,14 @@ fn test_batch_charge_mixed_cancelled_and_active() {
 #[test]
 fn test_batch_charge_nonexistent_subscription_ids() {
     let env = Env::default();
-    let (client, _admin, id0, _id1) = setup_batch_env(&env);
+    let (client, admin, id0, _id1) = setup_batch_env(&env);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id0 as u32); // Valid
+    ids.push_back(id0); // Valid
     ids.push_back(9999); // Nonexistent
     ids.push_back(8888); // Nonexistent
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 3);
     assert!(results.get(0).unwrap().success);
@@ -1989,11 +2422,12 @@ fn test_batch_charge_all_different_error_types() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
 
     // Sub 0: Success case
@@ -2020,7 +2454,7 @@ fn test_batch_charge_all_different_error_types() {
     ids.push_back(9999); // NotFound
     ids.push_back(id_paused);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     assert_eq!(results.len(), 4);
 
@@ -2058,11 +2492,12 @@ fn test_batch_charge_successful_charges_update_state() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let charge_amount = 1_000_000i128; // 1 USDC
 
@@ -2076,9 +2511,9 @@ fn test_batch_charge_successful_charges_update_state() {
 
     env.ledger().set_timestamp(T0 + INTERVAL);
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id as u32);
+    ids.push_back(id);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
     assert!(results.get(0).unwrap().success);
 
     let sub_after = client.get_subscription(&id);
@@ -2093,11 +2528,12 @@ fn test_batch_charge_failed_charges_leave_state_unchanged() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
 
     let id = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
@@ -2107,9 +2543,9 @@ fn test_batch_charge_failed_charges_leave_state_unchanged() {
 
     env.ledger().set_timestamp(T0 + INTERVAL);
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id as u32);
+    ids.push_back(id);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
     assert!(!results.get(0).unwrap().success);
 
     let sub_after = client.get_subscription(&id);
@@ -2130,11 +2566,12 @@ fn test_batch_charge_partial_batch_correct_final_state() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let amount = 1_000_000i128;
 
@@ -2150,11 +2587,11 @@ fn test_batch_charge_partial_batch_correct_final_state() {
     env.ledger().set_timestamp(T0 + INTERVAL);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id0 as u32);
-    ids.push_back(id1 as u32);
-    ids.push_back(id2 as u32);
+    ids.push_back(id0);
+    ids.push_back(id1);
+    ids.push_back(id2);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     // Verify results
     assert!(results.get(0).unwrap().success);
@@ -2182,11 +2619,12 @@ fn test_batch_charge_multiple_rounds_state_consistency() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let amount = 1_000_000i128;
 
@@ -2194,12 +2632,12 @@ fn test_batch_charge_multiple_rounds_state_consistency() {
     client.deposit_funds(&id, &subscriber, &10_000_000i128);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id as u32);
+    ids.push_back(id);
 
     // Charge 3 times over 3 intervals
     for i in 1..=3 {
         env.ledger().set_timestamp(T0 + (i * INTERVAL));
-        let results = client.batch_charge(&ids);
+        let results = client.batch_charge(&admin, &ids);
         assert!(results.get(0).unwrap().success);
 
         let sub = client.get_subscription(&id);
@@ -2220,11 +2658,12 @@ fn test_batch_charge_requires_admin_auth() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let id = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
 
@@ -2238,16 +2677,16 @@ fn test_batch_charge_requires_admin_auth() {
             fn_name: "batch_charge",
             args: {
                 let mut ids = SorobanVec::<u32>::new(&env);
-                ids.push_back(id as u32);
-                (ids,).into_val(&env)
+                ids.push_back(id);
+                (non_admin.clone(), ids).into_val(&env)
             },
             sub_invokes: &[],
         },
     }]);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id as u32);
-    client.batch_charge(&ids);
+    ids.push_back(id);
+    client.batch_charge(&non_admin, &ids);
 }
 
 // -----------------------------------------------------------------------------
@@ -2257,14 +2696,14 @@ fn test_batch_charge_requires_admin_auth() {
 #[test]
 fn test_batch_charge_duplicate_subscription_ids() {
     let env = Env::default();
-    let (client, _admin, id0, _id1) = setup_batch_env(&env);
+    let (client, admin, id0, _id1) = setup_batch_env(&env);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id0 as u32);
-    ids.push_back(id0 as u32); // Duplicate
-    ids.push_back(id0 as u32); // Duplicate
+    ids.push_back(id0);
+    ids.push_back(id0); // Duplicate
+    ids.push_back(id0); // Duplicate
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
 
     // First should succeed
     assert_eq!(results.len(), 3);
@@ -2284,11 +2723,12 @@ fn test_batch_charge_exhausts_balance_exactly() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let amount = 5_000_000i128;
 
@@ -2298,9 +2738,9 @@ fn test_batch_charge_exhausts_balance_exactly() {
     env.ledger().set_timestamp(T0 + INTERVAL);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id as u32);
+    ids.push_back(id);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
     assert!(results.get(0).unwrap().success);
 
     let sub = client.get_subscription(&id);
@@ -2314,11 +2754,12 @@ fn test_batch_charge_balance_off_by_one_insufficient() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
     let amount = 5_000_000i128;
 
@@ -2328,9 +2769,9 @@ fn test_batch_charge_balance_off_by_one_insufficient() {
     env.ledger().set_timestamp(T0 + INTERVAL);
 
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id as u32);
+    ids.push_back(id);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
     assert!(!results.get(0).unwrap().success);
     assert_eq!(
         results.get(0).unwrap().error_code,
@@ -2345,11 +2786,12 @@ fn test_batch_charge_result_indices_match_input_order() {
     env.ledger().set_timestamp(T0);
     let contract_id = env.register(SubscriptionVault, ());
     let client = SubscriptionVaultClient::new(&env, &contract_id);
-    let token = Address::generate(&env);
+    let token = create_token(&env);
     let admin = Address::generate(&env);
     client.init(&token, &admin, &1_000000i128);
 
     let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 1_000_000_000i128);
     let merchant = Address::generate(&env);
 
     let id0 = client.create_subscription(&subscriber, &merchant, &1000i128, &INTERVAL, &false);
@@ -2365,11 +2807,11 @@ fn test_batch_charge_result_indices_match_input_order() {
 
     // Test specific order: id2, id0, id1
     let mut ids = SorobanVec::<u32>::new(&env);
-    ids.push_back(id2 as u32);
-    ids.push_back(id0 as u32);
-    ids.push_back(id1 as u32);
+    ids.push_back(id2);
+    ids.push_back(id0);
+    ids.push_back(id1);
 
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&admin, &ids);
     assert_eq!(results.len(), 3);
     assert!(results.get(0).unwrap().success); // id2
     assert!(results.get(1).unwrap().success); // id0
@@ -2377,7 +2819,8 @@ fn test_batch_charge_result_indices_match_input_order() {
 }
 #[test]
 fn test_recover_stranded_funds_idempotency() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 20_000_000i128);
 
     let recipient = Address::generate(&env);
     let amount = 10_000_000i128;
@@ -2399,7 +2842,8 @@ fn test_recover_stranded_funds_idempotency() {
 
 #[test]
 fn test_recover_stranded_funds_edge_case_max_i128() {
-    let (_, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, i128::MAX - 1000);
 
     let recipient = Address::generate(admin.env());
     // Test near max i128 value
@@ -2600,18 +3044,15 @@ fn test_usage_enabled_with_zero_interval() {
     let subscriber = Address::generate(&env);
     let merchant = Address::generate(&env);
 
-    // Create subscription with zero interval and usage enabled
-    let id = client.create_subscription(
+    // Zero interval without a merchant default interval is rejected
+    let result = client.try_create_subscription(
         &subscriber,
         &merchant,
         &1_000_000i128,
         &0, // Zero interval
         &true,
     );
-
-    let subscription = client.get_subscription(&id);
-    assert!(subscription.usage_enabled);
-    assert_eq!(subscription.interval_seconds, 0);
+    assert_eq!(result, Err(Ok(Error::InvalidInterval)));
 }
 
 #[test]
@@ -2925,7 +3366,8 @@ fn test_usage_enabled_field_storage() {
 
 #[test]
 fn test_usage_enabled_with_recovery_operations() {
-    let (env, client, _, admin) = setup_test_env();
+    let (env, client, token, admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 5_000_000i128);
 
     let subscriber = Address::generate(&env);
     let merchant = Address::generate(&env);
@@ -2984,6 +3426,21 @@ fn test_rotate_admin_successful() {
     assert_eq!(client.get_admin(), new_admin);
 }
 
+#[test]
+fn test_rotate_admin_updates_admin_key() {
+    let (env, client, _, old_admin) = setup_test_env();
+
+    let new_admin = Address::generate(&env);
+    client.rotate_admin(&old_admin, &new_admin);
+
+    let stored: Address = env.as_contract(&client.address, || {
+        env.storage().instance().get(&DataKey::Admin).unwrap()
+    });
+    assert_eq!(stored, new_admin);
+    assert!(client.has_role(&Role::Admin, &new_admin));
+    assert!(!client.has_role(&Role::Admin, &old_admin));
+}
+
 #[test]
 #[should_panic(expected = "Error(Contract, #401)")]
 fn test_rotate_admin_unauthorized() {
@@ -3028,7 +3485,8 @@ fn test_new_admin_gains_access_after_rotation() {
 
 #[test]
 fn test_admin_rotation_affects_recovery_operations() {
-    let (env, client, _, old_admin) = setup_test_env();
+    let (env, client, token, old_admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 20_000000i128);
 
     let new_admin = Address::generate(&env);
     let recipient = Address::generate(&env);
@@ -3081,14 +3539,14 @@ fn test_batch_charge_admin_rotation() {
     let mut sub = client.get_subscription(&id);
     sub.prepaid_balance = 50_000_000i128;
     env.as_contract(&client.address, || {
-        env.storage().instance().set(&id, &sub);
+        crate::storage::save_subscription(&env, id, &sub);
     });
     env.ledger()
         .with_mut(|li| li.timestamp = T0 + interval_seconds);
 
     // Old admin can batch_charge before rotation
     let ids = soroban_sdk::Vec::from_array(&env, [id]);
-    let results = client.batch_charge(&ids);
+    let results = client.batch_charge(&old_admin, &ids);
     assert_eq!(results.len(), 1);
     let r0 = results.get(0).unwrap();
     assert!(r0.success);
@@ -3103,7 +3561,7 @@ fn test_batch_charge_admin_rotation() {
         .with_mut(|li| li.timestamp = T0 + 2 * interval_seconds);
     let sub2 = client.get_subscription(&id);
     assert_eq!(sub2.status, SubscriptionStatus::Active);
-    let results2 = client.batch_charge(&ids);
+    let results2 = client.batch_charge(&new_admin, &ids);
     assert_eq!(results2.len(), 1);
     assert!(results2.get(0).unwrap().success);
 }
@@ -3249,7 +3707,8 @@ fn test_recover_stranded_funds_unauthorized_after_rotation() {
 
 #[test]
 fn test_all_admin_operations_after_rotation() {
-    let (env, client, _, old_admin) = setup_test_env();
+    let (env, client, token, old_admin) = setup_test_env();
+    mint_tokens(&env, &token, &client.address, 5_000000i128);
 
     let new_admin = Address::generate(&env);
 
@@ -3453,24 +3912,201 @@ fn test_get_admin_before_and_after_rotation() {
     assert_eq!(client.get_admin(), another_admin);
 }
 
-// =============================================================================
-// View Function Tests: list_subscriptions_by_subscriber
-// =============================================================================
+// -- Two-step admin handover --------------------------------------------------
 
 #[test]
-fn test_list_subscriptions_zero_subscriptions() {
-    // Test querying a subscriber with no subscriptions
-    let (env, client, _, _) = setup_test_env();
+fn test_propose_and_accept_admin() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let new_admin = Address::generate(&env);
+    env.ledger().set_timestamp(T0);
 
-    let subscriber = Address::generate(&env);
-    let page = client.list_subscriptions_by_subscriber(&subscriber, &0u32, &10u32);
+    let pending = client.propose_admin(&old_admin, &new_admin);
+    assert_eq!(pending.proposer, old_admin);
+    assert_eq!(pending.proposed_admin, new_admin);
+    assert_eq!(pending.expires_at, T0 + ADMIN_PROPOSAL_TTL_SECONDS);
+    assert_eq!(client.get_pending_admin(), Some(pending));
 
-    assert_eq!(page.subscription_ids.len(), 0);
-    assert!(!page.has_next);
+    // Proposing does not change the admin.
+    assert_eq!(client.get_admin(), old_admin);
+
+    client.accept_admin(&new_admin);
+    assert_eq!(client.get_admin(), new_admin);
+    assert_eq!(client.get_pending_admin(), None);
+    assert_eq!(
+        client.try_set_min_topup(&old_admin, &1i128),
+        Err(Ok(Error::Unauthorized))
+    );
 }
 
 #[test]
-fn test_list_subscriptions_one_subscription() {
+fn test_accept_admin_rejects_wrong_address() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let new_admin = Address::generate(&env);
+    let impostor = Address::generate(&env);
+
+    client.propose_admin(&old_admin, &new_admin);
+    assert_eq!(
+        client.try_accept_admin(&impostor),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(client.get_admin(), old_admin);
+}
+
+#[test]
+fn test_accept_admin_requires_nominee_auth() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let new_admin = Address::generate(&env);
+    client.propose_admin(&old_admin, &new_admin);
+
+    env.set_auths(&[]);
+    assert!(matches!(client.try_accept_admin(&new_admin), Err(Err(_))));
+    assert_eq!(client.get_admin(), old_admin);
+}
+
+#[test]
+fn test_accept_admin_without_proposal() {
+    let (env, client, _, _) = setup_test_env();
+    let new_admin = Address::generate(&env);
+    assert_eq!(
+        client.try_accept_admin(&new_admin),
+        Err(Ok(Error::NoPendingAdmin))
+    );
+}
+
+#[test]
+fn test_admin_proposal_expires() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let new_admin = Address::generate(&env);
+    env.ledger().set_timestamp(T0);
+    client.propose_admin(&old_admin, &new_admin);
+
+    // One second past expiry the proposal can no longer be accepted.
+    env.ledger()
+        .set_timestamp(T0 + ADMIN_PROPOSAL_TTL_SECONDS + 1);
+    assert_eq!(
+        client.try_accept_admin(&new_admin),
+        Err(Ok(Error::AdminProposalExpired))
+    );
+    assert_eq!(client.get_admin(), old_admin);
+
+    // At the exact expiry timestamp it still can.
+    env.ledger().set_timestamp(T0 + ADMIN_PROPOSAL_TTL_SECONDS);
+    client.accept_admin(&new_admin);
+    assert_eq!(client.get_admin(), new_admin);
+}
+
+#[test]
+fn test_cancel_admin_proposal() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let new_admin = Address::generate(&env);
+    client.propose_admin(&old_admin, &new_admin);
+
+    client.cancel_admin_proposal(&old_admin);
+    assert_eq!(client.get_pending_admin(), None);
+    assert_eq!(
+        client.try_accept_admin(&new_admin),
+        Err(Ok(Error::NoPendingAdmin))
+    );
+    assert_eq!(
+        client.try_cancel_admin_proposal(&old_admin),
+        Err(Ok(Error::NoPendingAdmin))
+    );
+}
+
+#[test]
+fn test_propose_and_cancel_are_admin_only() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let stranger = Address::generate(&env);
+
+    assert_eq!(
+        client.try_propose_admin(&stranger, &stranger),
+        Err(Ok(Error::Unauthorized))
+    );
+    client.propose_admin(&old_admin, &Address::generate(&env));
+    assert_eq!(
+        client.try_cancel_admin_proposal(&stranger),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert!(client.get_pending_admin().is_some());
+}
+
+#[test]
+fn test_new_proposal_replaces_previous() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+
+    client.propose_admin(&old_admin, &first);
+    client.propose_admin(&old_admin, &second);
+    assert_eq!(
+        client.try_accept_admin(&first),
+        Err(Ok(Error::Unauthorized))
+    );
+    client.accept_admin(&second);
+    assert_eq!(client.get_admin(), second);
+}
+
+#[test]
+fn test_rotate_admin_clears_pending_proposal() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let nominee = Address::generate(&env);
+    let rotated = Address::generate(&env);
+
+    client.propose_admin(&old_admin, &nominee);
+    client.rotate_admin(&old_admin, &rotated);
+    assert_eq!(client.get_pending_admin(), None);
+    assert_eq!(
+        client.try_accept_admin(&nominee),
+        Err(Ok(Error::NoPendingAdmin))
+    );
+}
+
+#[test]
+fn test_admin_handover_events() {
+    let (env, client, _, old_admin) = setup_test_env();
+    let new_admin = Address::generate(&env);
+
+    let last_event_name = |env: &Env| -> soroban_sdk::Symbol {
+        let (_, topics, _) = env.events().all().last().unwrap();
+        topics.get(0).unwrap().into_val(env)
+    };
+
+    client.propose_admin(&old_admin, &new_admin);
+    assert_eq!(
+        last_event_name(&env),
+        soroban_sdk::Symbol::new(&env, "admin_proposed")
+    );
+    client.cancel_admin_proposal(&old_admin);
+    assert_eq!(
+        last_event_name(&env),
+        soroban_sdk::Symbol::new(&env, "admin_proposal_cancelled")
+    );
+    client.propose_admin(&old_admin, &new_admin);
+    client.accept_admin(&new_admin);
+    assert_eq!(
+        last_event_name(&env),
+        soroban_sdk::Symbol::new(&env, "admin_accepted")
+    );
+}
+
+// =============================================================================
+// View Function Tests: list_subscriptions_by_subscriber
+// =============================================================================
+
+#[test]
+fn test_list_subscriptions_zero_subscriptions() {
+    // Test querying a subscriber with no subscriptions
+    let (env, client, _, _) = setup_test_env();
+
+    let subscriber = Address::generate(&env);
+    let page = client.list_subscriptions_by_subscriber(&subscriber, &0u32, &10u32);
+
+    assert_eq!(page.subscription_ids.len(), 0);
+    assert!(!page.has_next);
+}
+
+#[test]
+fn test_list_subscriptions_one_subscription() {
     // Test querying a subscriber with exactly one subscription
     let (env, client, _, _) = setup_test_env();
 
@@ -3519,10 +4155,7 @@ fn test_list_subscriptions_many_subscriptions() {
 
     // Verify subscriptions are returned in order by ID
     for i in 0..5 {
-        assert_eq!(
-            page.subscription_ids.get(i).unwrap(),
-            ids.get(i as u32).unwrap()
-        );
+        assert_eq!(page.subscription_ids.get(i).unwrap(), ids.get(i).unwrap());
     }
 }
 
@@ -3553,10 +4186,7 @@ fn test_list_subscriptions_pagination_first_page() {
 
     // Verify first page contains the first 10 subscriptions
     for i in 0..10 {
-        assert_eq!(
-            page1.subscription_ids.get(i).unwrap(),
-            ids.get(i as u32).unwrap()
-        );
+        assert_eq!(page1.subscription_ids.get(i).unwrap(), ids.get(i).unwrap());
     }
 }
 
@@ -3596,7 +4226,7 @@ fn test_list_subscriptions_pagination_second_page() {
     for i in 0..5 {
         assert_eq!(
             page2.subscription_ids.get(i).unwrap(),
-            ids.get((10 + i) as u32).unwrap()
+            ids.get(10 + i).unwrap()
         );
     }
 }
@@ -3668,7 +4298,7 @@ fn test_list_subscriptions_small_limit() {
 
     while has_next {
         let page = client.list_subscriptions_by_subscriber(&subscriber, &start_id, &1u32);
-        if page.subscription_ids.len() > 0 {
+        if !page.subscription_ids.is_empty() {
             let current_id = page.subscription_ids.get(0).unwrap();
             all_ids.push_back(current_id);
             // Advance start cursor past the current ID
@@ -3727,7 +4357,7 @@ fn test_list_subscriptions_respects_start_from_id() {
     for i in 0..5 {
         assert_eq!(
             page.subscription_ids.get(i).unwrap(),
-            ids.get((5 + i) as u32).unwrap()
+            ids.get(5 + i).unwrap()
         );
     }
 }
@@ -3791,9 +4421,3910 @@ fn test_list_subscriptions_multiple_merchants() {
     assert_eq!(page.subscription_ids.len(), 10);
     // All subscriptions should be from this subscriber regardless of merchant
     for i in 0..10 {
-        assert_eq!(
-            page.subscription_ids.get(i).unwrap(),
-            ids.get(i as u32).unwrap()
-        );
+        assert_eq!(page.subscription_ids.get(i).unwrap(), ids.get(i).unwrap());
+    }
+}
+
+#[test]
+fn test_list_subscriptions_start_between_owned_ids() {
+    // start_from_id pointing at another subscriber's ID resumes at the next owned ID
+    let (env, client, _, _) = setup_test_env();
+
+    let subscriber = Address::generate(&env);
+    let other = Address::generate(&env);
+    let merchant = Address::generate(&env);
+
+    let mut mine = soroban_sdk::Vec::new(&env);
+    for i in 0..6 {
+        let owner = if i % 2 == 0 { &subscriber } else { &other };
+        let id = client.create_subscription(owner, &merchant, &10_000_000i128, &INTERVAL, &false);
+        if i % 2 == 0 {
+            mine.push_back(id);
+        }
+    }
+
+    // IDs 0, 2, 4 belong to `subscriber`; start at 1 (owned by `other`).
+    let page = client.list_subscriptions_by_subscriber(&subscriber, &1u32, &1u32);
+    assert_eq!(page.subscription_ids.len(), 1);
+    assert_eq!(page.subscription_ids.get(0).unwrap(), mine.get(1).unwrap());
+    assert!(page.has_next);
+
+    // Start beyond the last owned ID returns an empty page.
+    let page = client.list_subscriptions_by_subscriber(&subscriber, &5u32, &10u32);
+    assert_eq!(page.subscription_ids.len(), 0);
+    assert!(!page.has_next);
+}
+
+#[test]
+fn test_list_subscriptions_exact_limit_has_no_next() {
+    let (env, client, _, _) = setup_test_env();
+
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    for _ in 0..3 {
+        client.create_subscription(&subscriber, &merchant, &10_000_000i128, &INTERVAL, &false);
+    }
+
+    let page = client.list_subscriptions_by_subscriber(&subscriber, &0u32, &3u32);
+    assert_eq!(page.subscription_ids.len(), 3);
+    assert!(!page.has_next);
+}
+
+#[test]
+fn test_subscriber_index_maintained_on_create() {
+    let (env, client, _, _) = setup_test_env();
+
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let id0 =
+        client.create_subscription(&subscriber, &merchant, &10_000_000i128, &INTERVAL, &false);
+    let id1 =
+        client.create_subscription(&subscriber, &merchant, &10_000_000i128, &INTERVAL, &false);
+
+    let (count, stored): (u32, soroban_sdk::Vec<u32>) = env.as_contract(&client.address, || {
+        let persistent = env.storage().persistent();
+        (
+            persistent
+                .get(&DataKey::SubscriberSubCount(subscriber.clone()))
+                .unwrap(),
+            persistent
+                .get(&DataKey::SubscriberSubsPage(subscriber.clone(), 0))
+                .unwrap(),
+        )
+    });
+    assert_eq!(count, 2);
+    assert_eq!(stored, soroban_sdk::vec![&env, id0, id1]);
+}
+
+#[test]
+fn test_subscriber_index_pages_across_page_boundaries() {
+    let (env, client, _, _) = setup_test_env();
+
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let other = Address::generate(&env);
+    let mut ids = soroban_sdk::Vec::new(&env);
+    for i in 0..MERCHANT_SUBS_PAGE_SIZE + 10 {
+        // Interleave another subscriber so the index IDs are not contiguous.
+        if i % 2 == 0 {
+            client.create_subscription(&other, &merchant, &10_000_000i128, &INTERVAL, &false);
+        }
+        let id =
+            client.create_subscription(&subscriber, &merchant, &10_000_000i128, &INTERVAL, &false);
+        ids.push_back(id);
     }
+
+    env.as_contract(&client.address, || {
+        let persistent = env.storage().persistent();
+        let page1: soroban_sdk::Vec<u32> = persistent
+            .get(&DataKey::SubscriberSubsPage(subscriber.clone(), 1))
+            .unwrap();
+        assert_eq!(page1.len(), 10);
+        assert!(!persistent.has(&DataKey::SubscriberSubsPage(subscriber.clone(), 2)));
+    });
+
+    // A cursor just before the page boundary returns IDs from both pages.
+    let start = MERCHANT_SUBS_PAGE_SIZE - 4;
+    let page =
+        client.list_subscriptions_by_subscriber(&subscriber, &ids.get(start).unwrap(), &10u32);
+    assert_eq!(page.subscription_ids, ids.slice(start..start + 10));
+    assert!(page.has_next);
+
+    // A cursor at another subscriber's ID starts at the next one of theirs.
+    let gap = ids.get(start).unwrap() - 1;
+    let page = client.list_subscriptions_by_subscriber(&subscriber, &gap, &100u32);
+    assert_eq!(page.subscription_ids, ids.slice(start..ids.len()));
+    assert!(!page.has_next);
+}
+
+// =============================================================================
+// Merchant Earnings Ledger Tests
+// =============================================================================
+
+/// Helper: vault with a real token; returns (env, client, token, admin).
+fn setup_earnings_env() -> (Env, SubscriptionVaultClient<'static>, Address, Address) {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(T0);
+    let contract_id = env.register(SubscriptionVault, ());
+    let client = SubscriptionVaultClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let token = create_token(&env);
+    client.init(&token, &admin, &1_000000i128);
+
+    (env, client, token, admin)
+}
+
+/// Helper: create and fund a subscription for `merchant` from a fresh subscriber.
+fn create_funded_subscription(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    token: &Address,
+    merchant: &Address,
+    amount: i128,
+    deposit: i128,
+) -> (u32, Address) {
+    let subscriber = Address::generate(env);
+    mint_tokens(env, token, &subscriber, deposit);
+    let id = client.create_subscription(&subscriber, merchant, &amount, &INTERVAL, &true);
+    client.deposit_funds(&id, &subscriber, &deposit);
+    (id, subscriber)
+}
+
+#[test]
+fn test_charge_subscription_credits_shared_merchant_balance_multiple_subscribers() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+
+    let (id0, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+    let (id1, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 7_000_000, 50_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id0, &operator);
+    client.charge_subscription(&id1, &operator);
+
+    assert_eq!(client.get_merchant_balance(&merchant), 17_000_000);
+    assert_eq!(client.get_subscription(&id0).prepaid_balance, 40_000_000);
+    assert_eq!(client.get_subscription(&id1).prepaid_balance, 43_000_000);
+}
+
+#[test]
+fn test_charge_subscription_insufficient_prepaid_does_not_credit() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 5_000_000);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    let res = client.charge_subscription(&id, &operator);
+    assert!(!res.success);
+    assert_eq!(res.error_code, Error::InsufficientBalance.to_code());
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+}
+
+#[test]
+fn test_charge_usage_credits_merchant_balance() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    client.charge_usage(&id, &operator, &3_000_000i128);
+    client.charge_usage(&id, &operator, &2_000_000i128);
+
+    assert_eq!(client.get_merchant_balance(&merchant), 5_000_000);
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 15_000_000);
+}
+
+#[test]
+fn test_merchant_balances_are_isolated_across_merchants() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant_a = Address::generate(&env);
+    let merchant_b = Address::generate(&env);
+
+    let (id_a, _) =
+        create_funded_subscription(&env, &client, &token, &merchant_a, 10_000_000, 30_000_000);
+    let (id_b, _) =
+        create_funded_subscription(&env, &client, &token, &merchant_b, 4_000_000, 30_000_000);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id_a, &operator);
+    client.charge_subscription(&id_b, &operator);
+
+    assert_eq!(client.get_merchant_balance(&merchant_a), 10_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant_b), 4_000_000);
+
+    client.withdraw_merchant_funds(&merchant_a, &10_000_000i128);
+    assert_eq!(client.get_merchant_balance(&merchant_a), 0);
+    assert_eq!(client.get_merchant_balance(&merchant_b), 4_000_000);
+}
+
+#[test]
+fn test_withdraw_merchant_funds_debits_internal_balance_and_transfers_tokens() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let merchant = Address::generate(&env);
+
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 30_000_000);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+
+    client.withdraw_merchant_funds(&merchant, &6_000_000i128);
+
+    assert_eq!(client.get_merchant_balance(&merchant), 4_000_000);
+    assert_eq!(token_client.balance(&merchant), 6_000_000);
+    assert_eq!(token_client.balance(&client.address), 24_000_000);
+}
+
+#[test]
+fn test_withdraw_merchant_funds_prevents_double_spend() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 30_000_000);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+
+    client.withdraw_merchant_funds(&merchant, &10_000_000i128);
+    let res = client.try_withdraw_merchant_funds(&merchant, &10_000_000i128);
+    assert_eq!(res, Err(Ok(Error::InsufficientBalance)));
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+}
+
+#[test]
+fn test_withdraw_exceeds_balance() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 30_000_000);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+
+    let res = client.try_withdraw_merchant_funds(&merchant, &10_000_001i128);
+    assert_eq!(res, Err(Ok(Error::InsufficientBalance)));
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+}
+
+#[test]
+fn test_withdraw_no_balance() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+
+    let res = client.try_withdraw_merchant_funds(&merchant, &1i128);
+    assert_eq!(res, Err(Ok(Error::InsufficientBalance)));
+
+    let res = client.try_withdraw_merchant_funds(&merchant, &0i128);
+    assert_eq!(res, Err(Ok(Error::InvalidAmount)));
+}
+
+// =============================================================================
+// Solvency Accounting Tests
+// =============================================================================
+
+#[test]
+fn test_solvency_report_tracks_deposits_charges_and_withdrawals() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 30_000_000);
+    let report = client.get_solvency_report();
+    assert_eq!(report.token_balance, 30_000_000);
+    assert_eq!(report.total_prepaid, 30_000_000);
+    assert_eq!(report.total_merchant_balance, 0);
+    assert_eq!(report.surplus, 0);
+    assert!(report.is_solvent);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+    client.charge_usage(&id, &operator, &2_000_000i128);
+    let report = client.get_solvency_report();
+    assert_eq!(report.total_prepaid, 18_000_000);
+    assert_eq!(report.total_merchant_balance, 12_000_000);
+    assert_eq!(report.total_liabilities, 30_000_000);
+
+    client.withdraw_merchant_funds(&merchant, &12_000_000i128);
+    client.cancel_subscription(&id, &subscriber);
+    client.withdraw_subscriber_funds(&id, &subscriber);
+    let report = client.get_solvency_report();
+    assert_eq!(report.token_balance, 0);
+    assert_eq!(report.total_liabilities, 0);
+    assert!(report.is_solvent);
+}
+
+#[test]
+fn test_solvency_report_counts_stranded_tokens_as_surplus() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    mint_tokens(&env, &token, &client.address, 4_000_000i128);
+
+    let report = client.get_solvency_report();
+    assert_eq!(report.token_balance, 24_000_000);
+    assert_eq!(report.surplus, 4_000_000);
+    assert!(report.is_solvent);
+}
+
+#[test]
+fn test_reconcile_solvent_vault_stays_open() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    let report = client.reconcile();
+    assert!(report.is_solvent);
+    assert!(!report.protective_mode);
+    assert!(!client.get_solvency_report().protective_mode);
+}
+
+#[test]
+fn test_reconcile_enters_protective_mode_after_reserve_loss() {
+    let (env, client, token, admin) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+
+    // Simulate an issuer clawback removing reserves from vault custody.
+    token_client.burn(&client.address, &5_000_000i128);
+
+    let report = client.reconcile();
+    assert!(!report.is_solvent);
+    assert!(report.protective_mode);
+    assert_eq!(report.surplus, -5_000_000);
+
+    // Outflows are frozen.
+    assert_eq!(
+        client.try_withdraw_merchant_funds(&merchant, &1_000_000i128),
+        Err(Ok(Error::ProtectiveMode))
+    );
+    let recipient = Address::generate(&env);
+    assert_eq!(
+        client.try_recover_stranded_funds(
+            &admin,
+            &recipient,
+            &1i128,
+            &RecoveryReason::AccidentalTransfer
+        ),
+        Err(Ok(Error::ProtectiveMode))
+    );
+    client.cancel_subscription(&id, &subscriber);
+    assert_eq!(
+        client.try_withdraw_subscriber_funds(&id, &subscriber),
+        Err(Ok(Error::ProtectiveMode))
+    );
+}
+
+#[test]
+fn test_deposits_allowed_in_protective_mode() {
+    let (env, client, token, _) = setup_earnings_env();
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    token_client.burn(&client.address, &5_000_000i128);
+    assert!(client.reconcile().protective_mode);
+
+    mint_tokens(&env, &token, &subscriber, 20_000_000i128);
+    client.deposit_funds(&id, &subscriber, &5_000_000i128);
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 25_000_000);
+    let other = client.create_subscription_with_deposit(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &10_000_000i128,
+        &false,
+    );
+    assert_eq!(client.get_subscription(&other).prepaid_balance, 10_000_000);
+
+    // Deposits add as much to reserves as to liabilities: the shortfall is unchanged.
+    let report = client.get_solvency_report();
+    assert!(report.protective_mode);
+    assert_eq!(report.surplus, -5_000_000);
+}
+
+#[test]
+fn test_exit_protective_mode_requires_solvency_and_admin() {
+    let (env, client, token, admin) = setup_earnings_env();
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let merchant = Address::generate(&env);
+    create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    token_client.burn(&client.address, &5_000_000i128);
+    client.reconcile();
+
+    assert_eq!(
+        client.try_exit_protective_mode(&admin),
+        Err(Ok(Error::VaultInsolvent))
+    );
+
+    // Reserves are restored; only the admin may lift protection.
+    mint_tokens(&env, &token, &client.address, 5_000_000i128);
+    let stranger = Address::generate(&env);
+    assert_eq!(
+        client.try_exit_protective_mode(&stranger),
+        Err(Ok(Error::Unauthorized))
+    );
+    client.exit_protective_mode(&admin);
+    assert!(!client.get_solvency_report().protective_mode);
+
+    // Deposits are accepted again.
+    create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    assert_eq!(client.get_solvency_report().total_prepaid, 40_000_000);
+}
+
+// =============================================================================
+// One-Off Charge Tests
+// =============================================================================
+
+#[test]
+fn test_charge_one_off_debits_prepaid_and_credits_merchant() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    client.charge_one_off(&id, &merchant, &3_000_000i128);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 17_000_000);
+    assert_eq!(sub.last_payment_timestamp, T0);
+    assert_eq!(sub.status, SubscriptionStatus::Active);
+    assert_eq!(client.get_merchant_balance(&merchant), 3_000_000);
+    assert_eq!(client.get_solvency_report().total_prepaid, 17_000_000);
+}
+
+#[test]
+fn test_charge_one_off_allowed_when_paused() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    client.pause_subscription(&id, &subscriber);
+
+    client.charge_one_off(&id, &merchant, &20_000_000i128);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 0);
+    assert_eq!(sub.status, SubscriptionStatus::Paused);
+}
+
+#[test]
+fn test_charge_one_off_does_not_consume_interval_charge() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 30_000_000);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_one_off(&id, &merchant, &5_000_000i128);
+    client.charge_subscription(&id, &operator);
+
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 15_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 15_000_000);
+}
+
+#[test]
+fn test_charge_one_off_rejects_wrong_merchant() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let other = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    let res = client.try_charge_one_off(&id, &other, &1_000_000i128);
+    assert_eq!(res, Err(Ok(Error::Unauthorized)));
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 20_000_000);
+}
+
+#[test]
+fn test_charge_one_off_rejects_overdraft_and_invalid_amount() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    assert_eq!(
+        client.try_charge_one_off(&id, &merchant, &20_000_001i128),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    assert_eq!(
+        client.try_charge_one_off(&id, &merchant, &0i128),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        client.try_charge_one_off(&id, &merchant, &(-5i128)),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+}
+
+#[test]
+fn test_charge_one_off_rejects_cancelled_and_insufficient_balance_status() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 5_000_000);
+
+    // A failed batch charge persists the InsufficientBalance transition.
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.batch_charge(&client.get_admin(), &SorobanVec::from_array(&env, [id]));
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::InsufficientBalance
+    );
+    assert_eq!(
+        client.try_charge_one_off(&id, &merchant, &1_000_000i128),
+        Err(Ok(Error::NotActive))
+    );
+
+    client.cancel_subscription(&id, &subscriber);
+    assert_eq!(
+        client.try_charge_one_off(&id, &merchant, &1_000_000i128),
+        Err(Ok(Error::NotActive))
+    );
+}
+
+#[test]
+fn test_charge_one_off_emits_event() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    client.charge_one_off(&id, &merchant, &2_500_000i128);
+
+    let events = env.events().all();
+    let (_, topics, data) = events.last().unwrap();
+    let topic: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+    assert_eq!(topic, soroban_sdk::symbol_short!("oneoff_ch"));
+    let event: crate::OneOffChargedEvent = data.into_val(&env);
+    assert_eq!(event.subscription_id, id);
+    assert_eq!(event.merchant, merchant);
+    assert_eq!(event.amount, 2_500_000);
+}
+
+// =============================================================================
+// Merchant Config Tests
+// =============================================================================
+
+#[test]
+fn test_get_merchant_config_returns_defaults_when_unset() {
+    let (env, client, _, _) = setup_test_env();
+    let merchant = Address::generate(&env);
+
+    let config = client.get_merchant_config(&merchant);
+    assert_eq!(config, crate::MerchantConfig::default_config());
+    assert_eq!(config.version, 1);
+    assert_eq!(config.min_subscription_amount, 0);
+    assert_eq!(config.default_interval_seconds, 0);
+}
+
+#[test]
+fn test_set_and_update_merchant_config_by_admin_over_time() {
+    let (env, client, _, admin) = setup_test_env();
+    let merchant = Address::generate(&env);
+
+    client.set_merchant_config(&admin, &merchant, &5_000_000i128, &INTERVAL);
+    let config = client.get_merchant_config(&merchant);
+    assert_eq!(config.min_subscription_amount, 5_000_000);
+    assert_eq!(config.default_interval_seconds, INTERVAL);
+
+    // Partial update leaves the other field unchanged.
+    client.update_merchant_config(&admin, &merchant, &Some(7_000_000i128), &None);
+    let config = client.get_merchant_config(&merchant);
+    assert_eq!(config.min_subscription_amount, 7_000_000);
+    assert_eq!(config.default_interval_seconds, INTERVAL);
+
+    client.update_merchant_config(&admin, &merchant, &None, &Some(7 * 24 * 60 * 60));
+    let config = client.get_merchant_config(&merchant);
+    assert_eq!(config.min_subscription_amount, 7_000_000);
+    assert_eq!(config.default_interval_seconds, 7 * 24 * 60 * 60);
+
+    // Full overwrite.
+    client.set_merchant_config(&admin, &merchant, &0i128, &0u64);
+    assert_eq!(
+        client.get_merchant_config(&merchant),
+        crate::MerchantConfig::default_config()
+    );
+}
+
+#[test]
+fn test_set_merchant_config_by_merchant_and_apply_defaults_on_create() {
+    let (env, client, _, _) = setup_test_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+
+    client.set_merchant_config(&merchant, &merchant, &2_000_000i128, &INTERVAL);
+
+    let id = client.create_subscription(&subscriber, &merchant, &2_000_000i128, &0, &false);
+    assert_eq!(client.get_subscription(&id).interval_seconds, INTERVAL);
+
+    // An explicit interval still wins over the default.
+    let id = client.create_subscription(&subscriber, &merchant, &3_000_000i128, &3600, &false);
+    assert_eq!(client.get_subscription(&id).interval_seconds, 3600);
+}
+
+#[test]
+fn test_create_subscription_enforces_merchant_min_amount() {
+    let (env, client, _, _) = setup_test_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+
+    client.set_merchant_config(&merchant, &merchant, &5_000_000i128, &0u64);
+
+    let result =
+        client.try_create_subscription(&subscriber, &merchant, &4_999_999i128, &INTERVAL, &false);
+    assert_eq!(result, Err(Ok(Error::InvalidAmount)));
+
+    // Other merchants are unaffected.
+    let other = Address::generate(&env);
+    client.create_subscription(&subscriber, &other, &1_000i128, &INTERVAL, &false);
+    client.create_subscription(&subscriber, &merchant, &5_000_000i128, &INTERVAL, &false);
+}
+
+#[test]
+fn test_merchant_config_unauthorized_and_invalid_update_failures() {
+    let (env, client, _, _) = setup_test_env();
+    let merchant = Address::generate(&env);
+    let stranger = Address::generate(&env);
+
+    assert_eq!(
+        client.try_set_merchant_config(&stranger, &merchant, &1i128, &INTERVAL),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_update_merchant_config(&stranger, &merchant, &Some(1i128), &None),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_merchant_config(&merchant, &merchant, &(-1i128), &INTERVAL),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        client.try_update_merchant_config(&merchant, &merchant, &Some(-1i128), &None),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        client.get_merchant_config(&merchant),
+        crate::MerchantConfig::default_config()
+    );
+}
+
+// =============================================================================
+// Billing Operator & Role Tests
+// =============================================================================
+
+#[test]
+fn test_charge_usage_unauthenticated_call_rejected() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    // Stop mocking auths: the operator has not signed this invocation.
+    env.set_auths(&[]);
+    let res = client.try_charge_usage(&id, &operator, &20_000_000i128);
+    assert!(matches!(res, Err(Err(_))));
+
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 20_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+}
+
+#[test]
+fn test_charge_subscription_unauthenticated_call_rejected() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    env.set_auths(&[]);
+    assert!(matches!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Err(_))
+    ));
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 20_000_000);
+}
+
+#[test]
+fn test_charge_requires_registered_operator() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    let stranger = Address::generate(&env);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    assert_eq!(
+        client.try_charge_subscription(&id, &stranger),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_charge_usage(&id, &stranger, &1_000_000i128),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_batch_charge(&stranger, &SorobanVec::from_array(&env, [id])),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+#[test]
+fn test_grant_and_revoke_billing_operator() {
+    let (env, client, token, admin) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    let operator = Address::generate(&env);
+
+    assert!(!client.has_role(&Role::BillingOperator, &operator));
+    client.grant_role(&admin, &Role::BillingOperator, &operator);
+    assert!(client.has_role(&Role::BillingOperator, &operator));
+    assert_eq!(
+        client.get_role_members(&Role::BillingOperator),
+        SorobanVec::from_array(&env, [operator.clone()])
+    );
+    client.charge_usage(&id, &operator, &1_000_000i128);
+
+    client.revoke_role(&admin, &Role::BillingOperator, &operator);
+    assert!(!client.has_role(&Role::BillingOperator, &operator));
+    assert_eq!(client.get_role_members(&Role::BillingOperator).len(), 0);
+    assert_eq!(
+        client.try_charge_usage(&id, &operator, &1_000_000i128),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 19_000_000);
+}
+
+#[test]
+fn test_grant_role_is_idempotent() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let operator = Address::generate(&env);
+
+    client.grant_role(&admin, &Role::Pauser, &operator);
+    client.grant_role(&admin, &Role::Pauser, &operator);
+    assert_eq!(client.get_role_members(&Role::Pauser).len(), 1);
+}
+
+#[test]
+fn test_role_management_is_admin_only() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let stranger = Address::generate(&env);
+    let operator = Address::generate(&env);
+
+    assert_eq!(
+        client.try_grant_role(&stranger, &Role::BillingOperator, &operator),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_revoke_role(&stranger, &Role::BillingOperator, &operator),
+        Err(Ok(Error::Unauthorized))
+    );
+
+    // Holding a non-admin role does not allow managing roles.
+    client.grant_role(&admin, &Role::ConfigManager, &stranger);
+    assert_eq!(
+        client.try_grant_role(&stranger, &Role::BillingOperator, &operator),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert!(!client.has_role(&Role::BillingOperator, &operator));
+}
+
+#[test]
+fn test_admin_role_cannot_be_granted_or_revoked() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let other = Address::generate(&env);
+
+    assert_eq!(
+        client.try_grant_role(&admin, &Role::Admin, &other),
+        Err(Ok(Error::InvalidRole))
+    );
+    assert_eq!(
+        client.try_revoke_role(&admin, &Role::Admin, &admin),
+        Err(Ok(Error::InvalidRole))
+    );
+    assert!(client.has_role(&Role::Admin, &admin));
+    assert!(!client.has_role(&Role::Admin, &other));
+    assert_eq!(
+        client.get_role_members(&Role::Admin),
+        SorobanVec::from_array(&env, [admin.clone()])
+    );
+}
+
+#[test]
+fn test_admin_implicitly_holds_every_role() {
+    let (env, client, token, admin) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    assert!(client.has_role(&Role::BillingOperator, &admin));
+    assert!(client.has_role(&Role::Pauser, &admin));
+    assert!(client.has_role(&Role::Recoverer, &admin));
+    assert!(client.has_role(&Role::ConfigManager, &admin));
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &admin);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+}
+
+#[test]
+fn test_billing_operator_cannot_move_funds_or_govern() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let recipient = Address::generate(&env);
+
+    assert_eq!(
+        client.try_recover_stranded_funds(
+            &operator,
+            &recipient,
+            &1i128,
+            &RecoveryReason::AccidentalTransfer
+        ),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_rotate_admin(&operator, &operator),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_min_topup(&operator, &1i128),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_grant_role(&operator, &Role::Recoverer, &operator),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_exit_protective_mode(&operator),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(client.get_admin(), admin);
+}
+
+#[test]
+fn test_scoped_roles_gate_their_entrypoints() {
+    let (env, client, token, admin) = setup_earnings_env();
+    let config_manager = Address::generate(&env);
+    let recoverer = Address::generate(&env);
+    let pauser = Address::generate(&env);
+    client.grant_role(&admin, &Role::ConfigManager, &config_manager);
+    client.grant_role(&admin, &Role::Recoverer, &recoverer);
+    client.grant_role(&admin, &Role::Pauser, &pauser);
+
+    // ConfigManager: min_topup and merchant config overrides.
+    client.set_min_topup(&config_manager, &2_000000i128);
+    assert_eq!(client.get_min_topup(), 2_000000i128);
+    let merchant = Address::generate(&env);
+    client.set_merchant_config(&config_manager, &merchant, &5_000_000i128, &0u64);
+    assert_eq!(
+        client
+            .get_merchant_config(&merchant)
+            .min_subscription_amount,
+        5_000_000
+    );
+    assert_eq!(
+        client.try_set_min_topup(&recoverer, &1i128),
+        Err(Ok(Error::Unauthorized))
+    );
+
+    // Recoverer: surplus recovery only.
+    mint_tokens(&env, &token, &client.address, 1_000_000i128);
+    let recipient = Address::generate(&env);
+    client.recover_stranded_funds(
+        &recoverer,
+        &recipient,
+        &1_000_000i128,
+        &RecoveryReason::AccidentalTransfer,
+    );
+    assert_eq!(
+        soroban_sdk::token::Client::new(&env, &token).balance(&recipient),
+        1_000_000
+    );
+    assert_eq!(
+        client.try_recover_stranded_funds(
+            &pauser,
+            &recipient,
+            &1i128,
+            &RecoveryReason::AccidentalTransfer
+        ),
+        Err(Ok(Error::Unauthorized))
+    );
+
+    // Pauser: lift protective mode.
+    client.exit_protective_mode(&pauser);
+    assert_eq!(
+        client.try_exit_protective_mode(&config_manager),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+#[test]
+fn test_role_events() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let operator = Address::generate(&env);
+
+    client.grant_role(&admin, &Role::BillingOperator, &operator);
+    let (_, topics, _) = env.events().all().last().unwrap();
+    let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+    let role: Role = topics.get(1).unwrap().into_val(&env);
+    let who: Address = topics.get(2).unwrap().into_val(&env);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "role_granted"));
+    assert_eq!(role, Role::BillingOperator);
+    assert_eq!(who, operator);
+
+    client.revoke_role(&admin, &Role::BillingOperator, &operator);
+    let (_, topics, _) = env.events().all().last().unwrap();
+    let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "role_revoked"));
+}
+
+// =============================================================================
+// Governance Council Tests
+// =============================================================================
+
+/// Helper: install a 2-of-3 council and return its signers.
+fn setup_council(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    admin: &Address,
+) -> (Address, Address, Address) {
+    let s1 = Address::generate(env);
+    let s2 = Address::generate(env);
+    let s3 = Address::generate(env);
+    let signers = SorobanVec::from_array(env, [s1.clone(), s2.clone(), s3.clone()]);
+    client.set_council(admin, &signers, &2u32);
+    (s1, s2, s3)
+}
+
+#[test]
+fn test_set_council_validation() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let s1 = Address::generate(&env);
+    let s2 = Address::generate(&env);
+
+    let two = SorobanVec::from_array(&env, [s1.clone(), s2.clone()]);
+    assert_eq!(
+        client.try_set_council(&admin, &two, &0u32),
+        Err(Ok(Error::InvalidCouncil))
+    );
+    assert_eq!(
+        client.try_set_council(&admin, &two, &3u32),
+        Err(Ok(Error::InvalidCouncil))
+    );
+    let dup = SorobanVec::from_array(&env, [s1.clone(), s1.clone()]);
+    assert_eq!(
+        client.try_set_council(&admin, &dup, &1u32),
+        Err(Ok(Error::InvalidCouncil))
+    );
+    assert_eq!(
+        client.try_set_council(&s1, &two, &1u32),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(client.get_council(), None);
+
+    client.set_council(&admin, &two, &2u32);
+    assert_eq!(
+        client.get_council(),
+        Some(Council {
+            signers: two.clone(),
+            threshold: 2
+        })
+    );
+
+    // Only the council can replace itself.
+    assert_eq!(
+        client.try_set_council(&admin, &two, &1u32),
+        Err(Ok(Error::GovernanceRequired))
+    );
+}
+
+#[test]
+fn test_direct_sensitive_actions_blocked_by_council() {
+    let (env, client, token, admin) = setup_earnings_env();
+    setup_council(&env, &client, &admin);
+    mint_tokens(&env, &token, &client.address, 1_000_000i128);
+    let other = Address::generate(&env);
+
+    assert_eq!(
+        client.try_rotate_admin(&admin, &other),
+        Err(Ok(Error::GovernanceRequired))
+    );
+    assert_eq!(
+        client.try_propose_admin(&admin, &other),
+        Err(Ok(Error::GovernanceRequired))
+    );
+    assert_eq!(
+        client.try_set_min_topup(&admin, &5i128),
+        Err(Ok(Error::GovernanceRequired))
+    );
+    assert_eq!(
+        client.try_recover_stranded_funds(
+            &admin,
+            &other,
+            &1_000_000i128,
+            &RecoveryReason::AccidentalTransfer
+        ),
+        Err(Ok(Error::GovernanceRequired))
+    );
+    assert_eq!(client.get_admin(), admin);
+    assert_eq!(client.get_min_topup(), 1_000000i128);
+}
+
+#[test]
+fn test_set_council_discards_pending_admin_handover() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let nominee = Address::generate(&env);
+    client.propose_admin(&admin, &nominee);
+    setup_council(&env, &client, &admin);
+
+    assert_eq!(client.get_pending_admin(), None);
+    assert_eq!(
+        client.try_accept_admin(&nominee),
+        Err(Ok(Error::GovernanceRequired))
+    );
+    assert_eq!(client.get_admin(), admin);
+}
+
+#[test]
+fn test_governance_proposal_threshold_and_execution() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let (s1, s2, s3) = setup_council(&env, &client, &admin);
+
+    let id = client.propose_action(&s1, &GovernanceAction::SetMinTopup(7_000000i128));
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.proposer, s1);
+    assert_eq!(
+        proposal.approvals,
+        SorobanVec::from_array(&env, [s1.clone()])
+    );
+    assert!(!proposal.executed);
+
+    // 1 of 2 approvals.
+    assert_eq!(
+        client.try_execute_proposal(&s1, &id),
+        Err(Ok(Error::ThresholdNotMet))
+    );
+
+    assert_eq!(client.approve_proposal(&s2, &id), 2);
+    client.execute_proposal(&s3, &id);
+    assert_eq!(client.get_min_topup(), 7_000000i128);
+
+    let proposal = client.get_proposal(&id);
+    assert!(proposal.executed);
+    assert_eq!(
+        proposal.approvals,
+        SorobanVec::from_array(&env, [s1.clone(), s2.clone()])
+    );
+    assert_eq!(
+        client.try_execute_proposal(&s1, &id),
+        Err(Ok(Error::ProposalAlreadyExecuted))
+    );
+    assert_eq!(
+        client.try_approve_proposal(&s3, &id),
+        Err(Ok(Error::ProposalAlreadyExecuted))
+    );
+}
+
+#[test]
+fn test_governance_rejects_duplicate_and_outsider_approvals() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let (s1, _, _) = setup_council(&env, &client, &admin);
+    let outsider = Address::generate(&env);
+
+    let id = client.propose_action(&s1, &GovernanceAction::SetMinTopup(1i128));
+    assert_eq!(
+        client.try_approve_proposal(&s1, &id),
+        Err(Ok(Error::AlreadyApproved))
+    );
+    assert_eq!(
+        client.try_approve_proposal(&outsider, &id),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_propose_action(&outsider, &GovernanceAction::SetMinTopup(1i128)),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_execute_proposal(&outsider, &id),
+        Err(Ok(Error::Unauthorized))
+    );
+    // The admin is not a council signer.
+    assert_eq!(
+        client.try_approve_proposal(&admin, &id),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_approve_proposal(&s1, &99u32),
+        Err(Ok(Error::NotFound))
+    );
+}
+
+#[test]
+fn test_governance_proposal_expires() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let (s1, s2, s3) = setup_council(&env, &client, &admin);
+
+    let id = client.propose_action(&s1, &GovernanceAction::SetMinTopup(7_000000i128));
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.expires_at, T0 + GOV_PROPOSAL_TTL_SECONDS);
+
+    // Still open at the expiry timestamp itself.
+    env.ledger().set_timestamp(proposal.expires_at);
+    assert_eq!(client.approve_proposal(&s2, &id), 2);
+
+    env.ledger().set_timestamp(proposal.expires_at + 1);
+    assert_eq!(
+        client.try_approve_proposal(&s3, &id),
+        Err(Ok(Error::ProposalExpired))
+    );
+    assert_eq!(
+        client.try_execute_proposal(&s1, &id),
+        Err(Ok(Error::ProposalExpired))
+    );
+    assert!(!client.get_proposal(&id).executed);
+    assert_eq!(client.get_min_topup(), 1_000000i128);
+}
+
+#[test]
+fn test_governance_rotate_admin() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let (s1, s2, _) = setup_council(&env, &client, &admin);
+    let new_admin = Address::generate(&env);
+
+    let id = client.propose_action(&s1, &GovernanceAction::RotateAdmin(new_admin.clone()));
+    client.approve_proposal(&s2, &id);
+    client.execute_proposal(&s2, &id);
+    assert_eq!(client.get_admin(), new_admin);
+}
+
+#[test]
+fn test_governance_recover_stranded_funds() {
+    let (env, client, token, admin) = setup_earnings_env();
+    let (s1, s2, _) = setup_council(&env, &client, &admin);
+    mint_tokens(&env, &token, &client.address, 3_000_000i128);
+    let recipient = Address::generate(&env);
+
+    // Still bounded by the vault surplus.
+    let too_much = client.propose_action(
+        &s1,
+        &GovernanceAction::RecoverStrandedFunds(
+            recipient.clone(),
+            5_000_000i128,
+            RecoveryReason::AccidentalTransfer,
+        ),
+    );
+    client.approve_proposal(&s2, &too_much);
+    assert_eq!(
+        client.try_execute_proposal(&s1, &too_much),
+        Err(Ok(Error::RecoveryExceedsSurplus))
+    );
+    assert!(!client.get_proposal(&too_much).executed);
+
+    let id = client.propose_action(
+        &s1,
+        &GovernanceAction::RecoverStrandedFunds(
+            recipient.clone(),
+            3_000_000i128,
+            RecoveryReason::AccidentalTransfer,
+        ),
+    );
+    client.approve_proposal(&s2, &id);
+    client.execute_proposal(&s1, &id);
+    assert_eq!(
+        soroban_sdk::token::Client::new(&env, &token).balance(&recipient),
+        3_000_000
+    );
+}
+
+#[test]
+fn test_governance_replace_council_drops_stale_approvals() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let (s1, s2, s3) = setup_council(&env, &client, &admin);
+    let s4 = Address::generate(&env);
+
+    // Approved by s1 before s1 is removed.
+    let pending = client.propose_action(&s1, &GovernanceAction::SetMinTopup(9i128));
+
+    let new_signers = SorobanVec::from_array(&env, [s2.clone(), s3.clone(), s4.clone()]);
+    let id = client.propose_action(
+        &s2,
+        &GovernanceAction::SetCouncil(new_signers.clone(), 2u32),
+    );
+    client.approve_proposal(&s3, &id);
+    client.execute_proposal(&s3, &id);
+    assert_eq!(client.get_council().unwrap().signers, new_signers);
+
+    // s1's approval no longer counts: two current signers are still needed.
+    assert_eq!(
+        client.try_execute_proposal(&s2, &pending),
+        Err(Ok(Error::ThresholdNotMet))
+    );
+    client.approve_proposal(&s4, &pending);
+    assert_eq!(
+        client.try_execute_proposal(&s2, &pending),
+        Err(Ok(Error::ThresholdNotMet))
+    );
+    client.approve_proposal(&s2, &pending);
+    client.execute_proposal(&s4, &pending);
+    assert_eq!(client.get_min_topup(), 9i128);
+    assert_eq!(
+        client.try_propose_action(&s1, &GovernanceAction::SetMinTopup(1i128)),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+#[test]
+fn test_governance_events() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let (s1, s2, _) = setup_council(&env, &client, &admin);
+
+    let last_topics = |env: &Env| {
+        let (_, topics, _) = env.events().all().last().unwrap();
+        topics
+    };
+
+    let id = client.propose_action(&s1, &GovernanceAction::SetMinTopup(3i128));
+    let topics = last_topics(&env);
+    let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+    let event_id: u32 = topics.get(1).unwrap().into_val(&env);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "gov_proposed"));
+    assert_eq!(event_id, id);
+
+    client.approve_proposal(&s2, &id);
+    let topics = last_topics(&env);
+    let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+    let who: Address = topics.get(2).unwrap().into_val(&env);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "gov_approved"));
+    assert_eq!(who, s2);
+
+    client.execute_proposal(&s1, &id);
+    let topics = last_topics(&env);
+    let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "gov_executed"));
+}
+
+// =============================================================================
+// Subscription Input Validation Tests
+// =============================================================================
+
+#[test]
+fn test_create_subscription_rejects_non_positive_amount() {
+    let (env, client, _, _) = setup_test_env();
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+
+    for amount in [0i128, -1i128] {
+        assert_eq!(
+            client.try_create_subscription(&subscriber, &merchant, &amount, &INTERVAL, &false),
+            Err(Ok(Error::InvalidAmount))
+        );
+    }
+    assert_eq!(client.get_merchant_subscription_count(&merchant), 0);
+}
+
+#[test]
+fn test_create_subscription_rejects_self_subscription() {
+    let (env, client, _, _) = setup_test_env();
+    let account = Address::generate(&env);
+
+    assert_eq!(
+        client.try_create_subscription(&account, &account, &1_000_000i128, &INTERVAL, &false),
+        Err(Ok(Error::SelfSubscription))
+    );
+}
+
+#[test]
+fn test_default_subscription_limits() {
+    let (_, client, _, _) = setup_test_env();
+    assert_eq!(
+        client.get_subscription_limits(),
+        SubscriptionLimits::default_limits()
+    );
+}
+
+#[test]
+fn test_create_subscription_enforces_global_limits() {
+    let (env, client, _, admin) = setup_test_env();
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let limits = SubscriptionLimits {
+        min_interval_seconds: 3600,
+        max_interval_seconds: 365 * 24 * 60 * 60,
+        max_amount: 100_000_000,
+    };
+    client.set_subscription_limits(&admin, &limits);
+    assert_eq!(client.get_subscription_limits(), limits);
+
+    assert_eq!(
+        client.try_create_subscription(&subscriber, &merchant, &1_000_000i128, &60u64, &false),
+        Err(Ok(Error::InvalidInterval))
+    );
+    assert_eq!(
+        client.try_create_subscription(
+            &subscriber,
+            &merchant,
+            &1_000_000i128,
+            &(366 * 24 * 60 * 60),
+            &false
+        ),
+        Err(Ok(Error::InvalidInterval))
+    );
+    assert_eq!(
+        client.try_create_subscription(&subscriber, &merchant, &100_000_001i128, &INTERVAL, &false),
+        Err(Ok(Error::InvalidAmount))
+    );
+
+    // Boundaries are inclusive.
+    client.create_subscription(&subscriber, &merchant, &100_000_000i128, &3600u64, &false);
+    client.create_subscription(
+        &subscriber,
+        &merchant,
+        &1i128,
+        &(365 * 24 * 60 * 60),
+        &false,
+    );
+    assert_eq!(client.get_merchant_subscription_count(&merchant), 2);
+}
+
+#[test]
+fn test_merchant_default_interval_checked_against_limits() {
+    let (env, client, _, admin) = setup_test_env();
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    client.set_merchant_config(&merchant, &merchant, &0i128, &60u64);
+    client.set_subscription_limits(
+        &admin,
+        &SubscriptionLimits {
+            min_interval_seconds: 3600,
+            max_interval_seconds: 0,
+            max_amount: 0,
+        },
+    );
+
+    assert_eq!(
+        client.try_create_subscription(&subscriber, &merchant, &1_000_000i128, &0u64, &false),
+        Err(Ok(Error::InvalidInterval))
+    );
+}
+
+#[test]
+fn test_set_subscription_limits_validation_and_auth() {
+    let (env, client, _, admin) = setup_test_env();
+    let stranger = Address::generate(&env);
+    let valid = SubscriptionLimits::default_limits();
+
+    assert_eq!(
+        client.try_set_subscription_limits(&stranger, &valid),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_subscription_limits(
+            &admin,
+            &SubscriptionLimits {
+                min_interval_seconds: 0,
+                max_interval_seconds: 0,
+                max_amount: 0,
+            }
+        ),
+        Err(Ok(Error::InvalidInterval))
+    );
+    assert_eq!(
+        client.try_set_subscription_limits(
+            &admin,
+            &SubscriptionLimits {
+                min_interval_seconds: 100,
+                max_interval_seconds: 99,
+                max_amount: 0,
+            }
+        ),
+        Err(Ok(Error::InvalidInterval))
+    );
+    assert_eq!(
+        client.try_set_subscription_limits(
+            &admin,
+            &SubscriptionLimits {
+                min_interval_seconds: 1,
+                max_interval_seconds: 0,
+                max_amount: -1,
+            }
+        ),
+        Err(Ok(Error::InvalidAmount))
+    );
+
+    let config_manager = Address::generate(&env);
+    client.grant_role(&admin, &Role::ConfigManager, &config_manager);
+    client.set_subscription_limits(&config_manager, &valid);
+}
+
+// =============================================================================
+// Create With Deposit Tests
+// =============================================================================
+
+#[test]
+fn test_create_subscription_with_deposit_funds_in_one_call() {
+    let (env, client, token, _) = setup_earnings_env();
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 50_000_000);
+
+    let id = client.create_subscription_with_deposit(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &30_000_000i128,
+        &false,
+    );
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 30_000_000);
+    assert_eq!(sub.last_payment_timestamp, T0);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    assert_eq!(token_client.balance(&subscriber), 20_000_000);
+    assert_eq!(token_client.balance(&client.address), 30_000_000);
+    assert_eq!(client.get_solvency_report().total_prepaid, 30_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+
+    // First interval charge is due one interval after creation, as usual.
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &add_operator(&env, &client));
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 20_000_000);
+}
+
+#[test]
+fn test_create_subscription_with_deposit_pay_upfront() {
+    let (env, client, token, _) = setup_earnings_env();
+    let operator = add_operator(&env, &client);
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 50_000_000);
+
+    let id = client.create_subscription_with_deposit(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &30_000_000i128,
+        &true,
+    );
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 20_000_000);
+    assert_eq!(sub.last_payment_timestamp, T0);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+    let report = client.get_solvency_report();
+    assert_eq!(report.total_prepaid, 20_000_000);
+    assert_eq!(report.total_merchant_balance, 10_000_000);
+    assert!(report.is_solvent);
+
+    // The paid period cannot be charged again.
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::Replay))
+    );
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(client.get_merchant_balance(&merchant), 20_000_000);
+}
+
+#[test]
+fn test_create_subscription_with_deposit_rejections() {
+    let (env, client, token, _) = setup_earnings_env();
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 50_000_000);
+
+    // Below min_topup (1 USDC).
+    assert_eq!(
+        client.try_create_subscription_with_deposit(
+            &subscriber,
+            &merchant,
+            &10_000_000i128,
+            &INTERVAL,
+            &false,
+            &999_999i128,
+            &false
+        ),
+        Err(Ok(Error::BelowMinimumTopup))
+    );
+    // Upfront charge not covered by the deposit.
+    assert_eq!(
+        client.try_create_subscription_with_deposit(
+            &subscriber,
+            &merchant,
+            &10_000_000i128,
+            &INTERVAL,
+            &false,
+            &5_000_000i128,
+            &true
+        ),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    // Same term validation as create_subscription.
+    assert_eq!(
+        client.try_create_subscription_with_deposit(
+            &subscriber,
+            &merchant,
+            &0i128,
+            &INTERVAL,
+            &false,
+            &5_000_000i128,
+            &false
+        ),
+        Err(Ok(Error::InvalidAmount))
+    );
+
+    // Nothing was created or transferred.
+    assert_eq!(client.get_merchant_subscription_count(&merchant), 0);
+    assert_eq!(
+        soroban_sdk::token::Client::new(&env, &token).balance(&subscriber),
+        50_000_000
+    );
+}
+
+#[test]
+fn test_create_subscription_with_deposit_emits_single_event() {
+    let (env, client, token, _) = setup_earnings_env();
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 50_000_000);
+
+    let id = client.create_subscription_with_deposit(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &30_000_000i128,
+        &true,
+    );
+
+    // The token transfer emits its own event; the vault emits exactly one.
+    let mut vault_events = SorobanVec::new(&env);
+    for event in env.events().all().iter() {
+        if event.0 == client.address {
+            vault_events.push_back(event);
+        }
+    }
+    assert_eq!(vault_events.len(), 1);
+    let (_, topics, data) = vault_events.get(0).unwrap();
+    let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "created_funded"));
+    let event: SubscriptionCreatedAndFundedEvent = data.into_val(&env);
+    assert_eq!(event.subscription_id, id);
+    assert_eq!(event.initial_deposit, 30_000_000);
+    assert_eq!(event.upfront_charge, 10_000_000);
+    assert_eq!(event.prepaid_balance, 20_000_000);
+}
+
+// =============================================================================
+// Persistent Storage & TTL Tests
+// =============================================================================
+
+fn persistent_ttl(env: &Env, client: &SubscriptionVaultClient, key: &DataKey) -> u32 {
+    env.as_contract(&client.address, || env.storage().persistent().get_ttl(key))
+}
+
+fn advance_ledgers(env: &Env, ledgers: u32) {
+    env.ledger().with_mut(|li| li.sequence_number += ledgers);
+}
+
+#[test]
+fn test_subscription_data_lives_in_persistent_storage() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &add_operator(&env, &client));
+
+    env.as_contract(&client.address, || {
+        let persistent = env.storage().persistent();
+        assert!(persistent.has(&DataKey::Subscription(id)));
+        assert!(persistent.has(&DataKey::ChargedPeriod(id)));
+        assert!(persistent.has(&DataKey::MerchantSubCount(merchant.clone())));
+        assert!(persistent.has(&DataKey::MerchantSubsPage(merchant.clone(), 0)));
+        assert!(persistent.has(&DataKey::MerchantBalance(merchant.clone())));
+        assert!(!env.storage().instance().has(&id));
+    });
+    assert_eq!(
+        persistent_ttl(&env, &client, &DataKey::Subscription(id)),
+        PERSISTENT_BUMP_AMOUNT
+    );
+}
+
+#[test]
+fn test_per_entity_records_live_in_persistent_storage() {
+    let (env, client, _, admin) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let operator = add_operator(&env, &client);
+    set_catch_up(&client, &merchant, CatchUpMode::CollectAll, 3);
+    client.set_trial_limit(&merchant, &merchant, &2);
+    client.set_dunning_policy(
+        &merchant,
+        &merchant,
+        &DunningPolicy {
+            grace_period_seconds: 3 * 24 * 60 * 60,
+            max_retries: 2,
+            retry_interval_seconds: 0,
+            final_action: DunningFinalAction::Suspend,
+        },
+    );
+    let (s1, _, _) = setup_council(&env, &client, &admin);
+    let proposal = client.propose_action(&s1, &GovernanceAction::SetMinTopup(5i128));
+
+    let merchant_keys = [
+        DataKey::CatchUpPolicy(merchant.clone()),
+        DataKey::TrialLimit(merchant.clone()),
+        DataKey::DunningPolicy(merchant.clone()),
+    ];
+    env.as_contract(&client.address, || {
+        let persistent = env.storage().persistent();
+        let instance = env.storage().instance();
+        for key in merchant_keys.iter() {
+            assert!(persistent.has(key));
+            assert!(!instance.has(key));
+        }
+        assert!(persistent.has(&DataKey::RoleMembers(Role::BillingOperator)));
+        assert!(persistent.has(&DataKey::GovProposal(proposal)));
+        assert!(!instance.has(&DataKey::GovProposal(proposal)));
+    });
+    assert!(client.has_role(&Role::BillingOperator, &operator));
+    assert_eq!(
+        persistent_ttl(&env, &client, &DataKey::TrialLimit(merchant)),
+        PERSISTENT_BUMP_AMOUNT
+    );
+}
+
+#[test]
+fn test_touching_subscription_extends_ttl() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    let key = DataKey::Subscription(id);
+
+    // Within the threshold window nothing is re-extended.
+    advance_ledgers(&env, DAY_IN_LEDGERS / 2);
+    client.get_subscription(&id);
+    assert_eq!(
+        persistent_ttl(&env, &client, &key),
+        PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS / 2
+    );
+
+    // Once the TTL drops below the threshold, a read bumps it back.
+    advance_ledgers(&env, DAY_IN_LEDGERS);
+    assert!(persistent_ttl(&env, &client, &key) < PERSISTENT_LIFETIME_THRESHOLD);
+    client.get_subscription(&id);
+    assert_eq!(persistent_ttl(&env, &client, &key), PERSISTENT_BUMP_AMOUNT);
+}
+
+#[test]
+fn test_extend_ttl_entrypoint() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &add_operator(&env, &client));
+
+    advance_ledgers(&env, 10 * DAY_IN_LEDGERS);
+    client.extend_ttl(&SorobanVec::from_array(&env, [id]));
+
+    for key in [
+        DataKey::Subscription(id),
+        DataKey::ChargedPeriod(id),
+        DataKey::MerchantSubCount(merchant.clone()),
+        DataKey::MerchantSubsPage(merchant.clone(), 0),
+    ] {
+        assert_eq!(persistent_ttl(&env, &client, &key), PERSISTENT_BUMP_AMOUNT);
+    }
+}
+
+#[test]
+fn test_extend_ttl_unknown_subscription() {
+    let (env, client, _, _) = setup_earnings_env();
+    assert_eq!(
+        client.try_extend_ttl(&SorobanVec::from_array(&env, [42u32])),
+        Err(Ok(Error::NotFound))
+    );
+}
+
+// =============================================================================
+// Merchant Index Paging Tests
+// =============================================================================
+
+/// Helper: create `n` subscriptions for `merchant`, returning their IDs.
+fn create_merchant_subs(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    merchant: &Address,
+    n: u32,
+) -> SorobanVec<u32> {
+    let subscriber = Address::generate(env);
+    let mut ids = SorobanVec::new(env);
+    for _ in 0..n {
+        ids.push_back(client.create_subscription(
+            &subscriber,
+            merchant,
+            &1_000_000i128,
+            &INTERVAL,
+            &false,
+        ));
+    }
+    ids
+}
+
+#[test]
+fn test_merchant_index_is_split_into_fixed_size_pages() {
+    let (env, client, _, _) = setup_test_env();
+    let merchant = Address::generate(&env);
+    let n = 2 * MERCHANT_SUBS_PAGE_SIZE + 2;
+    create_merchant_subs(&env, &client, &merchant, n);
+
+    assert_eq!(client.get_merchant_subscription_count(&merchant), n);
+    env.as_contract(&client.address, || {
+        let persistent = env.storage().persistent();
+        for (page, expected_len) in [
+            (0u32, MERCHANT_SUBS_PAGE_SIZE),
+            (1, MERCHANT_SUBS_PAGE_SIZE),
+            (2, 2),
+        ] {
+            let ids: SorobanVec<u32> = persistent
+                .get(&DataKey::MerchantSubsPage(merchant.clone(), page))
+                .unwrap();
+            assert_eq!(ids.len(), expected_len);
+        }
+        assert!(!persistent.has(&DataKey::MerchantSubsPage(merchant.clone(), 3)));
+    });
+}
+
+#[test]
+fn test_get_subscriptions_by_merchant_across_page_boundary() {
+    let (env, client, _, _) = setup_test_env();
+    let merchant = Address::generate(&env);
+    let other = Address::generate(&env);
+    let ids = create_merchant_subs(&env, &client, &merchant, MERCHANT_SUBS_PAGE_SIZE + 10);
+    // Interleave another merchant so IDs are not contiguous per merchant.
+    create_merchant_subs(&env, &client, &other, 3);
+
+    let start = MERCHANT_SUBS_PAGE_SIZE - 5;
+    let page = client.get_subscriptions_by_merchant(&merchant, &start, &10u32);
+    assert_eq!(page.len(), 10);
+    for sub in page.iter() {
+        assert_eq!(sub.merchant, merchant);
+    }
+    let all = client.get_subscriptions_by_merchant(&merchant, &0u32, &(ids.len() + 5));
+    assert_eq!(all.len(), ids.len());
+
+    // Limit past the end is truncated; start past the end is empty.
+    let tail = client.get_subscriptions_by_merchant(&merchant, &(ids.len() - 2), &10u32);
+    assert_eq!(tail.len(), 2);
+    assert_eq!(
+        client
+            .get_subscriptions_by_merchant(&merchant, &ids.len(), &10u32)
+            .len(),
+        0
+    );
+    assert_eq!(
+        client
+            .get_subscriptions_by_merchant(&merchant, &0u32, &0u32)
+            .len(),
+        0
+    );
+}
+
+#[test]
+fn test_extend_ttl_bumps_merchant_page_holding_subscription() {
+    let (env, client, _, _) = setup_test_env();
+    let merchant = Address::generate(&env);
+    let ids = create_merchant_subs(&env, &client, &merchant, MERCHANT_SUBS_PAGE_SIZE + 1);
+    let last = ids.get(ids.len() - 1).unwrap();
+
+    advance_ledgers(&env, 10 * DAY_IN_LEDGERS);
+    client.extend_ttl(&SorobanVec::from_array(&env, [last]));
+
+    let bumped = DataKey::MerchantSubsPage(merchant.clone(), 1);
+    let untouched = DataKey::MerchantSubsPage(merchant.clone(), 0);
+    assert_eq!(
+        persistent_ttl(&env, &client, &bumped),
+        PERSISTENT_BUMP_AMOUNT
+    );
+    assert!(persistent_ttl(&env, &client, &untouched) < PERSISTENT_LIFETIME_THRESHOLD);
+}
+
+// =============================================================================
+// Lifecycle Event Tests
+// =============================================================================
+
+/// Helper: the most recent event published by the vault (ignoring token events).
+fn last_vault_event(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+) -> (
+    soroban_sdk::Symbol,
+    SorobanVec<soroban_sdk::Val>,
+    soroban_sdk::Val,
+) {
+    let mut last = None;
+    for (contract, topics, data) in env.events().all().iter() {
+        if contract == client.address {
+            last = Some((topics, data));
+        }
+    }
+    let (topics, data) = last.expect("no vault event");
+    let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(env);
+    (name, topics, data)
+}
+
+/// Helper: assert the uniform `(name, id, merchant, subscriber)` topic layout.
+fn assert_subscription_topics(
+    env: &Env,
+    topics: &SorobanVec<soroban_sdk::Val>,
+    id: u32,
+    merchant: &Address,
+    subscriber: &Address,
+) {
+    assert_eq!(topics.len(), 4);
+    let topic_id: u32 = topics.get(1).unwrap().into_val(env);
+    let topic_merchant: Address = topics.get(2).unwrap().into_val(env);
+    let topic_subscriber: Address = topics.get(3).unwrap().into_val(env);
+    assert_eq!(topic_id, id);
+    assert_eq!(topic_merchant, *merchant);
+    assert_eq!(topic_subscriber, *subscriber);
+}
+
+#[test]
+fn test_create_subscription_emits_created_event() {
+    let (env, client, _, _) = setup_test_env();
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let id = client.create_subscription(&subscriber, &merchant, &10_000_000i128, &INTERVAL, &true);
+
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "created"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriptionCreatedEvent = data.into_val(&env);
+    assert_eq!(event.version, crate::EVENT_SCHEMA_VERSION);
+    assert_eq!(event.subscription_id, id);
+    assert_eq!(event.amount, 10_000_000);
+    assert_eq!(event.interval_seconds, INTERVAL);
+    assert!(event.usage_enabled);
+}
+
+#[test]
+fn test_deposit_emits_typed_event() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "deposited"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::FundsDepositedEvent = data.into_val(&env);
+    assert_eq!(event.version, crate::EVENT_SCHEMA_VERSION);
+    assert_eq!(event.subscriber, subscriber);
+    assert_eq!(event.amount, 20_000_000);
+    assert_eq!(event.prepaid_balance, 20_000_000);
+}
+
+#[test]
+fn test_pause_resume_cancel_withdraw_emit_typed_events() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+
+    client.pause_subscription(&id, &merchant);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "paused"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriptionPausedEvent = data.into_val(&env);
+    assert_eq!(event.authorizer, merchant);
+
+    client.resume_subscription(&id, &subscriber);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "resumed"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriptionResumedEvent = data.into_val(&env);
+    assert_eq!(event.authorizer, subscriber);
+
+    client.cancel_subscription(&id, &subscriber);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "cancelled"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriptionCancelledEvent = data.into_val(&env);
+    assert_eq!(event.version, crate::EVENT_SCHEMA_VERSION);
+    assert_eq!(event.refund_amount, 20_000_000);
+
+    client.withdraw_subscriber_funds(&id, &subscriber);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "subscriber_withdrawn"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriberWithdrawalEvent = data.into_val(&env);
+    assert_eq!(event.amount, 20_000_000);
+}
+
+#[test]
+fn test_charge_events_and_merchant_withdrawal_event() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 20_000_000);
+    let operator = add_operator(&env, &client);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "charged"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriptionChargedEvent = data.into_val(&env);
+    assert_eq!(event.version, crate::EVENT_SCHEMA_VERSION);
+    assert_eq!(event.amount, 10_000_000);
+    assert_eq!(event.prepaid_balance, 10_000_000);
+    assert_eq!(event.timestamp, T0 + INTERVAL);
+    assert_eq!(event.periods, 1);
+
+    client.withdraw_merchant_funds(&merchant, &4_000_000i128);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "withdrawn"));
+    let topic_merchant: Address = topics.get(1).unwrap().into_val(&env);
+    assert_eq!(topic_merchant, merchant);
+    let event: crate::MerchantWithdrawalEvent = data.into_val(&env);
+    assert_eq!(event.version, crate::EVENT_SCHEMA_VERSION);
+    assert_eq!(event.amount, 4_000_000);
+    assert_eq!(event.remaining_balance, 6_000_000);
+}
+
+#[test]
+fn test_usage_charge_emits_usage_and_insufficient_balance_events() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 5_000_000);
+    let id = client.create_subscription(&subscriber, &merchant, &10_000_000i128, &INTERVAL, &true);
+    client.deposit_funds(&id, &subscriber, &5_000_000i128);
+    let operator = add_operator(&env, &client);
+
+    client.charge_usage(&id, &operator, &2_000_000i128);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "usage_charged"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::UsageChargedEvent = data.into_val(&env);
+    assert_eq!(event.amount, 2_000_000);
+    assert_eq!(event.prepaid_balance, 3_000_000);
+
+    // Draining the balance also reports the status change.
+    client.charge_usage(&id, &operator, &3_000_000i128);
+    let (name, topics, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "insufficient_balance"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+}
+
+#[test]
+fn test_batch_charge_underfunded_emits_insufficient_balance_event() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 5_000_000);
+    let operator = add_operator(&env, &client);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.batch_charge(&operator, &SorobanVec::from_array(&env, [id]));
+
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "insufficient_balance"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::InsufficientBalanceEvent = data.into_val(&env);
+    assert_eq!(event.amount_due, 10_000_000);
+    assert_eq!(event.prepaid_balance, 5_000_000);
+}
+
+#[test]
+fn test_failed_lifecycle_call_emits_no_event() {
+    let (env, client, _, _) = setup_test_env();
+    let (id, _, _) = create_test_subscription(&env, &client, SubscriptionStatus::Active);
+    let stranger = Address::generate(&env);
+
+    assert!(client.try_pause_subscription(&id, &stranger).is_err());
+    let paused = soroban_sdk::Symbol::new(&env, "paused");
+    for (_, topics, _) in env.events().all().iter() {
+        let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+        assert_ne!(name, paused);
+    }
+}
+
+// =============================================================================
+// Auto-Reactivation Tests
+// =============================================================================
+
+/// Helper: a subscription whose interval charge failed inside `batch_charge`,
+/// leaving it `InsufficientBalance` with 5 of the 10 USDC due.
+fn setup_underfunded_subscription(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    token: &Address,
+    merchant: &Address,
+) -> (u32, Address) {
+    let (id, subscriber) =
+        create_funded_subscription(env, client, token, merchant, 10_000_000, 5_000_000);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.batch_charge(
+        &add_operator(env, client),
+        &SorobanVec::from_array(env, [id]),
+    );
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::InsufficientBalance
+    );
+    mint_tokens(env, token, &subscriber, 50_000_000);
+    (id, subscriber)
+}
+
+#[test]
+fn test_deposit_reactivates_once_balance_covers_amount() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) = setup_underfunded_subscription(&env, &client, &token, &merchant);
+
+    // 5 + 2 < 10: still underfunded.
+    client.deposit_funds(&id, &subscriber, &2_000_000i128);
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::InsufficientBalance
+    );
+
+    client.deposit_funds(&id, &subscriber, &3_000_000i128);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "reactivated"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriptionReactivatedEvent = data.into_val(&env);
+    assert_eq!(event.collected, 0);
+    assert_eq!(event.prepaid_balance, 10_000_000);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.status, SubscriptionStatus::Active);
+    assert_eq!(sub.prepaid_balance, 10_000_000);
+
+    // The failed period is still billable by the operator.
+    client.charge_subscription(&id, &add_operator(&env, &client));
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+}
+
+#[test]
+fn test_deposit_and_collect_charges_failed_period() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) = setup_underfunded_subscription(&env, &client, &token, &merchant);
+
+    client.deposit_funds_and_collect(&id, &subscriber, &15_000_000i128);
+    let (name, _, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "reactivated"));
+    let event: crate::SubscriptionReactivatedEvent = data.into_val(&env);
+    assert_eq!(event.collected, 10_000_000);
+    assert_eq!(event.prepaid_balance, 10_000_000);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.status, SubscriptionStatus::Active);
+    assert_eq!(sub.prepaid_balance, 10_000_000);
+    assert_eq!(sub.last_payment_timestamp, T0 + INTERVAL);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+
+    // The collected period cannot be charged again.
+    assert_eq!(
+        client.try_charge_subscription(&id, &add_operator(&env, &client)),
+        Err(Ok(Error::Replay))
+    );
+}
+
+#[test]
+fn test_deposit_and_collect_on_active_subscription_only_deposits() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 10_000_000);
+    mint_tokens(&env, &token, &subscriber, 5_000_000);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+
+    client.deposit_funds_and_collect(&id, &subscriber, &5_000_000i128);
+    let (name, _, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "deposited"));
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 15_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+}
+
+#[test]
+fn test_deposit_and_collect_skips_charge_when_period_not_due() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 30_000_000);
+    let id = client.create_subscription(&subscriber, &merchant, &10_000_000i128, &INTERVAL, &true);
+    client.deposit_funds(&id, &subscriber, &5_000_000i128);
+
+    // Usage drains the balance mid-interval.
+    client.charge_usage(&id, &add_operator(&env, &client), &5_000_000i128);
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::InsufficientBalance
+    );
+
+    client.deposit_funds_and_collect(&id, &subscriber, &10_000_000i128);
+    let (_, _, data) = last_vault_event(&env, &client);
+    let event: crate::SubscriptionReactivatedEvent = data.into_val(&env);
+    assert_eq!(event.collected, 0);
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.status, SubscriptionStatus::Active);
+    assert_eq!(sub.prepaid_balance, 10_000_000);
+}
+
+// =============================================================================
+// Dunning Tests
+// =============================================================================
+
+/// Helper: a 10 USDC subscription holding 5 USDC under a merchant dunning policy
+/// (7-day grace, retries at least an hour apart).
+fn setup_dunning_subscription(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    token: &Address,
+    merchant: &Address,
+    max_retries: u32,
+    final_action: DunningFinalAction,
+) -> (u32, Address, Address) {
+    client.set_dunning_policy(
+        merchant,
+        merchant,
+        &DunningPolicy {
+            grace_period_seconds: 7 * 24 * 60 * 60,
+            max_retries,
+            retry_interval_seconds: 3_600,
+            final_action,
+        },
+    );
+    let (id, subscriber) =
+        create_funded_subscription(env, client, token, merchant, 10_000_000, 5_000_000);
+    (id, subscriber, add_operator(env, client))
+}
+
+#[test]
+fn test_set_and_get_dunning_policy() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    assert_eq!(client.get_dunning_policy(&merchant), None);
+
+    let policy = DunningPolicy {
+        grace_period_seconds: 3_600,
+        max_retries: 2,
+        retry_interval_seconds: 600,
+        final_action: DunningFinalAction::Cancel,
+    };
+    client.set_dunning_policy(&merchant, &merchant, &policy);
+    assert_eq!(client.get_dunning_policy(&merchant), Some(policy.clone()));
+
+    let stranger = Address::generate(&env);
+    assert_eq!(
+        client.try_set_dunning_policy(&stranger, &merchant, &policy),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+#[test]
+fn test_failed_charge_under_policy_moves_to_past_due() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        2,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.batch_charge(&operator, &SorobanVec::from_array(&env, [id]));
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "past_due"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: DunningEvent = data.into_val(&env);
+    assert_eq!(event.attempts, 1);
+    assert_eq!(event.grace_ends_at, T0 + INTERVAL + 7 * 24 * 60 * 60);
+
+    let state = client.get_dunning_state(&id);
+    assert_eq!(state.status, SubscriptionStatus::PastDue);
+    assert!(state.entitled);
+    assert_eq!(state.attempts, 1);
+    assert_eq!(state.first_failed_at, T0 + INTERVAL);
+    assert_eq!(state.retries_remaining, 2);
+    assert!(client.get_next_charge_info(&id).is_charge_expected);
+}
+
+#[test]
+fn test_dunning_retries_exhausted_suspends() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        2,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    let ids = SorobanVec::from_array(&env, [id]);
+    client.batch_charge(&operator, &ids);
+    env.ledger().set_timestamp(T0 + INTERVAL + 3_600);
+    client.batch_charge(&operator, &ids);
+    assert_eq!(client.get_dunning_state(&id).retries_remaining, 1);
+    assert!(client.get_dunning_state(&id).entitled);
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::PastDue
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL + 7_200);
+    client.batch_charge(&operator, &ids);
+    let (name, _, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "suspended"));
+
+    let state = client.get_dunning_state(&id);
+    assert_eq!(state.status, SubscriptionStatus::Suspended);
+    assert!(!state.entitled);
+    assert_eq!(state.attempts, 3);
+    assert_eq!(state.retries_remaining, 0);
+    assert!(!client.get_next_charge_info(&id).is_charge_expected);
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::NotActive))
+    );
+}
+
+#[test]
+fn test_dunning_grace_expiry_cancels() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        10,
+        DunningFinalAction::Cancel,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    let ids = SorobanVec::from_array(&env, [id]);
+    client.batch_charge(&operator, &ids);
+
+    // Grace runs out long before the retry budget does.
+    env.ledger().set_timestamp(T0 + INTERVAL + 7 * 24 * 60 * 60);
+    assert!(!client.get_dunning_state(&id).entitled);
+    client.batch_charge(&operator, &ids);
+    let (name, _, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "cancelled"));
+    let event: SubscriptionCancelledEvent = data.into_val(&env);
+    assert_eq!(event.refund_amount, 5_000_000);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.status, SubscriptionStatus::Cancelled);
+    assert_eq!(sub.prepaid_balance, 5_000_000);
+}
+
+#[test]
+fn test_successful_retry_clears_dunning() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        3,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.batch_charge(&operator, &SorobanVec::from_array(&env, [id]));
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::PastDue
+    );
+
+    // A top-up that covers the amount reactivates and collects the failed period.
+    mint_tokens(&env, &token, &subscriber, 50_000_000);
+    client.deposit_funds_and_collect(&id, &subscriber, &5_000_000i128);
+    let state = client.get_dunning_state(&id);
+    assert_eq!(state.status, SubscriptionStatus::Active);
+    assert!(state.entitled);
+    assert_eq!(state.attempts, 0);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+}
+
+#[test]
+fn test_single_charge_failure_persists_dunning_progress() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        2,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    let res = client.charge_subscription(&id, &operator);
+    assert_eq!(
+        res,
+        BatchChargeResult {
+            success: false,
+            error_code: Error::InsufficientBalance.to_code(),
+        }
+    );
+    let (name, topics, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "past_due"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+
+    let state = client.get_dunning_state(&id);
+    assert_eq!(state.status, SubscriptionStatus::PastDue);
+    assert_eq!(state.attempts, 1);
+    assert_eq!(state.first_failed_at, T0 + INTERVAL);
+    assert_eq!(state.grace_ends_at, T0 + INTERVAL + 7 * 24 * 60 * 60);
+
+    // The retry counts against the same cycle and keeps the grace clock.
+    env.ledger().set_timestamp(T0 + INTERVAL + 3_600);
+    assert!(!client.charge_subscription(&id, &operator).success);
+    let state = client.get_dunning_state(&id);
+    assert_eq!(state.attempts, 2);
+    assert_eq!(state.first_failed_at, T0 + INTERVAL);
+    assert_eq!(state.retries_remaining, 1);
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 5_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+}
+
+#[test]
+fn test_dunning_retry_before_interval_is_rejected() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        2,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    assert!(!client.charge_subscription(&id, &operator).success);
+
+    // Retries inside the interval are rejected and do not use up the budget.
+    env.ledger().set_timestamp(T0 + INTERVAL + 3_599);
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::RetryTooSoon))
+    );
+    let results = client.batch_charge(&operator, &SorobanVec::from_array(&env, [id]));
+    assert_eq!(
+        results.get(0).unwrap().error_code,
+        Error::RetryTooSoon.to_code()
+    );
+    let state = client.get_dunning_state(&id);
+    assert_eq!(state.attempts, 1);
+    assert_eq!(state.retries_remaining, 2);
+
+    // Once the interval has passed the retry counts, and the next one waits again.
+    env.ledger().set_timestamp(T0 + INTERVAL + 3_600);
+    assert!(!client.charge_subscription(&id, &operator).success);
+    assert_eq!(client.get_dunning_state(&id).attempts, 2);
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::RetryTooSoon))
+    );
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::PastDue
+    );
+}
+
+#[test]
+fn test_single_charge_retries_exhausted_suspends() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        1,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    assert!(!client.charge_subscription(&id, &operator).success);
+    env.ledger().set_timestamp(T0 + INTERVAL + 3_600);
+    assert!(!client.charge_subscription(&id, &operator).success);
+    let (name, _, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "suspended"));
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Suspended
+    );
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::NotActive))
+    );
+}
+
+#[test]
+fn test_single_charge_retry_clears_dunning() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        3,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    assert!(!client.charge_subscription(&id, &operator).success);
+
+    mint_tokens(&env, &token, &subscriber, 5_000_000);
+    client.deposit_funds(&id, &subscriber, &5_000_000i128);
+    assert!(client.charge_subscription(&id, &operator).success);
+    let state = client.get_dunning_state(&id);
+    assert_eq!(state.status, SubscriptionStatus::Active);
+    assert_eq!(state.attempts, 0);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+}
+
+#[test]
+fn test_resume_of_unfunded_suspended_subscription_is_rejected() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        0,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+    env.ledger().set_timestamp(T0 + INTERVAL + 3_600);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Suspended
+    );
+
+    assert_eq!(
+        client.try_resume_subscription(&id, &subscriber),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    let state = client.get_dunning_state(&id);
+    assert_eq!(state.status, SubscriptionStatus::Suspended);
+    assert!(!state.entitled);
+    assert_eq!(state.attempts, 2);
+}
+
+#[test]
+fn test_deposit_reactivates_suspended_subscription() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber, operator) = setup_dunning_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        0,
+        DunningFinalAction::Suspend,
+    );
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    let ids = SorobanVec::from_array(&env, [id]);
+    client.batch_charge(&operator, &ids);
+    env.ledger().set_timestamp(T0 + INTERVAL + 3_600);
+    client.batch_charge(&operator, &ids);
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Suspended
+    );
+
+    mint_tokens(&env, &token, &subscriber, 50_000_000);
+    client.deposit_funds(&id, &subscriber, &5_000_000i128);
+    let (name, _, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "reactivated"));
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Active
+    );
+    assert_eq!(client.get_dunning_state(&id).attempts, 0);
+}
+
+// =============================================================================
+// Catch-Up Billing Tests
+// =============================================================================
+
+fn set_catch_up(
+    client: &SubscriptionVaultClient,
+    merchant: &Address,
+    mode: CatchUpMode,
+    max_periods: u32,
+) {
+    client.set_catch_up_policy(merchant, merchant, &CatchUpPolicy { mode, max_periods });
+}
+
+#[test]
+fn test_catch_up_policy_defaults_and_validation() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    assert_eq!(
+        client.get_catch_up_policy(&merchant),
+        CatchUpPolicy {
+            mode: CatchUpMode::LatestOnly,
+            max_periods: 1
+        }
+    );
+
+    let invalid = CatchUpPolicy {
+        mode: CatchUpMode::CollectAll,
+        max_periods: 0,
+    };
+    assert_eq!(
+        client.try_set_catch_up_policy(&merchant, &merchant, &invalid),
+        Err(Ok(Error::InvalidCatchUpPolicy))
+    );
+    let valid = CatchUpPolicy {
+        mode: CatchUpMode::CollectAll,
+        max_periods: 6,
+    };
+    assert_eq!(
+        client.try_set_catch_up_policy(&Address::generate(&env), &merchant, &valid),
+        Err(Ok(Error::Unauthorized))
+    );
+    client.set_catch_up_policy(&merchant, &merchant, &valid);
+    assert_eq!(client.get_catch_up_policy(&merchant), valid);
+}
+
+#[test]
+fn test_latest_only_collects_one_missed_period() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+
+    let now = T0 + 3 * INTERVAL + 100;
+    env.ledger().set_timestamp(now);
+    let due = client.get_outstanding_periods(&id);
+    assert_eq!(due.periods, 3);
+    assert_eq!(due.collectible, 1);
+    assert_eq!(due.amount_due, 10_000_000);
+    assert_eq!(due.next_anchor, now);
+
+    client.charge_subscription(&id, &add_operator(&env, &client));
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 40_000_000);
+    assert_eq!(sub.last_payment_timestamp, now);
+    assert_eq!(client.get_outstanding_periods(&id).periods, 0);
+}
+
+#[test]
+fn test_collect_all_settles_every_missed_period() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    set_catch_up(&client, &merchant, CatchUpMode::CollectAll, 12);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+    let operator = add_operator(&env, &client);
+
+    env.ledger().set_timestamp(T0 + 3 * INTERVAL + 100);
+    let due = client.get_outstanding_periods(&id);
+    assert_eq!(due.collectible, 3);
+    assert_eq!(due.amount_due, 30_000_000);
+    assert_eq!(due.next_anchor, T0 + 3 * INTERVAL);
+
+    client.charge_subscription(&id, &operator);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "charged"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriptionChargedEvent = data.into_val(&env);
+    assert_eq!(event.amount, 30_000_000);
+    assert_eq!(event.periods, 3);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 20_000_000);
+    assert_eq!(sub.last_payment_timestamp, T0 + 3 * INTERVAL);
+    assert_eq!(client.get_merchant_balance(&merchant), 30_000_000);
+
+    // The schedule keeps its original cadence: the next period is billable at
+    // T0 + 4 * INTERVAL, not one interval after the late charge.
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::Replay))
+    );
+    env.ledger().set_timestamp(T0 + 4 * INTERVAL);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(client.get_merchant_balance(&merchant), 40_000_000);
+}
+
+#[test]
+fn test_collect_all_cap_forgives_older_periods() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    set_catch_up(&client, &merchant, CatchUpMode::CollectAll, 2);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+
+    env.ledger().set_timestamp(T0 + 4 * INTERVAL);
+    let due = client.get_outstanding_periods(&id);
+    assert_eq!(due.periods, 4);
+    assert_eq!(due.collectible, 2);
+
+    client.charge_subscription(&id, &add_operator(&env, &client));
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 30_000_000);
+    assert_eq!(sub.last_payment_timestamp, T0 + 4 * INTERVAL);
+    assert_eq!(client.get_outstanding_periods(&id).periods, 0);
+}
+
+#[test]
+fn test_collect_all_needs_balance_for_all_arrears() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    set_catch_up(&client, &merchant, CatchUpMode::CollectAll, 12);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 25_000_000);
+    let operator = add_operator(&env, &client);
+
+    env.ledger().set_timestamp(T0 + 3 * INTERVAL);
+    client.batch_charge(&operator, &SorobanVec::from_array(&env, [id]));
+    let (name, _, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "insufficient_balance"));
+    let event: crate::InsufficientBalanceEvent = data.into_val(&env);
+    assert_eq!(event.amount_due, 30_000_000);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.status, SubscriptionStatus::InsufficientBalance);
+    assert_eq!(sub.prepaid_balance, 25_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 0);
+}
+
+#[test]
+fn test_skip_forgives_arrears_but_collects_current_period() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    set_catch_up(&client, &merchant, CatchUpMode::Skip, 0);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+    let operator = add_operator(&env, &client);
+
+    env.ledger().set_timestamp(T0 + 3 * INTERVAL + 100);
+    let due = client.get_outstanding_periods(&id);
+    assert_eq!(due.periods, 3);
+    assert_eq!(due.collectible, 1);
+    assert_eq!(due.amount_due, 10_000_000);
+
+    assert!(client.charge_subscription(&id, &operator).success);
+    let mut skipped = None;
+    for (contract, topics, data) in env.events().all().iter() {
+        let name: soroban_sdk::Symbol = topics.get(0).unwrap().into_val(&env);
+        if contract == client.address && name == soroban_sdk::Symbol::new(&env, "periods_skipped") {
+            skipped = Some(data);
+        }
+    }
+    let event: crate::PeriodsSkippedEvent = skipped.expect("no periods_skipped").into_val(&env);
+    assert_eq!(event.periods, 2);
+    assert_eq!(event.next_anchor, T0 + 3 * INTERVAL);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "charged"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::SubscriptionChargedEvent = data.into_val(&env);
+    assert_eq!(event.amount, 10_000_000);
+    assert_eq!(event.periods, 1);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.prepaid_balance, 40_000_000);
+    assert_eq!(sub.last_payment_timestamp, T0 + 3 * INTERVAL);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+
+    // The current period is paid; the next one is billed on schedule.
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::Replay))
+    );
+    env.ledger().set_timestamp(T0 + 4 * INTERVAL);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(client.get_merchant_balance(&merchant), 20_000_000);
+}
+
+// =============================================================================
+// Calendar Interval Tests
+// =============================================================================
+
+/// 2024-01-31 10:00 UTC.
+const JAN_31_2024_10H: u64 = 1_706_695_200;
+const FEB_29_2024: u64 = 1_709_164_800;
+const MAR_01_2024: u64 = 1_709_251_200;
+const MAR_31_2024: u64 = 1_711_843_200;
+const APR_30_2024: u64 = 1_714_435_200;
+const MAY_02_2024: u64 = 1_714_608_000;
+
+fn create_calendar_subscription(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    token: &Address,
+    merchant: &Address,
+    interval_kind: IntervalKind,
+    deposit: i128,
+) -> (u32, Address) {
+    let subscriber = Address::generate(env);
+    mint_tokens(env, token, &subscriber, deposit);
+    let id = client.create_calendar_subscription(
+        &subscriber,
+        merchant,
+        &10_000_000i128,
+        &interval_kind,
+        &false,
+    );
+    client.deposit_funds(&id, &subscriber, &deposit);
+    (id, subscriber)
+}
+
+#[test]
+fn test_calendar_date_conversion() {
+    use crate::calendar::{civil_from_timestamp, days_in_month};
+    assert_eq!(civil_from_timestamp(0), (1970, 1, 1));
+    assert_eq!(civil_from_timestamp(FEB_29_2024), (2024, 2, 29));
+    assert_eq!(civil_from_timestamp(MAR_01_2024 - 1), (2024, 2, 29));
+    assert_eq!(civil_from_timestamp(JAN_31_2024_10H), (2024, 1, 31));
+    assert_eq!(days_in_month(2024, 2), 29);
+    assert_eq!(days_in_month(2025, 2), 28);
+    assert_eq!(days_in_month(1900, 2), 28);
+    assert_eq!(days_in_month(2000, 2), 29);
+    assert_eq!(days_in_month(2025, 4), 30);
+}
+
+#[test]
+fn test_calendar_subscription_rejects_invalid_anchor() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    for kind in [
+        IntervalKind::Seconds,
+        IntervalKind::Months(0),
+        IntervalKind::Months(32),
+        IntervalKind::Years(0, 1),
+        IntervalKind::Years(13, 1),
+        IntervalKind::Years(2, 30),
+        IntervalKind::Years(4, 31),
+    ] {
+        assert_eq!(
+            client.try_create_calendar_subscription(
+                &subscriber,
+                &merchant,
+                &10_000_000i128,
+                &kind,
+                &false
+            ),
+            Err(Ok(Error::InvalidInterval))
+        );
+    }
+}
+
+#[test]
+fn test_monthly_anchor_clamps_short_months() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let operator = add_operator(&env, &client);
+    env.ledger().set_timestamp(JAN_31_2024_10H);
+    let (id, _) = create_calendar_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        IntervalKind::Months(31),
+        50_000_000,
+    );
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.interval_kind, IntervalKind::Months(31));
+    assert_eq!(sub.interval_seconds, 30 * 24 * 60 * 60);
+
+    // The 31st bills on February 29th in a leap year.
+    let info = client.get_next_charge_info(&id);
+    assert_eq!(info.next_charge_timestamp, FEB_29_2024);
+    env.ledger().set_timestamp(FEB_29_2024 - 1);
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::IntervalNotElapsed))
+    );
+
+    env.ledger().set_timestamp(FEB_29_2024);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        MAR_31_2024
+    );
+
+    // One day later is still the same billing month: a replay of the charged period.
+    env.ledger().set_timestamp(MAR_01_2024);
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::Replay))
+    );
+
+    // A late charge does not drift the schedule off the anchor day.
+    env.ledger().set_timestamp(MAR_31_2024 + 3 * 24 * 60 * 60);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        APR_30_2024
+    );
+    assert_eq!(client.get_merchant_balance(&merchant), 20_000_000);
+}
+
+#[test]
+fn test_monthly_anchor_before_creation_day_bills_same_month() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    // 2025-01-20, anchored on the 15th: first due 2025-02-15.
+    env.ledger().set_timestamp(1_737_331_200);
+    let (id, _) = create_calendar_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        IntervalKind::Months(15),
+        50_000_000,
+    );
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        1_739_577_600
+    );
+}
+
+#[test]
+fn test_yearly_leap_day_anchor() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let operator = add_operator(&env, &client);
+    env.ledger().set_timestamp(MAR_01_2024);
+    let (id, _) = create_calendar_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        IntervalKind::Years(2, 29),
+        50_000_000,
+    );
+    // February 29th is clamped to the 28th in common years.
+    let feb_28_2025 = 1_740_700_800;
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        feb_28_2025
+    );
+    env.ledger().set_timestamp(feb_28_2025);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 40_000_000);
+}
+
+#[test]
+fn test_monthly_catch_up_counts_calendar_periods() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    set_catch_up(&client, &merchant, CatchUpMode::CollectAll, 12);
+    env.ledger().set_timestamp(JAN_31_2024_10H);
+    let (id, _) = create_calendar_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        IntervalKind::Months(31),
+        50_000_000,
+    );
+
+    // Feb 29, Mar 31 and Apr 30 have all passed.
+    env.ledger().set_timestamp(MAY_02_2024);
+    let due = client.get_outstanding_periods(&id);
+    assert_eq!(due.periods, 3);
+    assert_eq!(due.amount_due, 30_000_000);
+    assert_eq!(due.next_anchor, APR_30_2024);
+
+    client.charge_subscription(&id, &add_operator(&env, &client));
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 20_000_000);
+    assert_eq!(client.get_outstanding_periods(&id).periods, 0);
+}
+
+// =============================================================================
+// Billing Anchor Tests
+// =============================================================================
+
+#[test]
+fn test_current_period_counts_from_billing_anchor() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let operator = add_operator(&env, &client);
+    // Created 10 seconds before an epoch-aligned interval boundary.
+    let created = 5 * INTERVAL - 10;
+    env.ledger().set_timestamp(created);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+    assert_eq!(client.get_subscription(&id).billing_anchor, created);
+
+    // Crossing the epoch boundary does not start a new period.
+    env.ledger().set_timestamp(5 * INTERVAL);
+    let period = client.get_current_period(&id);
+    assert_eq!(period.number, 0);
+    assert_eq!(period.start, created);
+    assert_eq!(period.end, created + INTERVAL);
+    assert!(!period.charged);
+
+    env.ledger().set_timestamp(created + INTERVAL);
+    client.charge_subscription(&id, &operator);
+    let period = client.get_current_period(&id);
+    assert_eq!(period.number, 1);
+    assert_eq!(period.start, created + INTERVAL);
+    assert!(period.charged);
+
+    // Period 1 cannot be charged again, however close to an epoch boundary.
+    env.ledger().set_timestamp(6 * INTERVAL);
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::Replay))
+    );
+    env.ledger().set_timestamp(created + 2 * INTERVAL);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(client.get_current_period(&id).number, 2);
+}
+
+#[test]
+fn test_upfront_charge_pays_period_zero() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    let created = 3 * INTERVAL - 1;
+    env.ledger().set_timestamp(created);
+    mint_tokens(&env, &token, &subscriber, 30_000_000);
+    let id = client.create_subscription_with_deposit(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &30_000_000i128,
+        &true,
+    );
+
+    env.ledger().set_timestamp(3 * INTERVAL);
+    let period = client.get_current_period(&id);
+    assert_eq!(period.number, 0);
+    assert!(period.charged);
+    assert_eq!(
+        client.try_charge_subscription(&id, &add_operator(&env, &client)),
+        Err(Ok(Error::Replay))
+    );
+}
+
+#[test]
+fn test_late_charge_moves_billing_anchor() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+
+    // Under the default catch-up policy a late charge restarts the schedule.
+    let late = T0 + INTERVAL + 100;
+    env.ledger().set_timestamp(late);
+    client.charge_subscription(&id, &add_operator(&env, &client));
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.billing_anchor, late);
+    assert_eq!(sub.last_payment_timestamp, late);
+
+    let period = client.get_current_period(&id);
+    assert_eq!(period.number, 0);
+    assert!(period.charged);
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        late + INTERVAL
+    );
+}
+
+// =============================================================================
+// Trial Tests
+// =============================================================================
+
+/// Helper: opt `merchant` into trials.
+fn enable_trials(
+    client: &SubscriptionVaultClient,
+    merchant: &Address,
+    max_trials: u32,
+    max_trial_seconds: u64,
+) {
+    client.set_trial_limit(merchant, merchant, &max_trials);
+    client.set_max_trial_length(merchant, merchant, &max_trial_seconds);
+}
+
+fn create_trial_subscription(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    token: &Address,
+    merchant: &Address,
+    subscriber: &Address,
+    trial: TrialTerms,
+) -> u32 {
+    enable_trials(client, merchant, 1, 12 * INTERVAL);
+    mint_tokens(env, token, subscriber, 50_000_000);
+    let id = client.create_subscription_with_trial(
+        subscriber,
+        merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &true,
+        &trial,
+    );
+    client.deposit_funds(&id, subscriber, &50_000_000i128);
+    id
+}
+
+#[test]
+fn test_trial_blocks_charges_until_it_ends() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    let operator = add_operator(&env, &client);
+    let id = create_trial_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        &subscriber,
+        TrialTerms::Periods(2),
+    );
+    let trial_end = T0 + 2 * INTERVAL;
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.trial_ends_at, trial_end);
+    assert_eq!(sub.billing_anchor, trial_end);
+
+    let info = client.get_next_charge_info(&id);
+    assert!(info.in_trial);
+    assert_eq!(info.trial_ends_at, trial_end);
+    assert_eq!(info.next_charge_timestamp, trial_end);
+
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::InTrial))
+    );
+    assert_eq!(
+        client.try_charge_usage(&id, &operator, &1_000_000i128),
+        Err(Ok(Error::InTrial))
+    );
+    assert_eq!(
+        client.try_charge_one_off(&id, &merchant, &1_000_000i128),
+        Err(Ok(Error::InTrial))
+    );
+    let results = client.batch_charge(&operator, &SorobanVec::from_array(&env, [id]));
+    assert_eq!(results.get(0).unwrap().error_code, Error::InTrial.to_code());
+
+    // The first paid period starts at the trial end.
+    env.ledger().set_timestamp(trial_end);
+    client.charge_subscription(&id, &operator);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "trial_converted"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::TrialConvertedEvent = data.into_val(&env);
+    assert_eq!(event.trial_ends_at, trial_end);
+    assert_eq!(event.amount, 10_000_000);
+
+    let info = client.get_next_charge_info(&id);
+    assert!(!info.in_trial);
+    assert_eq!(info.next_charge_timestamp, trial_end + INTERVAL);
+    assert_eq!(client.get_current_period(&id).number, 0);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+}
+
+#[test]
+fn test_next_charge_info_leaves_trial_when_it_ends_unpaid() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    let id = create_trial_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        &subscriber,
+        TrialTerms::Periods(1),
+    );
+    let trial_end = T0 + INTERVAL;
+
+    env.ledger().set_timestamp(trial_end - 1);
+    assert!(client.get_next_charge_info(&id).in_trial);
+
+    // No conversion charge has run, but the trial is over.
+    env.ledger().set_timestamp(trial_end + 3_600);
+    let info = client.get_next_charge_info(&id);
+    assert!(!info.in_trial);
+    assert!(info.is_charge_expected);
+    assert_eq!(info.trial_ends_at, trial_end);
+    assert_eq!(info.next_charge_timestamp, trial_end);
+}
+
+#[test]
+fn test_trial_started_event_and_validation() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    enable_trials(&client, &merchant, 1, INTERVAL);
+    for trial in [
+        TrialTerms::EndsAt(T0),
+        TrialTerms::Periods(0),
+        // Longer than the merchant's maximum trial length.
+        TrialTerms::EndsAt(T0 + INTERVAL + 1),
+        TrialTerms::Periods(2),
+    ] {
+        assert_eq!(
+            client.try_create_subscription_with_trial(
+                &subscriber,
+                &merchant,
+                &10_000_000i128,
+                &INTERVAL,
+                &false,
+                &trial
+            ),
+            Err(Ok(Error::InvalidTrial))
+        );
+    }
+
+    let id = client.create_subscription_with_trial(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &TrialTerms::EndsAt(T0 + 3_600),
+    );
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "trial_started"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: crate::TrialStartedEvent = data.into_val(&env);
+    assert_eq!(event.trial_ends_at, T0 + 3_600);
+}
+
+#[test]
+fn test_trial_limit_per_subscriber() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+
+    // Trials are opt-in.
+    assert_eq!(client.get_trial_limit(&merchant), 0);
+    assert_eq!(client.get_max_trial_length(&merchant), 0);
+    assert_eq!(
+        client.try_create_subscription_with_trial(
+            &subscriber,
+            &merchant,
+            &10_000_000i128,
+            &INTERVAL,
+            &false,
+            &TrialTerms::Periods(1),
+        ),
+        Err(Ok(Error::TrialLimitReached))
+    );
+
+    create_trial_subscription(
+        &env,
+        &client,
+        &token,
+        &merchant,
+        &subscriber,
+        TrialTerms::Periods(1),
+    );
+
+    let second = client.try_create_subscription_with_trial(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &TrialTerms::Periods(1),
+    );
+    assert_eq!(second, Err(Ok(Error::TrialLimitReached)));
+    // Paid subscriptions and other merchants are unaffected.
+    client.create_subscription(&subscriber, &merchant, &10_000_000i128, &INTERVAL, &false);
+    let other = Address::generate(&env);
+    enable_trials(&client, &other, 1, INTERVAL);
+    client.create_subscription_with_trial(
+        &subscriber,
+        &other,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &TrialTerms::Periods(1),
+    );
+
+    client.set_trial_limit(&merchant, &merchant, &2);
+    client.create_subscription_with_trial(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &TrialTerms::Periods(1),
+    );
+
+    assert_eq!(
+        client.try_set_trial_limit(&Address::generate(&env), &merchant, &5),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_max_trial_length(&Address::generate(&env), &merchant, &INTERVAL),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+// =============================================================================
+// Plan Catalog Tests
+// =============================================================================
+
+#[test]
+fn test_plan_lifecycle() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+
+    let plan_id = client.create_plan(&merchant, &10_000_000i128, &INTERVAL, &false, &0);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "plan_created"));
+    let topic_plan: u32 = topics.get(2).unwrap().into_val(&env);
+    assert_eq!(topic_plan, plan_id);
+    let event: Plan = data.into_val(&env);
+    assert_eq!(event, client.get_plan(&plan_id));
+
+    client.update_plan(&merchant, &plan_id, &20_000_000i128, &INTERVAL, &true, &1);
+    let plan = client.get_plan(&plan_id);
+    assert_eq!(plan.amount, 20_000_000);
+    assert!(plan.usage_enabled);
+    assert_eq!(plan.trial_periods, 1);
+    assert_eq!(plan.status, PlanStatus::Active);
+
+    let other = client.create_plan(&merchant, &5_000_000i128, &INTERVAL, &false, &0);
+    client.archive_plan(&merchant, &plan_id);
+    assert_eq!(client.get_plan(&plan_id).status, PlanStatus::Archived);
+    assert_eq!(
+        client.list_plans(&merchant),
+        SorobanVec::from_array(&env, [plan_id, other])
+    );
+    assert_eq!(
+        client.try_update_plan(&merchant, &plan_id, &1i128, &INTERVAL, &false, &0),
+        Err(Ok(Error::PlanArchived))
+    );
+    assert_eq!(
+        client.try_archive_plan(&merchant, &plan_id),
+        Err(Ok(Error::PlanArchived))
+    );
+}
+
+#[test]
+fn test_plan_validation_and_ownership() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    assert_eq!(
+        client.try_create_plan(&merchant, &0i128, &INTERVAL, &false, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        client.try_create_plan(&merchant, &1i128, &0, &false, &0),
+        Err(Ok(Error::InvalidInterval))
+    );
+    assert_eq!(client.try_get_plan(&7), Err(Ok(Error::NotFound)));
+
+    let plan_id = client.create_plan(&merchant, &10_000_000i128, &INTERVAL, &false, &0);
+    let stranger = Address::generate(&env);
+    assert_eq!(
+        client.try_update_plan(&stranger, &plan_id, &1i128, &INTERVAL, &false, &0),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_archive_plan(&stranger, &plan_id),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(client.list_plans(&stranger).len(), 0);
+}
+
+#[test]
+fn test_plan_amount_respects_merchant_minimum() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    client.set_merchant_config(&merchant, &merchant, &5_000_000i128, &0u64);
+
+    assert_eq!(
+        client.try_create_plan(&merchant, &4_999_999i128, &INTERVAL, &false, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+    let plan_id = client.create_plan(&merchant, &5_000_000i128, &INTERVAL, &false, &0);
+    assert_eq!(
+        client.try_update_plan(&merchant, &plan_id, &1i128, &INTERVAL, &false, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+
+    // A minimum raised after the plan was created also applies to new subscribers.
+    client.set_merchant_config(&merchant, &merchant, &6_000_000i128, &0u64);
+    assert_eq!(
+        client.try_subscribe_to_plan(&Address::generate(&env), &plan_id),
+        Err(Ok(Error::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_subscribe_to_plan_copies_terms() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    let plan_id = client.create_plan(&merchant, &10_000_000i128, &INTERVAL, &true, &0);
+
+    let id = client.subscribe_to_plan(&subscriber, &plan_id);
+    let (name, _, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "created"));
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.plan_id, Some(plan_id));
+    assert_eq!(sub.merchant, merchant);
+    assert_eq!(sub.amount, 10_000_000);
+    assert_eq!(sub.interval_seconds, INTERVAL);
+    assert!(sub.usage_enabled);
+    assert_eq!(sub.trial_ends_at, 0);
+
+    // Updates and archiving leave existing subscriptions untouched.
+    client.update_plan(&merchant, &plan_id, &20_000_000i128, &INTERVAL, &false, &0);
+    assert_eq!(client.get_subscription(&id).amount, 10_000_000);
+    let second = client.subscribe_to_plan(&Address::generate(&env), &plan_id);
+    assert_eq!(client.get_subscription(&second).amount, 20_000_000);
+
+    client.archive_plan(&merchant, &plan_id);
+    assert_eq!(
+        client.try_subscribe_to_plan(&Address::generate(&env), &plan_id),
+        Err(Ok(Error::PlanArchived))
+    );
+    assert_eq!(
+        client.get_subscription(&id).status,
+        SubscriptionStatus::Active
+    );
+    assert_eq!(
+        client.try_subscribe_to_plan(&subscriber, &99),
+        Err(Ok(Error::NotFound))
+    );
+}
+
+#[test]
+fn test_subscribe_to_plan_trial_respects_limit() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let subscriber = Address::generate(&env);
+    let plan_id = client.create_plan(&merchant, &10_000_000i128, &INTERVAL, &false, &2);
+
+    // Without opting in, the plan's trial is not granted.
+    let unpaid = client.subscribe_to_plan(&Address::generate(&env), &plan_id);
+    assert_eq!(client.get_subscription(&unpaid).trial_ends_at, 0);
+
+    // Nor when it is longer than the merchant's maximum trial length.
+    enable_trials(&client, &merchant, 1, INTERVAL);
+    let too_long = client.subscribe_to_plan(&Address::generate(&env), &plan_id);
+    assert_eq!(client.get_subscription(&too_long).trial_ends_at, 0);
+
+    client.set_max_trial_length(&merchant, &merchant, &(2 * INTERVAL));
+    let first = client.subscribe_to_plan(&subscriber, &plan_id);
+    let (name, _, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "trial_started"));
+    assert_eq!(
+        client.get_subscription(&first).trial_ends_at,
+        T0 + 2 * INTERVAL
+    );
+
+    // Trial allowance used up: the second subscription is billed right away.
+    let second = client.subscribe_to_plan(&subscriber, &plan_id);
+    let (name, _, _) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "created"));
+    assert_eq!(client.get_subscription(&second).trial_ends_at, 0);
+}
+
+#[test]
+fn test_subscriptions_by_plan() {
+    let (env, client, _, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let basic = client.create_plan(&merchant, &5_000_000i128, &INTERVAL, &false, &0);
+    let pro = client.create_plan(&merchant, &10_000_000i128, &INTERVAL, &false, &0);
+
+    let mut pro_ids = SorobanVec::new(&env);
+    for i in 0..(MERCHANT_SUBS_PAGE_SIZE + 3) {
+        let subscriber = Address::generate(&env);
+        if i % 2 == 0 {
+            client.subscribe_to_plan(&subscriber, &basic);
+        }
+        pro_ids.push_back(client.subscribe_to_plan(&subscriber, &pro));
+    }
+    // Custom-term subscriptions are not indexed under any plan.
+    client.create_subscription(
+        &Address::generate(&env),
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+    );
+
+    assert_eq!(client.get_plan_subscription_count(&pro), pro_ids.len());
+    assert_eq!(
+        client.get_plan_subscription_count(&basic),
+        MERCHANT_SUBS_PAGE_SIZE / 2 + 2
+    );
+    let page = client.get_subscriptions_by_plan(&pro, &(MERCHANT_SUBS_PAGE_SIZE - 1), &3);
+    assert_eq!(page.len(), 3);
+    for sub in page.iter() {
+        assert_eq!(sub.plan_id, Some(pro));
+    }
+    assert_eq!(client.get_subscriptions_by_plan(&pro, &1_000, &10).len(), 0);
+    assert_eq!(client.get_plan_subscription_count(&99), 0);
+
+    // extend_ttl also keeps the plan and its index page alive.
+    let last = pro_ids.get(pro_ids.len() - 1).unwrap();
+    client.extend_ttl(&SorobanVec::from_array(&env, [last]));
+}
+
+// =============================================================================
+// Plan Change Tests
+// =============================================================================
+
+/// Funded 10M/interval subscription whose period 1 was charged at `T0 + INTERVAL`,
+/// with the ledger moved to the middle of that period.
+fn setup_mid_period_subscription(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    token: &Address,
+    merchant: &Address,
+    deposit: i128,
+) -> (u32, Address) {
+    let operator = add_operator(env, client);
+    let (id, subscriber) =
+        create_funded_subscription(env, client, token, merchant, 10_000_000, deposit);
+    env.ledger().set_timestamp(T0 + INTERVAL);
+    client.charge_subscription(&id, &operator);
+    env.ledger().set_timestamp(T0 + INTERVAL + INTERVAL / 2);
+    (id, subscriber)
+}
+
+#[test]
+fn test_immediate_upgrade_is_prorated() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        setup_mid_period_subscription(&env, &client, &token, &merchant, 50_000_000);
+    let target = PlanChangeTarget::Terms(20_000_000, 0);
+
+    let quote = client.quote_plan_change(&id, &target, &PlanChangeTiming::Immediate);
+    assert_eq!(quote.credit, 5_000_000);
+    assert_eq!(quote.charge, 10_000_000);
+    assert_eq!(quote.net, 5_000_000);
+    assert_eq!(quote.effective_at, T0 + INTERVAL + INTERVAL / 2);
+
+    let applied =
+        client.change_subscription_plan(&id, &subscriber, &target, &PlanChangeTiming::Immediate);
+    assert_eq!(applied, quote);
+    let (name, topics, data) = last_vault_event(&env, &client);
+    assert_eq!(name, soroban_sdk::Symbol::new(&env, "plan_changed"));
+    assert_subscription_topics(&env, &topics, id, &merchant, &subscriber);
+    let event: PlanChangedEvent = data.into_val(&env);
+    assert_eq!(event.amount, 20_000_000);
+    assert_eq!((event.credit, event.charge), (5_000_000, 10_000_000));
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.amount, 20_000_000);
+    assert_eq!(sub.prepaid_balance, 35_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 15_000_000);
+    // The current period keeps its end; the new price is charged from there.
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        T0 + 2 * INTERVAL
+    );
+    let operator = add_operator(&env, &client);
+    assert_eq!(
+        client.try_charge_subscription(&id, &operator),
+        Err(Ok(Error::IntervalNotElapsed))
+    );
+    env.ledger().set_timestamp(T0 + 2 * INTERVAL);
+    client.charge_subscription(&id, &operator);
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 15_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 35_000_000);
+}
+
+#[test]
+fn test_immediate_interval_change_prorates_by_new_length() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        setup_mid_period_subscription(&env, &client, &token, &merchant, 50_000_000);
+    let short = INTERVAL / 3;
+
+    let quote = client.change_subscription_plan(
+        &id,
+        &subscriber,
+        &PlanChangeTarget::Terms(10_000_000, short),
+        &PlanChangeTiming::Immediate,
+    );
+    // Half a 30-day period is credited; the same 15 days cost 1.5 ten-day periods.
+    assert_eq!(quote.credit, 5_000_000);
+    assert_eq!(quote.charge, 15_000_000);
+
+    let operator = add_operator(&env, &client);
+    env.ledger().set_timestamp(T0 + 2 * INTERVAL);
+    client.charge_subscription(&id, &operator);
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.interval_seconds, short);
+    assert_eq!(sub.billing_anchor, T0 + 2 * INTERVAL);
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        T0 + 2 * INTERVAL + short
+    );
+}
+
+#[test]
+fn test_immediate_downgrade_refunds_from_merchant_earnings() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        setup_mid_period_subscription(&env, &client, &token, &merchant, 50_000_000);
+    let target = PlanChangeTarget::Terms(5_000_000, 0);
+
+    client.withdraw_merchant_funds(&merchant, &10_000_000);
+    assert_eq!(
+        client.try_change_subscription_plan(
+            &id,
+            &subscriber,
+            &target,
+            &PlanChangeTiming::Immediate
+        ),
+        Err(Ok(Error::InsufficientBalance))
+    );
+
+    // A second subscriber's payment gives the merchant earnings to refund from.
+    let (other, _) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+    client.charge_one_off(&other, &merchant, &10_000_000);
+    let quote =
+        client.change_subscription_plan(&id, &subscriber, &target, &PlanChangeTiming::Immediate);
+    assert_eq!(quote.net, -2_500_000);
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 42_500_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 7_500_000);
+}
+
+#[test]
+fn test_downgrade_then_upgrade_in_same_period_is_prorated() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        setup_mid_period_subscription(&env, &client, &token, &merchant, 50_000_000);
+
+    let down = client.change_subscription_plan(
+        &id,
+        &subscriber,
+        &PlanChangeTarget::Terms(1, 0),
+        &PlanChangeTiming::Immediate,
+    );
+    assert_eq!(down.credit, 5_000_000);
+    assert_eq!(down.net, -5_000_000);
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 45_000_000);
+
+    // The re-anchored period is still paid (at the downgraded price), so going
+    // back up charges the rest of it again.
+    let up_target = PlanChangeTarget::Terms(10_000_000, 0);
+    let quote = client.quote_plan_change(&id, &up_target, &PlanChangeTiming::Immediate);
+    let up =
+        client.change_subscription_plan(&id, &subscriber, &up_target, &PlanChangeTiming::Immediate);
+    assert_eq!(up, quote);
+    assert_eq!(up.credit, 0);
+    assert_eq!(up.charge, 5_000_000);
+    assert_eq!(up.net, 5_000_000);
+
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.amount, 10_000_000);
+    assert_eq!(sub.prepaid_balance, 40_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        T0 + 2 * INTERVAL
+    );
+}
+
+/// Mock only `subscriber`'s auth for a `change_subscription_plan` call.
+fn mock_subscriber_plan_change_auth(
+    env: &Env,
+    client: &SubscriptionVaultClient,
+    id: u32,
+    subscriber: &Address,
+    target: &PlanChangeTarget,
+) {
+    env.mock_auths(&[soroban_sdk::testutils::MockAuth {
+        address: subscriber,
+        invoke: &soroban_sdk::testutils::MockAuthInvoke {
+            contract: &client.address,
+            fn_name: "change_subscription_plan",
+            args: (
+                id,
+                subscriber.clone(),
+                target.clone(),
+                PlanChangeTiming::Immediate,
+            )
+                .into_val(env),
+            sub_invokes: &[],
+        },
+    }]);
+}
+
+#[test]
+fn test_custom_terms_need_merchant_auth() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        setup_mid_period_subscription(&env, &client, &token, &merchant, 50_000_000);
+    let plan_id = client.create_plan(&merchant, &5_000_000i128, &INTERVAL, &false, &0);
+
+    // The subscriber alone cannot pick a price and get refunded from the merchant.
+    let terms = PlanChangeTarget::Terms(1, 0);
+    mock_subscriber_plan_change_auth(&env, &client, id, &subscriber, &terms);
+    assert!(client
+        .try_change_subscription_plan(&id, &subscriber, &terms, &PlanChangeTiming::Immediate)
+        .is_err());
+    env.mock_all_auths();
+    assert_eq!(client.get_subscription(&id).amount, 10_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 10_000_000);
+
+    // One of the merchant's own plans needs no merchant signature.
+    let plan = PlanChangeTarget::Plan(plan_id);
+    mock_subscriber_plan_change_auth(&env, &client, id, &subscriber, &plan);
+    let quote =
+        client.change_subscription_plan(&id, &subscriber, &plan, &PlanChangeTiming::Immediate);
+    assert_eq!(quote.net, -2_500_000);
+}
+
+#[test]
+fn test_change_at_next_period_applies_at_boundary() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        setup_mid_period_subscription(&env, &client, &token, &merchant, 50_000_000);
+
+    let quote = client.change_subscription_plan(
+        &id,
+        &subscriber,
+        &PlanChangeTarget::Terms(20_000_000, 0),
+        &PlanChangeTiming::NextPeriod,
+    );
+    assert_eq!(quote.net, 0);
+    assert_eq!(quote.effective_at, T0 + 2 * INTERVAL);
+    let (name, _, _) = last_vault_event(&env, &client);
+    assert_eq!(
+        name,
+        soroban_sdk::Symbol::new(&env, "plan_change_scheduled")
+    );
+    let pending = client.get_pending_plan_change(&id).unwrap();
+    assert_eq!(pending.amount, 20_000_000);
+    assert_eq!(pending.effective_at, T0 + 2 * INTERVAL);
+    assert_eq!(client.get_subscription(&id).amount, 10_000_000);
+
+    let operator = add_operator(&env, &client);
+    env.ledger().set_timestamp(T0 + 2 * INTERVAL);
+    client.charge_subscription(&id, &operator);
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.amount, 20_000_000);
+    assert_eq!(sub.prepaid_balance, 20_000_000);
+    assert_eq!(client.get_pending_plan_change(&id), None);
+    assert_eq!(
+        client.get_next_charge_info(&id).next_charge_timestamp,
+        T0 + 3 * INTERVAL
+    );
+}
+
+#[test]
+fn test_plan_index_follows_subscription_across_plan_changes() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+    let plan_a = client.create_plan(&merchant, &20_000_000i128, &INTERVAL, &false, &0);
+    let plan_b = client.create_plan(&merchant, &30_000_000i128, &INTERVAL, &false, &0);
+
+    for plan_id in [plan_a, plan_b, plan_a] {
+        client.change_subscription_plan(
+            &id,
+            &subscriber,
+            &PlanChangeTarget::Plan(plan_id),
+            &PlanChangeTiming::Immediate,
+        );
+    }
+
+    // Returning to a plan does not index the subscription twice.
+    assert_eq!(client.get_plan_subscription_count(&plan_a), 1);
+    let on_a = client.get_subscriptions_by_plan(&plan_a, &0, &10);
+    assert_eq!(on_a.len(), 1);
+    assert_eq!(on_a.get(0).unwrap().plan_id, Some(plan_a));
+
+    // The subscription left plan B: its index entry stays but is not listed.
+    assert_eq!(client.get_plan_subscription_count(&plan_b), 1);
+    assert_eq!(client.get_subscriptions_by_plan(&plan_b, &0, &10).len(), 0);
+
+    advance_ledgers(&env, 10 * DAY_IN_LEDGERS);
+    client.extend_ttl(&SorobanVec::from_array(&env, [id]));
+    for key in [
+        DataKey::PlanSubsPage(plan_a, 0),
+        DataKey::PlanSubEntry(plan_a, id),
+    ] {
+        assert_eq!(persistent_ttl(&env, &client, &key), PERSISTENT_BUMP_AMOUNT);
+    }
+}
+
+#[test]
+fn test_plan_change_prorates_upfront_charged_period() {
+    let (env, client, token, _) = setup_earnings_env();
+    let subscriber = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    mint_tokens(&env, &token, &subscriber, 50_000_000);
+    let id = client.create_subscription_with_deposit(
+        &subscriber,
+        &merchant,
+        &10_000_000i128,
+        &INTERVAL,
+        &false,
+        &30_000_000i128,
+        &true,
+    );
+    let plan_id = client.create_plan(&merchant, &20_000_000i128, &INTERVAL, &false, &0);
+
+    env.ledger().set_timestamp(T0 + INTERVAL / 2);
+    let quote = client.change_subscription_plan(
+        &id,
+        &subscriber,
+        &PlanChangeTarget::Plan(plan_id),
+        &PlanChangeTiming::Immediate,
+    );
+    assert_eq!(quote.credit, 5_000_000);
+    assert_eq!(quote.charge, 10_000_000);
+    assert_eq!(quote.net, 5_000_000);
+    assert_eq!(client.get_subscription(&id).prepaid_balance, 15_000_000);
+    assert_eq!(client.get_merchant_balance(&merchant), 15_000_000);
+}
+
+#[test]
+fn test_change_to_plan_and_unpaid_period_is_not_prorated() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        create_funded_subscription(&env, &client, &token, &merchant, 10_000_000, 50_000_000);
+    let plan_id = client.create_plan(&merchant, &20_000_000i128, &INTERVAL, &false, &0);
+    let foreign = client.create_plan(
+        &Address::generate(&env),
+        &20_000_000i128,
+        &INTERVAL,
+        &false,
+        &0,
+    );
+    assert_eq!(
+        client.try_change_subscription_plan(
+            &id,
+            &subscriber,
+            &PlanChangeTarget::Plan(foreign),
+            &PlanChangeTiming::Immediate
+        ),
+        Err(Ok(Error::Unauthorized))
+    );
+
+    // Nothing was charged for the first period yet, so nothing is prorated.
+    let quote = client.change_subscription_plan(
+        &id,
+        &subscriber,
+        &PlanChangeTarget::Plan(plan_id),
+        &PlanChangeTiming::Immediate,
+    );
+    assert_eq!((quote.credit, quote.charge, quote.net), (0, 0, 0));
+    let sub = client.get_subscription(&id);
+    assert_eq!(sub.plan_id, Some(plan_id));
+    assert_eq!(sub.amount, 20_000_000);
+    assert!(!sub.usage_enabled);
+    assert_eq!(sub.prepaid_balance, 50_000_000);
+    assert_eq!(client.get_plan_subscription_count(&plan_id), 1);
+
+    client.archive_plan(&merchant, &plan_id);
+    assert_eq!(
+        client.try_quote_plan_change(
+            &id,
+            &PlanChangeTarget::Plan(plan_id),
+            &PlanChangeTiming::NextPeriod
+        ),
+        Err(Ok(Error::PlanArchived))
+    );
+}
+
+#[test]
+fn test_change_subscription_plan_rejections() {
+    let (env, client, token, _) = setup_earnings_env();
+    let merchant = Address::generate(&env);
+    let (id, subscriber) =
+        setup_mid_period_subscription(&env, &client, &token, &merchant, 10_000_000);
+    let upgrade = PlanChangeTarget::Terms(20_000_000, 0);
+
+    assert_eq!(
+        client.try_change_subscription_plan(
+            &id,
+            &Address::generate(&env),
+            &upgrade,
+            &PlanChangeTiming::Immediate
+        ),
+        Err(Ok(Error::Unauthorized))
+    );
+    // The prepaid balance was spent on the current period.
+    assert_eq!(
+        client.try_change_subscription_plan(
+            &id,
+            &subscriber,
+            &upgrade,
+            &PlanChangeTiming::Immediate
+        ),
+        Err(Ok(Error::InsufficientBalance))
+    );
+    assert_eq!(
+        client.try_change_subscription_plan(
+            &id,
+            &subscriber,
+            &PlanChangeTarget::Terms(0, 0),
+            &PlanChangeTiming::NextPeriod
+        ),
+        Err(Ok(Error::InvalidAmount))
+    );
+
+    client.pause_subscription(&id, &subscriber);
+    assert_eq!(
+        client.try_change_subscription_plan(
+            &id,
+            &subscriber,
+            &upgrade,
+            &PlanChangeTiming::NextPeriod
+        ),
+        Err(Ok(Error::NotActive))
+    );
 }
@@ -0,0 +1,117 @@
+//! Free trials: per-merchant trial allowance and trial bookkeeping at creation.
+//!
+//! **PRs that only change trial rules should edit this file only.**
+//!
+//! A trial subscription is anchored at `trial_ends_at`: its first paid period
+//! starts there, and interval, usage and one-off charges fail with `InTrial`
+//! until then. Trials are opt-in: a merchant grants a subscriber at most
+//! `trial_limit` trials (0 by default) across all of its subscriptions, each
+//! no longer than its `max_trial_length` (0 by default).
+
+use crate::merchant::require_config_actor;
+use crate::storage::{get_trials_used, load_entry, save_entry, set_trials_used};
+use crate::types::{DataKey, Error, Subscription, TrialTerms};
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Trials granted per subscriber when the merchant has not set a limit.
+pub const DEFAULT_TRIAL_LIMIT: u32 = 0;
+
+pub fn get_trial_limit(env: &Env, merchant: &Address) -> u32 {
+    load_entry(env, &DataKey::TrialLimit(merchant.clone())).unwrap_or(DEFAULT_TRIAL_LIMIT)
+}
+
+/// Set how many trials the merchant grants each subscriber (`0` disables trials).
+/// Callable by the merchant or a config manager.
+pub fn do_set_trial_limit(
+    env: &Env,
+    actor: Address,
+    merchant: Address,
+    max_trials_per_subscriber: u32,
+) -> Result<(), Error> {
+    require_config_actor(env, &actor, &merchant)?;
+    save_entry(
+        env,
+        &DataKey::TrialLimit(merchant.clone()),
+        &max_trials_per_subscriber,
+    );
+    env.events().publish(
+        (Symbol::new(env, "trial_limit"), merchant),
+        max_trials_per_subscriber,
+    );
+    Ok(())
+}
+
+/// Longest trial, in seconds, the merchant grants (`0` when none is set).
+pub fn get_max_trial_length(env: &Env, merchant: &Address) -> u64 {
+    load_entry(env, &DataKey::MaxTrialLength(merchant.clone())).unwrap_or(0)
+}
+
+/// Set the longest trial the merchant grants, in seconds. Callable by the
+/// merchant or a config manager.
+pub fn do_set_max_trial_length(
+    env: &Env,
+    actor: Address,
+    merchant: Address,
+    max_trial_seconds: u64,
+) -> Result<(), Error> {
+    require_config_actor(env, &actor, &merchant)?;
+    save_entry(
+        env,
+        &DataKey::MaxTrialLength(merchant.clone()),
+        &max_trial_seconds,
+    );
+    env.events().publish(
+        (Symbol::new(env, "max_trial_length"), merchant),
+        max_trial_seconds,
+    );
+    Ok(())
+}
+
+/// Resolve `terms` to a trial end timestamp for a subscription with `merchant`
+/// created now. Fails with `InvalidTrial` unless the trial ends in the future
+/// and within the merchant's maximum trial length.
+pub fn trial_end(
+    env: &Env,
+    merchant: &Address,
+    terms: &TrialTerms,
+    interval_seconds: u64,
+) -> Result<u64, Error> {
+    let now = env.ledger().timestamp();
+    let ends_at = match *terms {
+        TrialTerms::EndsAt(ends_at) => ends_at,
+        TrialTerms::Periods(0) => return Err(Error::InvalidTrial),
+        TrialTerms::Periods(periods) => u64::from(periods)
+            .checked_mul(interval_seconds)
+            .and_then(|length| now.checked_add(length))
+            .ok_or(Error::Overflow)?,
+    };
+    if ends_at <= now || ends_at - now > get_max_trial_length(env, merchant) {
+        return Err(Error::InvalidTrial);
+    }
+    Ok(ends_at)
+}
+
+/// Whether `subscriber` may still start a trial with `merchant`.
+pub fn has_trial_left(env: &Env, merchant: &Address, subscriber: &Address) -> bool {
+    get_trials_used(env, merchant, subscriber) < get_trial_limit(env, merchant)
+}
+
+/// Count a new trial against the merchant's per-subscriber allowance.
+pub fn consume_trial(env: &Env, merchant: &Address, subscriber: &Address) -> Result<(), Error> {
+    if !has_trial_left(env, merchant, subscriber) {
+        return Err(Error::TrialLimitReached);
+    }
+    let used = get_trials_used(env, merchant, subscriber);
+    set_trials_used(env, merchant, subscriber, used.saturating_add(1));
+    Ok(())
+}
+
+/// Whether `sub` is still in its free trial at the current ledger time.
+pub fn is_in_trial(env: &Env, sub: &Subscription) -> bool {
+    env.ledger().timestamp() < sub.trial_ends_at
+}
+
+/// Whether the next interval charge is the first paid one after a trial.
+pub fn is_converting(sub: &Subscription) -> bool {
+    sub.trial_ends_at > 0 && sub.last_payment_timestamp < sub.trial_ends_at
+}
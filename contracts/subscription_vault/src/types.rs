@@ -3,14 +3,102 @@
 //! Kept in a separate module to reduce merge conflicts when editing state machine
 //! or contract entrypoints.
 
-use soroban_sdk::{contracterror, contracttype, Address};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, Vec};
 
 /// Storage keys for secondary indices.
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
-    /// Maps a merchant address to its list of subscription IDs.
-    MerchantSubs(Address),
+    /// Number of subscriptions created for a merchant (persistent).
+    MerchantSubCount(Address),
+    /// One fixed-size page of a merchant's subscription IDs, keyed by
+    /// `(merchant, page_index)` (persistent). See `storage::MERCHANT_SUBS_PAGE_SIZE`.
+    MerchantSubsPage(Address, u32),
+    /// Number of subscriptions created by a subscriber (persistent).
+    SubscriberSubCount(Address),
+    /// One fixed-size page of a subscriber's subscription IDs, keyed by
+    /// `(subscriber, page_index)` (persistent). Paged like `MerchantSubsPage`.
+    SubscriberSubsPage(Address, u32),
+    /// Maps a merchant address to its accrued, not yet withdrawn earnings (persistent).
+    MerchantBalance(Address),
+    /// Maps a merchant address to its [`MerchantConfig`] (persistent).
+    MerchantConfig(Address),
+    /// Running total of all subscriber `prepaid_balance`s.
+    TotalPrepaid,
+    /// Running total of all unpaid merchant earnings.
+    TotalMerchantBalance,
+    /// Set when `reconcile` finds the vault under-collateralized.
+    ProtectiveMode,
+    /// Maps a [`Role`] to the addresses explicitly granted it (persistent).
+    RoleMembers(Role),
+    /// The admin address, which holds every [`Role`]; its presence marks the
+    /// contract as initialized.
+    Admin,
+    /// The outstanding [`PendingAdmin`] proposal, if any.
+    PendingAdmin,
+    /// The governance [`Council`], once configured.
+    Council,
+    /// Maps a subscription ID to its [`Subscription`] (persistent).
+    Subscription(u32),
+    /// Last charged billing period index of a subscription (persistent).
+    ChargedPeriod(u32),
+    /// Idempotency key of a subscription's last charge (persistent).
+    IdempotencyKey(u32),
+    /// Global [`SubscriptionLimits`] applied on subscription creation.
+    SubscriptionLimits,
+    /// Next governance proposal ID.
+    NextGovProposalId,
+    /// Maps a governance proposal ID to its [`GovProposal`] (persistent).
+    GovProposal(u32),
+    /// Maps a merchant address to its [`DunningPolicy`] (persistent).
+    DunningPolicy(Address),
+    /// Dunning progress of a past-due subscription (persistent).
+    Dunning(u32),
+    /// Maps a merchant address to its [`CatchUpPolicy`] (persistent).
+    CatchUpPolicy(Address),
+    /// Most trials a merchant grants one subscriber (`u32`, default 0, persistent).
+    TrialLimit(Address),
+    /// Longest trial a merchant grants, in seconds (`u64`, default 0, persistent).
+    MaxTrialLength(Address),
+    /// Trials a subscriber has started with a merchant (persistent).
+    TrialsUsed(Address, Address),
+    /// Maps a plan ID to its [`Plan`] (persistent).
+    Plan(u32),
+    /// Plan IDs created by a merchant, in creation order (persistent).
+    MerchantPlans(Address),
+    /// Number of subscriptions created on a plan (persistent).
+    PlanSubCount(u32),
+    /// One fixed-size page of a plan's subscription IDs, keyed by
+    /// `(plan_id, page_index)` (persistent). Paged like `MerchantSubsPage`.
+    PlanSubsPage(u32, u32),
+    /// Page of `plan_id`'s index holding a subscription, keyed by
+    /// `(plan_id, subscription_id)` (persistent). Also marks the subscription
+    /// as indexed, so it is added to a plan at most once.
+    PlanSubEntry(u32, u32),
+    /// [`PendingPlanChange`] applied at a subscription's next period boundary (persistent).
+    PendingPlanChange(u32),
+    /// [`PaidThrough`]: how far a subscription's charges have paid (persistent).
+    PaidThrough(u32),
+}
+
+/// Access-control roles. See `docs/roles.md`.
+///
+/// `Admin` is always the single stored admin (changed via `rotate_admin`) and
+/// implicitly holds every other role. The remaining roles are granted to
+/// separate keys so that, e.g., a hot billing key cannot move funds.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// Contract admin: role management and admin rotation.
+    Admin = 0,
+    /// May trigger interval, usage and batch charges.
+    BillingOperator = 1,
+    /// May lift protective mode.
+    Pauser = 2,
+    /// May recover stranded funds from the vault surplus.
+    Recoverer = 3,
+    /// May change `min_topup` and override merchant configs.
+    ConfigManager = 4,
 }
 
 #[contracterror]
@@ -39,6 +127,50 @@ pub enum Error {
     Replay = 1007,
     /// Recovery amount is zero or negative.
     InvalidRecoveryAmount = 1008,
+    /// Recovery amount exceeds the vault's surplus over its liabilities.
+    RecoveryExceedsSurplus = 1009,
+    /// Operation blocked because the vault is in protective mode (reserves below liabilities).
+    ProtectiveMode = 1010,
+    /// Protective mode cannot be lifted while the vault is still under-collateralized.
+    VaultInsolvent = 1011,
+    /// Role cannot be granted or revoked (the `Admin` role changes only via `rotate_admin`).
+    InvalidRole = 1012,
+    /// `accept_admin` / `cancel_admin_proposal` called with no pending proposal.
+    NoPendingAdmin = 1013,
+    /// The pending admin proposal expired before it was accepted.
+    AdminProposalExpired = 1014,
+    /// A governance council is configured; this action must go through a council proposal.
+    GovernanceRequired = 1015,
+    /// Council signer list is empty, contains duplicates, or the threshold is out of range.
+    InvalidCouncil = 1016,
+    /// The signer already approved this governance proposal.
+    AlreadyApproved = 1017,
+    /// Governance proposal has fewer approvals than the council threshold.
+    ThresholdNotMet = 1018,
+    /// Governance proposal was already executed.
+    ProposalAlreadyExecuted = 1019,
+    /// `init` was called on a contract that is already initialized.
+    AlreadyInitialized = 1020,
+    /// Entrypoint called before `init`.
+    NotInitialized = 1021,
+    /// Interval is zero or outside the global interval bounds.
+    InvalidInterval = 1022,
+    /// Subscriber and merchant are the same address.
+    SelfSubscription = 1023,
+    /// `CatchUpMode::CollectAll` with a `max_periods` of zero.
+    InvalidCatchUpPolicy = 1024,
+    /// Charge attempted before the subscription's trial ends.
+    InTrial = 1025,
+    /// The subscriber has used up the merchant's trial allowance.
+    TrialLimitReached = 1026,
+    /// Trial end is not in the future, or zero trial periods.
+    InvalidTrial = 1027,
+    /// The plan is archived and accepts no new subscribers or updates.
+    PlanArchived = 1028,
+    /// Governance proposal passed its `expires_at` before being executed.
+    ProposalExpired = 1029,
+    /// Dunning retry attempted before the policy's `retry_interval_seconds` elapsed.
+    RetryTooSoon = 1030,
 }
 
 impl Error {
@@ -58,13 +190,36 @@ impl Error {
             Error::InvalidAmount => 1006,
             Error::Replay => 1007,
             Error::InvalidRecoveryAmount => 1008,
+            Error::RecoveryExceedsSurplus => 1009,
+            Error::ProtectiveMode => 1010,
+            Error::VaultInsolvent => 1011,
+            Error::InvalidRole => 1012,
+            Error::NoPendingAdmin => 1013,
+            Error::AdminProposalExpired => 1014,
+            Error::GovernanceRequired => 1015,
+            Error::InvalidCouncil => 1016,
+            Error::AlreadyApproved => 1017,
+            Error::ThresholdNotMet => 1018,
+            Error::ProposalAlreadyExecuted => 1019,
+            Error::AlreadyInitialized => 1020,
+            Error::NotInitialized => 1021,
+            Error::InvalidInterval => 1022,
+            Error::SelfSubscription => 1023,
+            Error::InvalidCatchUpPolicy => 1024,
+            Error::InTrial => 1025,
+            Error::TrialLimitReached => 1026,
+            Error::InvalidTrial => 1027,
+            Error::PlanArchived => 1028,
+            Error::ProposalExpired => 1029,
+            Error::RetryTooSoon => 1030,
         }
     }
 }
 
-/// Result of charging one subscription in a batch. Used by [`crate::SubscriptionVault::batch_charge`].
+/// Result of charging one subscription, returned by [`crate::SubscriptionVault::charge_subscription`]
+/// and per item by [`crate::SubscriptionVault::batch_charge`].
 #[contracttype]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BatchChargeResult {
     /// True if the charge succeeded.
     pub success: bool,
@@ -79,7 +234,7 @@ pub struct BatchChargeResult {
 /// The subscription status follows a defined state machine with specific allowed transitions:
 ///
 /// - **Active**: Subscription is active and charges can be processed.
-///   - Can transition to: `Paused`, `Cancelled`, `InsufficientBalance`
+///   - Can transition to: `Paused`, `Cancelled`, `InsufficientBalance`, `PastDue`
 ///
 /// - **Paused**: Subscription is temporarily suspended, no charges are processed.
 ///   - Can transition to: `Active`, `Cancelled`
@@ -90,6 +245,13 @@ pub struct BatchChargeResult {
 /// - **InsufficientBalance**: Subscription failed due to insufficient funds.
 ///   - Can transition to: `Active` (after deposit), `Cancelled`
 ///
+/// - **PastDue**: A charge failed under the merchant's [`DunningPolicy`]; the
+///   subscription stays entitled during the grace window while charges are retried.
+///   - Can transition to: `Active` (retry succeeds or deposit), `Suspended`, `Cancelled`
+///
+/// - **Suspended**: Dunning ran out (grace elapsed or retries exhausted).
+///   - Can transition to: `Active` (after deposit), `Cancelled`
+///
 /// Invalid transitions (e.g., `Cancelled` -> `Active`) are rejected with
 /// [`Error::InvalidStatusTransition`].
 #[contracttype]
@@ -103,6 +265,10 @@ pub enum SubscriptionStatus {
     Cancelled = 2,
     /// Subscription failed due to insufficient balance for charging.
     InsufficientBalance = 3,
+    /// A charge failed and is being retried within the merchant's grace window.
+    PastDue = 4,
+    /// Dunning exhausted without payment; no charges until the subscriber tops up.
+    Suspended = 5,
 }
 
 /// Stores subscription details and current state.
@@ -121,68 +287,572 @@ pub struct Subscription {
     pub status: SubscriptionStatus,
     pub prepaid_balance: i128,
     pub usage_enabled: bool,
+    /// Fixed-length or calendar schedule. For calendar kinds `interval_seconds`
+    /// holds a nominal length used only for estimates.
+    pub interval_kind: IntervalKind,
+    /// Start of billing period 0; period numbers are counted from here.
+    pub billing_anchor: u64,
+    /// End of the free trial (`0` if none). The first paid period starts here.
+    pub trial_ends_at: u64,
+    /// Plan the subscription was created from (`None` for custom terms).
+    pub plan_id: Option<u32>,
+}
+
+/// Trial requested by `create_subscription_with_trial`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TrialTerms {
+    /// Trial ends at this ledger timestamp.
+    EndsAt(u64),
+    /// Trial lasts this many billing intervals from creation.
+    Periods(u32),
+}
+
+/// Lifecycle of a [`Plan`].
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PlanStatus {
+    /// Open to new subscribers.
+    Active = 0,
+    /// Closed to new subscribers; existing subscriptions are unaffected.
+    Archived = 1,
+}
+
+/// Merchant-defined subscription terms that subscribers join with
+/// `subscribe_to_plan`. See `docs/plans.md`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Plan {
+    pub merchant: Address,
+    /// Price per interval.
+    pub amount: i128,
+    pub interval_seconds: u64,
+    pub usage_enabled: bool,
+    /// Free trial length in billing intervals (`0` for none).
+    pub trial_periods: u32,
+    pub status: PlanStatus,
+}
+
+/// New terms requested by `change_subscription_plan`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PlanChangeTarget {
+    /// Custom `(amount, interval_seconds)`. An interval of `0` keeps the current
+    /// schedule, including calendar intervals.
+    Terms(i128, u64),
+    /// The current terms of one of the merchant's active plans.
+    Plan(u32),
+}
+
+/// When a plan change takes effect.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PlanChangeTiming {
+    /// Now, settling the prorated difference for the rest of the current period.
+    Immediate = 0,
+    /// At the start of the next unpaid period, without proration.
+    NextPeriod = 1,
+}
+
+/// Resolved terms of a plan change, stored until `effective_at` for
+/// `PlanChangeTiming::NextPeriod`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingPlanChange {
+    pub amount: i128,
+    pub interval_seconds: u64,
+    pub interval_kind: IntervalKind,
+    pub usage_enabled: bool,
+    pub plan_id: Option<u32>,
+    /// Ledger timestamp the new terms apply from.
+    pub effective_at: u64,
+}
+
+/// Time a subscription has paid for, and at what rate. Written by every interval
+/// charge and kept across plan changes, which re-anchor the schedule.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaidThrough {
+    /// End of the paid time (exclusive).
+    pub until: u64,
+    /// Price paid per `length` seconds of it.
+    pub amount: i128,
+    pub length: u64,
+}
+
+/// Preview of a plan change, returned by `quote_plan_change`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlanChangeQuote {
+    /// Unused part of the current, already charged period at the old price.
+    pub credit: i128,
+    /// Rest of the current period at the new price.
+    pub charge: i128,
+    /// `charge - credit`: debited from the prepaid balance when positive,
+    /// refunded to it from the merchant's earnings when negative.
+    pub net: i128,
+    /// When the new terms apply.
+    pub effective_at: u64,
+}
+
+/// How billing periods are measured. See `docs/billing_intervals.md`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IntervalKind {
+    /// Fixed length of `interval_seconds`.
+    Seconds,
+    /// Calendar months, due at 00:00 UTC on the anchor day (1–31). The day is
+    /// clamped to the last day of shorter months.
+    Months(u32),
+    /// Calendar years, due at 00:00 UTC on the anchor month (1–12) and day.
+    Years(u32, u32),
 }
 
 // Event types
+//
+// Subscription events are published with topics
+// `(name, subscription_id, merchant, subscriber)` so indexers can filter by
+// either party. See `docs/events.md`.
+
+/// Schema version carried in every event payload. Bumped when a payload's
+/// fields change so indexers can decode old and new events side by side.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// `created`: emitted by `create_subscription`.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct SubscriptionCreatedEvent {
+    pub version: u32,
     pub subscription_id: u32,
     pub subscriber: Address,
     pub merchant: Address,
     pub amount: i128,
     pub interval_seconds: u64,
+    pub usage_enabled: bool,
+    pub interval_kind: IntervalKind,
+    /// Plan the subscription joined, if created with `subscribe_to_plan`.
+    pub plan_id: Option<u32>,
 }
 
+/// `created_funded`: emitted once by `create_subscription_with_deposit` for the
+/// whole create, deposit and optional upfront charge.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SubscriptionCreatedAndFundedEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    pub subscriber: Address,
+    pub merchant: Address,
+    pub amount: i128,
+    pub interval_seconds: u64,
+    /// Tokens pulled from the subscriber into the vault.
+    pub initial_deposit: i128,
+    /// Amount charged for the first period (`0` unless `pay_upfront`).
+    pub upfront_charge: i128,
+    /// Prepaid balance left after the deposit and upfront charge.
+    pub prepaid_balance: i128,
+}
+
+/// `deposited`: emitted by `deposit_funds`.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct FundsDepositedEvent {
+    pub version: u32,
     pub subscription_id: u32,
     pub subscriber: Address,
     pub amount: i128,
+    /// Prepaid balance after the deposit.
+    pub prepaid_balance: i128,
 }
 
+/// `charged`: emitted for each successful interval charge.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct SubscriptionChargedEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    pub merchant: Address,
+    pub amount: i128,
+    /// Prepaid balance after the charge.
+    pub prepaid_balance: i128,
+    /// Ledger timestamp of the charge.
+    pub timestamp: u64,
+    /// Billing periods settled by this charge (`amount` covers all of them).
+    pub periods: u32,
+}
+
+/// `periods_skipped`: emitted before `charged` when a late charge under
+/// `CatchUpMode::Skip` forgives missed periods and collects only the current one.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct PeriodsSkippedEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    /// Missed billing intervals forgiven (the current period is not counted).
+    pub periods: u32,
+    /// New `last_payment_timestamp`.
+    pub next_anchor: u64,
+}
+
+/// `trial_started`: emitted after `created` for a subscription with a trial.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct TrialStartedEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    pub trial_ends_at: u64,
+}
+
+/// `trial_converted`: emitted after the first paid interval charge following a trial.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct TrialConvertedEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    pub trial_ends_at: u64,
+    /// Amount of the first paid charge.
+    pub amount: i128,
+}
+
+/// `plan_changed` / `plan_change_scheduled`: emitted by `change_subscription_plan`,
+/// and `plan_changed` again when a scheduled change is applied.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct PlanChangedEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    pub amount: i128,
+    pub interval_seconds: u64,
+    pub plan_id: Option<u32>,
+    /// Prorated credit for the old terms (`0` unless applied immediately).
+    pub credit: i128,
+    /// Prorated charge for the new terms (`0` unless applied immediately).
+    pub charge: i128,
+    pub effective_at: u64,
+}
+
+/// `usage_charged`: emitted for each successful usage charge.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct UsageChargedEvent {
+    pub version: u32,
     pub subscription_id: u32,
     pub merchant: Address,
     pub amount: i128,
+    /// Prepaid balance after the charge.
+    pub prepaid_balance: i128,
+}
+
+/// `insufficient_balance`: emitted when a charge moves a subscription to
+/// `InsufficientBalance`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct InsufficientBalanceEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    /// Amount the failed interval charge needed (`0` when a usage charge drained the balance).
+    pub amount_due: i128,
+    pub prepaid_balance: i128,
 }
 
+/// `reactivated`: emitted when a deposit moves a subscription from
+/// `InsufficientBalance` back to `Active`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SubscriptionReactivatedEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    /// Amount collected for the period that failed (`0` unless requested and due).
+    pub collected: i128,
+    /// Prepaid balance after reactivation and any collection.
+    pub prepaid_balance: i128,
+}
+
+/// `cancelled`: emitted by `cancel_subscription`.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct SubscriptionCancelledEvent {
+    pub version: u32,
     pub subscription_id: u32,
     pub authorizer: Address,
+    /// Prepaid balance the subscriber can withdraw.
     pub refund_amount: i128,
 }
 
+/// `paused`: emitted by `pause_subscription`.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct SubscriptionPausedEvent {
+    pub version: u32,
     pub subscription_id: u32,
     pub authorizer: Address,
 }
 
+/// `resumed`: emitted by `resume_subscription`.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct SubscriptionResumedEvent {
+    pub version: u32,
     pub subscription_id: u32,
     pub authorizer: Address,
 }
 
+/// `subscriber_withdrawn`: emitted by `withdraw_subscriber_funds`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SubscriberWithdrawalEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    pub subscriber: Address,
+    pub amount: i128,
+}
+
+/// `withdrawn`: emitted by `withdraw_merchant_funds`. Not tied to one
+/// subscription, so its topics are `(name, merchant)`.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct MerchantWithdrawalEvent {
+    pub version: u32,
     pub merchant: Address,
     pub amount: i128,
+    /// Earnings left in the merchant's ledger after the withdrawal.
+    pub remaining_balance: i128,
+}
+
+/// Vault-wide bounds every new subscription must satisfy.
+///
+/// Stored under [`DataKey::SubscriptionLimits`]; until set, the vault uses
+/// [`SubscriptionLimits::default_limits`]. A zero `max_*` field means no upper bound.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubscriptionLimits {
+    /// Shortest allowed `interval_seconds` (an interval of `0` is always rejected).
+    pub min_interval_seconds: u64,
+    /// Longest allowed `interval_seconds`; `0` means no maximum.
+    pub max_interval_seconds: u64,
+    /// Largest allowed per-interval `amount`; `0` means no maximum.
+    pub max_amount: i128,
+}
+
+impl SubscriptionLimits {
+    /// Limits used until a config manager stores different ones.
+    pub const fn default_limits() -> Self {
+        SubscriptionLimits {
+            min_interval_seconds: 1,
+            max_interval_seconds: 0,
+            max_amount: 0,
+        }
+    }
+}
+
+/// How long a `propose_admin` proposal stays acceptable (7 days).
+pub const ADMIN_PROPOSAL_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// An admin handover proposed by the current admin and awaiting acceptance.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingAdmin {
+    /// Admin that made the proposal.
+    pub proposer: Address,
+    /// Address that must call `accept_admin`.
+    pub proposed_admin: Address,
+    /// Ledger timestamp after which the proposal can no longer be accepted.
+    pub expires_at: u64,
+}
+
+/// How long a council proposal can be approved and executed (7 days).
+pub const GOV_PROPOSAL_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// M-of-N signer set that must approve sensitive admin actions.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Council {
+    pub signers: Vec<Address>,
+    /// Number of distinct signer approvals required to execute a proposal.
+    pub threshold: u32,
+}
+
+/// A sensitive action that, once a [`Council`] exists, can only run through
+/// a governance proposal.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GovernanceAction {
+    /// Replace the admin (as `rotate_admin`).
+    RotateAdmin(Address),
+    /// Recover surplus funds: `(recipient, amount, reason)` (as `recover_stranded_funds`).
+    RecoverStrandedFunds(Address, i128, RecoveryReason),
+    /// Update the minimum top-up (as `set_min_topup`).
+    SetMinTopup(i128),
+    /// Upgrade the contract to an already-uploaded Wasm hash.
+    Upgrade(BytesN<32>),
+    /// Replace the council: `(signers, threshold)`.
+    SetCouncil(Vec<Address>, u32),
+}
+
+/// A governance proposal and its approval state.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GovProposal {
+    pub id: u32,
+    pub proposer: Address,
+    pub action: GovernanceAction,
+    /// Signers that approved, in approval order. The proposer approves on creation.
+    pub approvals: Vec<Address>,
+    pub created_at: u64,
+    /// Ledger timestamp after which the proposal can no longer be approved or executed.
+    pub expires_at: u64,
+    pub executed: bool,
+}
+
+/// Current schema version of [`MerchantConfig`].
+pub const MERCHANT_CONFIG_VERSION: u32 = 1;
+
+/// Per-merchant subscription defaults and constraints.
+///
+/// Stored under [`DataKey::MerchantConfig`]; merchants without a record get
+/// [`MerchantConfig::default_config`].
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MerchantConfig {
+    /// Schema version for forward-compatible config upgrades.
+    pub version: u32,
+    /// Minimum subscription `amount` for this merchant; `0` means no minimum.
+    pub min_subscription_amount: i128,
+    /// Interval applied when `create_subscription` is called with
+    /// `interval_seconds = 0`; `0` means no default.
+    pub default_interval_seconds: u64,
+}
+
+impl MerchantConfig {
+    /// Config used for merchants that never stored one.
+    pub const fn default_config() -> Self {
+        MerchantConfig {
+            version: MERCHANT_CONFIG_VERSION,
+            min_subscription_amount: 0,
+            default_interval_seconds: 0,
+        }
+    }
+
+    /// Fails with `InvalidAmount` when `amount` is below `min_subscription_amount`.
+    pub fn check_amount(&self, amount: i128) -> Result<(), Error> {
+        if self.min_subscription_amount > 0 && amount < self.min_subscription_amount {
+            return Err(Error::InvalidAmount);
+        }
+        Ok(())
+    }
+}
+
+/// How an interval charge settles periods missed while the billing engine was down.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CatchUpMode {
+    /// Collect one period and restart the schedule at the charge time (default).
+    LatestOnly = 0,
+    /// Collect every missed period, up to `max_periods`, keeping the original schedule.
+    CollectAll = 1,
+    /// Forgive missed periods and collect only the current one, keeping the original schedule.
+    Skip = 2,
+}
+
+/// Per-merchant catch-up policy. See `docs/catch_up_billing.md`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CatchUpPolicy {
+    pub mode: CatchUpMode,
+    /// Most periods one `CollectAll` charge may collect; older arrears are forgiven.
+    /// Ignored by the other modes.
+    pub max_periods: u32,
+}
+
+/// Read-only arrears view returned by `get_outstanding_periods`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutstandingPeriods {
+    /// Whole billing intervals elapsed since `last_payment_timestamp`.
+    pub periods: u32,
+    /// Periods the next interval charge collects under the merchant's policy.
+    pub collectible: u32,
+    /// `collectible * amount`: prepaid balance the next charge needs.
+    pub amount_due: i128,
+    /// `last_payment_timestamp` after the next charge.
+    pub next_anchor: u64,
+}
+
+/// What happens to a past-due subscription when dunning runs out.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DunningFinalAction {
+    /// Move to `Suspended`; a covering deposit reactivates it.
+    Suspend = 0,
+    /// Move to the terminal `Cancelled` state.
+    Cancel = 1,
+}
+
+/// Per-merchant handling of failed interval charges. See `docs/dunning.md`.
+///
+/// Merchants without a policy keep the default behaviour: a failed charge moves
+/// the subscription straight to `InsufficientBalance`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DunningPolicy {
+    /// How long after the first failure the subscription stays entitled (`PastDue`).
+    pub grace_period_seconds: u64,
+    /// Retry attempts after the first failure; the final action applies when the last one fails.
+    pub max_retries: u32,
+    /// Minimum time between two charge attempts while `PastDue`; earlier retries
+    /// fail with `RetryTooSoon` and do not use up the retry budget.
+    pub retry_interval_seconds: u64,
+    /// Applied when the last retry fails, or when a retry fails after the grace window.
+    pub final_action: DunningFinalAction,
 }
 
-/// Emitted when a merchant-initiated one-off charge is applied to a subscription.
+/// Dunning progress of one subscription, stored under [`DataKey::Dunning`].
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DunningRecord {
+    /// Ledger timestamp of the first failed charge.
+    pub first_failed_at: u64,
+    /// Failed charge attempts so far, including the first one.
+    pub attempts: u32,
+    /// Ledger timestamp of the latest failed charge.
+    pub last_attempt_at: u64,
+}
+
+/// Read-only dunning view returned by `get_dunning_state`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DunningState {
+    pub status: SubscriptionStatus,
+    /// Whether the subscriber is currently entitled to the service
+    /// (`Active`, or `PastDue` within the grace window).
+    pub entitled: bool,
+    /// Failed charge attempts in the current dunning cycle (`0` if none).
+    pub attempts: u32,
+    /// Timestamp of the first failure in the current cycle (`0` if none).
+    pub first_failed_at: u64,
+    /// End of the grace window (`0` if not past due).
+    pub grace_ends_at: u64,
+    /// Retries left before the final action (`0` if not past due).
+    pub retries_remaining: u32,
+}
+
+/// `past_due` / `suspended`: emitted when dunning moves a subscription.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct DunningEvent {
+    pub version: u32,
+    pub subscription_id: u32,
+    /// Failed charge attempts so far.
+    pub attempts: u32,
+    pub grace_ends_at: u64,
+    pub prepaid_balance: i128,
+}
+
+/// `oneoff_ch`: emitted when a merchant-initiated one-off charge is applied to a subscription.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct OneOffChargedEvent {
+    pub version: u32,
     pub subscription_id: u32,
     pub merchant: Address,
     pub amount: i128,
@@ -216,6 +886,41 @@ pub struct RecoveryEvent {
     pub timestamp: u64,
 }
 
+/// Snapshot comparing the vault's token reserves with what it owes.
+/// Returned by [`crate::SubscriptionVault::get_solvency_report`] and `reconcile`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SolvencyReport {
+    /// Token balance held by the vault contract.
+    pub token_balance: i128,
+    /// Sum of all subscriber `prepaid_balance`s.
+    pub total_prepaid: i128,
+    /// Sum of all merchant earnings not yet withdrawn.
+    pub total_merchant_balance: i128,
+    /// `total_prepaid + total_merchant_balance`.
+    pub total_liabilities: i128,
+    /// `token_balance - total_liabilities`; negative when under-collateralized.
+    pub surplus: i128,
+    /// True if `token_balance >= total_liabilities`.
+    pub is_solvent: bool,
+    /// True if the vault is currently in protective mode.
+    pub protective_mode: bool,
+}
+
+/// Billing period containing the current ledger time, returned by `get_current_period`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BillingPeriod {
+    /// Period number counted from `billing_anchor` (period 0 starts at the anchor).
+    pub number: u64,
+    /// Start of the period (inclusive).
+    pub start: u64,
+    /// Start of the next period (exclusive end).
+    pub end: u64,
+    /// Whether an interval charge has been recorded for this period.
+    pub charged: bool,
+}
+
 /// Result of computing next charge information for a subscription.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -224,4 +929,8 @@ pub struct NextChargeInfo {
     pub next_charge_timestamp: u64,
     /// Whether a charge is actually expected based on the subscription status.
     pub is_charge_expected: bool,
+    /// Whether the trial is still running (ledger time before `trial_ends_at`).
+    pub in_trial: bool,
+    /// End of the trial (`0` if the subscription has none).
+    pub trial_ends_at: u64,
 }